@@ -1,3 +1,4 @@
+use alertaemcena::agenda_cultural::api::AgendaCulturalAPI;
 use alertaemcena::config::env_loader::load_config;
 use alertaemcena::discord::api::DiscordAPI;
 use alertaemcena::tracing::setup_tracing;
@@ -32,7 +33,9 @@ async fn main() {
         serde_json::from_str(&raw).expect("Failed to parse input JSON");
 
     let config = load_config();
-    let discord = DiscordAPI::default().await;
+    let discord = DiscordAPI::default()
+        .await
+        .with_tag_labels(config.tag_labels.clone());
     let total = records.len();
 
     async {
@@ -55,11 +58,12 @@ async fn main() {
 
             match discord
                 .send_backfill_review(
+                    &AgendaCulturalAPI::default(),
                     user_id,
                     &record.url,
                     vote_emoji,
                     comment,
-                    &config.venue_ticket_shop_url,
+                    &config.venue_ticket_shops,
                     &config.ticket_shop_icon_url,
                 )
                 .await
@@ -1,56 +1,106 @@
-use super::{dto::EventResponse, model::Event};
-use crate::agenda_cultural::model::{Category, EventDetails, Schedule};
+use super::{audience::Audience, dto::EventResponse, model::Event};
+use crate::agenda_cultural::markdown::html_to_markdown;
+use crate::agenda_cultural::model::{Category, Coordinates, EventDetails, Price, Schedule, Venue};
+use crate::agenda_cultural::page_cache::{CachedPage, PageCache};
+use crate::agenda_cultural::source::EventSource;
+use crate::metrics::{
+    record_description_fallback, record_event_page_fetch, record_page_cache_lookup,
+    CacheLookupResult, MetricResult,
+};
 use chrono::{Datelike, NaiveDate, TimeDelta, Utc};
-use futures::TryFutureExt;
+use futures::{stream, Stream, StreamExt, TryFutureExt, TryStreamExt};
 use lazy_static::lazy_static;
-use reqwest::{Client, Response};
+use regex::Regex;
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{Client, Response, StatusCode, Url};
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
-use reqwest_retry::policies::ExponentialBackoff;
+use reqwest_retry::policies::{ExponentialBackoff, ExponentialBackoffTimed};
 use reqwest_retry::Jitter::Bounded;
-use reqwest_retry::RetryTransientMiddleware;
+use reqwest_retry::{RetryPolicy, RetryTransientMiddleware};
 use scraper::{Html, Selector};
 use std::cmp::Ordering;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::ops::Add;
+use std::sync::Mutex;
 use std::time::Duration;
 use strum::Display;
+use tokio::time::Instant;
 use tracing::{debug, error, info, instrument, trace, warn};
-use voca_rs::strip::strip_tags;
 
-const AGENDA_EVENTS_URL: &str = "https://www.agendalx.pt/wp-json/agendalx/v1/events";
+const AGENDALX_URL: &str = "https://www.agendalx.pt";
+const EVENTS_PATH: &str = "/wp-json/agendalx/v1/events";
 const EVENT_TYPE: &str = "event";
+/// WordPress doesn't allow more than 100 per page
+const PAGE_SIZE: usize = 100;
+const TOTAL_PAGES_HEADER: &str = "X-WP-TotalPages";
 const DATE_PRINT_FORMAT: &str = "%Y-%m-%d";
+/// Shops whose links are for tickets whatever their text
+const TICKET_SHOP_HOSTS: [&str; 4] = [
+    "bol.pt",
+    "ticketline.sapo.pt",
+    "blueticket.meo.pt",
+    "imbilhetes.com",
+];
 
 lazy_static! {
-    static ref REST_CLIENT: ClientWithMiddleware = ClientBuilder::new(Client::new())
-        .with(RetryTransientMiddleware::new_with_policy(
-            ExponentialBackoff::builder()
-                .jitter(Bounded)
-                .retry_bounds(Duration::from_millis(50), Duration::from_millis(1000))
-                .build_with_total_retry_duration_and_max_retries(Duration::from_secs(30))
-        ))
-        .build();
     static ref EVENT_DESCRIPTION_SELECTOR: Selector =
         Selector::parse(".entry-container > :not(.event__extra-info):not(.section-title):not(.section-title--venue):not(.venue):not(.post__share)").unwrap();
     static ref OG_TITLE_SELECTOR: Selector = Selector::parse(r#"meta[property="og:title"]"#).unwrap();
     static ref OG_IMAGE_SELECTOR: Selector = Selector::parse(r#"meta[property="og:image"]"#).unwrap();
     static ref VENUE_NAME_SELECTOR: Selector = Selector::parse(".venue__name").unwrap();
+    static ref VENUE_LINK_SELECTOR: Selector = Selector::parse(".venue-card__header > a").unwrap();
+    static ref VENUE_ADDRESS_SELECTOR: Selector = Selector::parse(".venue .address").unwrap();
+    static ref VENUE_MAP_SELECTOR: Selector =
+        Selector::parse(r#".venue a[href*="maps/search"]"#).unwrap();
     static ref EVENT_DATES_SELECTOR: Selector = Selector::parse(".signpost__date").unwrap();
+    static ref EVENT_LINK_SELECTOR: Selector = Selector::parse(".entry-container a[href]").unwrap();
+    static ref TICKET_LINK_TEXT: Regex = Regex::new(r"(?i)\b(bilhetes?|bilheteira|comprar)\b").unwrap();
 }
 
-pub struct AgendaCulturalAPI;
+pub struct AgendaCulturalAPI {
+    base_url: String,
+    client: ClientWithMiddleware,
+    fetch_limits: PageFetchLimits,
+    page_cache: Option<PageCache>,
+    /// When each host can be requested again
+    next_host_requests: Mutex<HashMap<String, Instant>>,
+}
 
-impl AgendaCulturalAPI {
-    /**
-    Returns events in ascending order
-    * amount_per_page: if not specified, will retrieve everything
-    */
-    #[instrument]
-    pub async fn get_events_by_month(
+/// Limits on how event pages are fetched, to go easy on agendalx
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageFetchLimits {
+    /// How many event pages are fetched at the same time
+    pub max_concurrent_fetches: usize,
+    /// Minimum time between starting two requests to the same host
+    pub host_delay: Duration,
+}
+
+impl Default for PageFetchLimits {
+    fn default() -> Self {
+        Self {
+            max_concurrent_fetches: 4,
+            host_delay: Duration::from_millis(250),
+        }
+    }
+}
+
+impl Default for AgendaCulturalAPI {
+    fn default() -> Self {
+        Self::new(
+            AGENDALX_URL,
+            Self::build_client(Self::default_retry_policy()),
+        )
+    }
+}
+
+impl EventSource for AgendaCulturalAPI {
+    #[instrument(skip(self))]
+    async fn get_events_by_month(
+        &self,
         category: &Category,
-        amount_per_page: Option<i32>,
+        event_limit: Option<i32>,
     ) -> Result<BTreeMap<NaiveDate, Vec<Event>>, APIError> {
-        match amount_per_page {
+        match event_limit {
             None => {
                 info!("Getting all events");
             }
@@ -59,84 +109,175 @@ impl AgendaCulturalAPI {
             }
         }
 
-        let category: &'static str = category.into();
-        let parsed_response = Self::get_events_by_category(amount_per_page, category)
-            .await
-            .inspect_err(|err| {
-                error!("Failed to get events by category '{}': {}", category, err)
-            })?;
+        let mut events_by_date: BTreeMap<NaiveDate, Vec<Event>> = BTreeMap::new();
+        let mut fetched_count = 0;
+        let mut pages = std::pin::pin!(self.get_events_by_category(event_limit, &category.slug));
 
-        info!("Fetched {} events", parsed_response.len());
+        while let Some(page) = pages.try_next().await.inspect_err(|err| {
+            error!(
+                "Failed to get events by category '{}': {}",
+                category.slug, err
+            )
+        })? {
+            fetched_count += page.len();
+            self.parse_events_by_date(page, &mut events_by_date).await;
+        }
 
-        let events = Self::parse_events_by_date(parsed_response).await;
+        info!("Fetched {} events", fetched_count);
 
-        Ok(events)
+        Self::fill_incoming_months(&mut events_by_date);
+
+        Ok(events_by_date)
     }
 
-    async fn parse_events_by_date(response: Vec<EventResponse>) -> BTreeMap<NaiveDate, Vec<Event>> {
-        let mut events_by_date: BTreeMap<NaiveDate, Vec<Event>> = BTreeMap::new();
+    /// Scrapes title, venue, dates, image and ticket link directly off the event page, for events
+    /// no longer present in the upcoming-events API (e.g. when backfilling old reviews).
+    async fn scrape_event(&self, link: &str) -> Option<Event> {
+        let body = self.fetch_page(link).await?;
 
-        Self::fill_incoming_months(&response, &mut events_by_date);
+        let description = Self::extract_full_description(&body).unwrap_or_else(|| {
+            warn!("Unable to extract description for '{}'", link);
+            String::new()
+        });
 
-        debug!(
-            "Found these months of events: {:?}",
-            events_by_date.keys().cloned().collect::<Vec<NaiveDate>>()
+        let document = Html::parse_document(&body);
+
+        let title =
+            Self::extract_meta_content(&document, &OG_TITLE_SELECTOR).unwrap_or_else(|| {
+                warn!("Unable to extract title for '{}'", link);
+                String::new()
+            });
+        let image_url =
+            Self::extract_meta_content(&document, &OG_IMAGE_SELECTOR).unwrap_or_else(|| {
+                warn!("Unable to extract image for '{}'", link);
+                String::new()
+            });
+        let venue = Self::extract_venue(&document).unwrap_or_else(|| {
+            warn!("Unable to extract venue for '{}'", link);
+            Venue::default()
+        });
+        let dates = Self::extract_text(&document, &EVENT_DATES_SELECTOR).unwrap_or_else(|| {
+            warn!("Unable to extract dates for '{}'", link);
+            String::new()
+        });
+
+        let mut event = Event::new(
+            title,
+            EventDetails::new(String::new(), description, image_url),
+            link.to_string(),
+            Schedule::new(dates, String::new(), Vec::new(), None, None),
+            venue,
+            Vec::new(),
+            Price::Unknown,
+            Audience::default(),
         );
+        event.ticket_url = Self::extract_ticket_url(&document);
 
-        for response in response
-            .iter()
-            .filter(|event| event.start_date != NaiveDate::MIN)
-        {
-            let model = Self::convert_response_to_model(response).await;
-            let date = response.start_date.with_day(1).unwrap();
+        Some(event)
+    }
+}
 
-            if let Some(events) = events_by_date.get_mut(&date) {
-                events.push(model);
-            } else {
-                warn!(
-                    event = model.link,
-                    date = date.format(DATE_PRINT_FORMAT).to_string(),
-                    "The date of event '{}' was not in the list! (when it should)",
-                    model.title
-                );
-                events_by_date.insert(date, Vec::from([model]));
-            }
+impl AgendaCulturalAPI {
+    /// * base_url: where agendalx is served from (e.g. a local stub in tests)
+    pub fn new(base_url: &str, client: ClientWithMiddleware) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client,
+            fetch_limits: PageFetchLimits::default(),
+            page_cache: None,
+            next_host_requests: Mutex::new(HashMap::new()),
         }
+    }
 
-        info!("Parsed events");
+    pub fn with_fetch_limits(mut self, fetch_limits: PageFetchLimits) -> Self {
+        self.fetch_limits = PageFetchLimits {
+            max_concurrent_fetches: fetch_limits.max_concurrent_fetches.max(1),
+            ..fetch_limits
+        };
+        self
+    }
 
-        events_by_date
+    pub fn with_page_cache(mut self, page_cache: PageCache) -> Self {
+        self.page_cache = Some(page_cache);
+        self
     }
 
-    fn fill_incoming_months(
-        response: &[EventResponse],
+    pub fn build_client(
+        retry_policy: impl RetryPolicy + Send + Sync + 'static,
+    ) -> ClientWithMiddleware {
+        ClientBuilder::new(Client::new())
+            .with(RetryTransientMiddleware::new_with_policy(retry_policy))
+            .build()
+    }
+
+    pub fn default_retry_policy() -> ExponentialBackoffTimed {
+        ExponentialBackoff::builder()
+            .jitter(Bounded)
+            .retry_bounds(Duration::from_millis(50), Duration::from_millis(1000))
+            .build_with_total_retry_duration_and_max_retries(Duration::from_secs(30))
+    }
+
+    /// Adds a page of events to the months they start in
+    async fn parse_events_by_date(
+        &self,
+        response: Vec<EventResponse>,
         events_by_date: &mut BTreeMap<NaiveDate, Vec<Event>>,
     ) {
-        let max_month = response
-            .iter()
-            .max_by(|first, second| first.start_date.cmp(&second.start_date));
-
-        if let Some(last_event) = max_month {
-            let max_date = NaiveDate::from_ymd_opt(
-                last_event.start_date.year(),
-                last_event.start_date.month(),
-                1,
-            )
-            .unwrap();
+        let response: Vec<EventResponse> = response
+            .into_iter()
+            .filter(|event| event.start_date != NaiveDate::MIN)
+            .collect();
+
+        let models: Vec<(NaiveDate, Event)> = stream::iter(response)
+            .map(|response| async move {
+                let model = self.convert_response_to_model(&response).await;
+
+                (response.start_date, model)
+            })
+            .buffered(self.fetch_limits.max_concurrent_fetches)
+            .collect()
+            .await;
+
+        for (start_date, model) in models {
+            let date = start_date.with_day(1).unwrap();
+
+            trace!(
+                event = model.link,
+                date = date.format(DATE_PRINT_FORMAT).to_string(),
+                "Adding event '{}'",
+                model.title
+            );
+            events_by_date.entry(date).or_default().push(model);
+        }
+
+        info!("Parsed events");
+    }
+
+    /// Makes sure every month from the current one up to the last event's is present,
+    /// even when there are no events starting in it
+    fn fill_incoming_months(events_by_date: &mut BTreeMap<NaiveDate, Vec<Event>>) {
+        if let Some(max_date) = events_by_date.keys().last().cloned() {
             let mut min_date = Utc::now().date_naive().with_day(1).unwrap();
 
             info!("Filling up until {:?}", max_date);
             while min_date.cmp(&max_date) != Ordering::Greater {
-                events_by_date.insert(min_date, Vec::from([]));
+                events_by_date.entry(min_date).or_default();
                 trace!("Going for {:?}", min_date);
                 min_date = min_date.add(TimeDelta::days(31)).with_day(1).unwrap();
             }
         }
+
+        debug!(
+            "Found these months of events: {:?}",
+            events_by_date.keys().cloned().collect::<Vec<NaiveDate>>()
+        );
     }
 
-    async fn convert_response_to_model(response: &EventResponse) -> Event {
-        let description = Self::get_full_description(&response.link)
-            .await
+    async fn convert_response_to_model(&self, response: &EventResponse) -> Event {
+        let page = self.fetch_page(&response.link).await;
+        let description = page
+            .as_deref()
+            .and_then(Self::extract_full_description)
             .unwrap_or_else(|| {
                 let preview_description = Self::clean_description(&response.description.concat());
 
@@ -144,23 +285,86 @@ impl AgendaCulturalAPI {
                     "Unable to get full description. Using only preview description ({})",
                     preview_description
                 );
+                record_description_fallback();
 
                 preview_description
             });
 
-        response.to_model(description).await
+        let mut event = response.to_model(description).await;
+
+        if let Some(page) = page {
+            let document = Html::parse_document(&page);
+
+            Self::complete_venue(&mut event.venue, &document);
+            event.ticket_url = Self::extract_ticket_url(&document);
+        }
+
+        event
     }
 
-    #[instrument]
-    async fn get_events_by_category(
-        amount_per_page: Option<i32>,
+    /// Pages through the events of a category, stopping once `event_limit` events were fetched
+    /// or after the last page announced by WordPress.
+    /// Pages are always of the same size, since WordPress offsets them by it
+    fn get_events_by_category<'a>(
+        &'a self,
+        event_limit: Option<i32>,
+        category: &'a str,
+    ) -> impl Stream<Item = Result<Vec<EventResponse>, APIError>> + Send + 'a {
+        let event_limit = event_limit.map(|limit| limit.max(0) as usize);
+
+        stream::try_unfold(Some((1, 0)), move |next_page| async move {
+            let Some((page, fetched_count)) = next_page else {
+                return Ok(None);
+            };
+            let remaining_count =
+                event_limit.map_or(usize::MAX, |limit| limit.saturating_sub(fetched_count));
+
+            if remaining_count == 0 {
+                return Ok(None);
+            }
+
+            let (mut events, total_pages) = self.get_events_page(category, page, PAGE_SIZE).await?;
+            let has_more_pages = match total_pages {
+                Some(total_pages) => page < total_pages,
+                None => events.len() == PAGE_SIZE,
+            };
+
+            events.truncate(remaining_count);
+
+            let fetched_count = fetched_count + events.len();
+
+            debug!(
+                "Fetched page {} of {:?} with {} events",
+                page,
+                total_pages,
+                events.len()
+            );
+
+            let next_page = match has_more_pages && !events.is_empty() {
+                true => Some((page + 1, fetched_count)),
+                false => None,
+            };
+
+            Ok(Some((events, next_page)))
+        })
+    }
+
+    /// Returns the page's events and the total amount of pages, when the server tells it
+    #[instrument(skip(self))]
+    async fn get_events_page(
+        &self,
         category: &str,
-    ) -> Result<Vec<EventResponse>, APIError> {
-        let json_response = REST_CLIENT
+        page: usize,
+        per_page: usize,
+    ) -> Result<(Vec<EventResponse>, Option<usize>), APIError> {
+        let response = self
+            .client
             .get(format!(
-                "{}?per_page={}&categories={}&type={}",
-                AGENDA_EVENTS_URL,
-                amount_per_page.unwrap_or(50000),
+                "{}{}?per_page={}&page={}&categories={}&type={}",
+                self.base_url,
+                EVENTS_PATH,
+                per_page,
+                page,
                 category.to_lowercase(),
                 EVENT_TYPE
             ))
@@ -168,34 +372,138 @@ impl AgendaCulturalAPI {
             .map_err(APIError::ErrorSending)
             .await?
             .error_for_status()
-            .map_err(APIError::ResponseError)?
-            .text()
-            .map_err(APIError::InvalidResponse)
-            .await?;
+            .map_err(APIError::ResponseError)?;
+
+        let total_pages = response
+            .headers()
+            .get(TOTAL_PAGES_HEADER)
+            .and_then(|total_pages| total_pages.to_str().ok())
+            .and_then(|total_pages| total_pages.trim().parse().ok());
+
+        let json_response = response.text().map_err(APIError::InvalidResponse).await?;
+
+        let events = serde_json::from_str::<Vec<EventResponse>>(&json_response)
+            .map_err(APIError::ParseError)?;
 
-        serde_json::from_str::<Vec<EventResponse>>(&json_response).map_err(APIError::ParseError)
+        Ok((events, total_pages))
     }
 
-    async fn get_full_description(link: &str) -> Option<String> {
-        let full_page: Result<Response, _> = REST_CLIENT.get(link).send().await;
+    /// Gets an event page's HTML, waiting for the host's politeness delay first
+    /// Pages still fresh in the cache are used as is, while stale ones are revalidated with
+    /// their ETag or Last-Modified date.
+    async fn fetch_page(&self, link: &str) -> Option<String> {
+        let cached_page = match &self.page_cache {
+            Some(page_cache) => page_cache.get(link).await,
+            None => None,
+        };
+
+        if let (Some(page_cache), Some(cached_page)) = (&self.page_cache, &cached_page) {
+            if page_cache.is_fresh(cached_page) {
+                trace!("Using cached page for '{}'", link);
+                record_page_cache_lookup(CacheLookupResult::Hit);
+                return Some(cached_page.body.clone());
+            }
+        }
 
-        match full_page {
-            Ok(full_page) => {
-                let description = full_page
-                    .text()
-                    .await
-                    .inspect_err(|err| warn!("Failed to get full page text: {}", err));
+        self.wait_for_host(link).await;
 
-                if description.is_err() {
-                    return None;
-                }
+        let mut request = self.client.get(link);
 
-                Self::extract_full_description(&description.unwrap())
+        if let Some(cached_page) = &cached_page {
+            if let Some(etag) = &cached_page.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &cached_page.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
             }
+        }
+
+        let full_page: Result<Response, _> =
+            request.send().await.and_then(|response| {
+                match response.status() == StatusCode::NOT_MODIFIED {
+                    true => Ok(response),
+                    false => response.error_for_status().map_err(Into::into),
+                }
+            });
+
+        let full_page = match full_page {
+            Ok(full_page) => full_page,
             Err(err) => {
                 warn!("Failed to get full page: {:?}", err);
-                None
+                record_event_page_fetch(MetricResult::Error);
+                return None;
             }
+        };
+
+        record_event_page_fetch(MetricResult::Ok);
+
+        if let (Some(page_cache), Some(cached_page), StatusCode::NOT_MODIFIED) =
+            (&self.page_cache, &cached_page, full_page.status())
+        {
+            debug!("Cached page for '{}' is still valid", link);
+            record_page_cache_lookup(CacheLookupResult::Revalidated);
+            page_cache
+                .put(&CachedPage::new(
+                    link,
+                    cached_page.body.clone(),
+                    cached_page.etag.clone(),
+                    cached_page.last_modified.clone(),
+                ))
+                .await;
+
+            return Some(cached_page.body.clone());
+        }
+
+        let header = |name| {
+            full_page
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let (etag, last_modified) = (header(ETAG), header(LAST_MODIFIED));
+
+        let body = full_page
+            .text()
+            .await
+            .inspect_err(|err| warn!("Failed to get full page text: {}", err))
+            .ok()?;
+
+        if let Some(page_cache) = &self.page_cache {
+            record_page_cache_lookup(CacheLookupResult::Miss);
+            page_cache
+                .put(&CachedPage::new(link, body.clone(), etag, last_modified))
+                .await;
+        }
+
+        Some(body)
+    }
+
+    /// Spaces out requests to the same host by the configured delay, even across concurrent fetches
+    async fn wait_for_host(&self, link: &str) {
+        let Some(host) = Url::parse(link)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+        else {
+            return;
+        };
+
+        let wait = {
+            let mut next_host_requests = self.next_host_requests.lock().unwrap();
+            let now = Instant::now();
+            let next_request = next_host_requests
+                .get(&host)
+                .cloned()
+                .unwrap_or(now)
+                .max(now);
+
+            next_host_requests.insert(host, next_request + self.fetch_limits.host_delay);
+            next_request - now
+        };
+
+        if !wait.is_zero() {
+            trace!("Waiting {:?} before requesting '{}'", wait, link);
+            tokio::time::sleep(wait).await;
         }
     }
 
@@ -204,7 +512,7 @@ impl AgendaCulturalAPI {
 
         let description_elements = page_html
             .select(&EVENT_DESCRIPTION_SELECTOR)
-            .map(|p| p.inner_html().to_string())
+            .map(|p| p.html())
             .collect::<Vec<String>>();
 
         if description_elements.is_empty() {
@@ -217,62 +525,80 @@ impl AgendaCulturalAPI {
     }
 
     fn clean_description(description: &str) -> String {
-        strip_tags(description)
-            .replace("&nbsp;", " ")
-            .trim()
-            .to_owned()
+        html_to_markdown(description)
     }
 
-    /// Scrapes title, venue, dates and image directly off the event page, for events
-    /// no longer present in the upcoming-events API (e.g. when backfilling old reviews).
-    pub async fn scrape_event(link: &str) -> Option<Event> {
-        let full_page: Result<Response, _> = REST_CLIENT.get(link).send().await;
-
-        let body = match full_page {
-            Ok(full_page) => full_page
-                .text()
-                .await
-                .inspect_err(|err| warn!("Failed to get event page text: {}", err))
-                .ok()?,
-            Err(err) => {
-                warn!("Failed to get event page: {:?}", err);
-                return None;
-            }
-        };
+    fn extract_venue(document: &Html) -> Option<Venue> {
+        let name = Self::extract_text(document, &VENUE_NAME_SELECTOR)?;
+        let slug = document
+            .select(&VENUE_LINK_SELECTOR)
+            .next()
+            .and_then(|link| link.value().attr("href"))
+            .and_then(|href| Url::parse(AGENDALX_URL).ok()?.join(href).ok())
+            .and_then(|url| {
+                url.query_pairs()
+                    .find(|(key, _)| key == "venues")
+                    .map(|(_, slug)| slug.to_string())
+            })
+            .unwrap_or_default();
+
+        let mut venue = Venue::new(None, &slug, &name);
+        Self::complete_venue(&mut venue, document);
+
+        Some(venue)
+    }
 
-        let description = Self::extract_full_description(&body).unwrap_or_else(|| {
-            warn!("Unable to extract description for '{}'", link);
-            String::new()
-        });
+    /// Fills in the address and coordinates, which the API doesn't list, from the event page
+    fn complete_venue(venue: &mut Venue, document: &Html) {
+        if venue.address.is_none() {
+            venue.address = Self::extract_text(document, &VENUE_ADDRESS_SELECTOR)
+                .filter(|address| !address.is_empty());
+        }
 
-        let document = Html::parse_document(&body);
+        if venue.coordinates.is_none() {
+            venue.coordinates = Self::extract_coordinates(document);
+        }
+    }
 
-        let title = Self::extract_meta_content(&document, &OG_TITLE_SELECTOR).unwrap_or_else(|| {
-            warn!("Unable to extract title for '{}'", link);
-            String::new()
-        });
-        let image_url = Self::extract_meta_content(&document, &OG_IMAGE_SELECTOR)
-            .unwrap_or_else(|| {
-                warn!("Unable to extract image for '{}'", link);
-                String::new()
-            });
-        let venue = Self::extract_text(&document, &VENUE_NAME_SELECTOR).unwrap_or_else(|| {
-            warn!("Unable to extract venue for '{}'", link);
-            String::new()
-        });
-        let dates = Self::extract_text(&document, &EVENT_DATES_SELECTOR).unwrap_or_else(|| {
-            warn!("Unable to extract dates for '{}'", link);
-            String::new()
-        });
+    /// From the "Obter direções" link (e.g. "maps/search/?api=1&query=38.695503,-9.208354")
+    fn extract_coordinates(document: &Html) -> Option<Coordinates> {
+        let href = document
+            .select(&VENUE_MAP_SELECTOR)
+            .next()?
+            .value()
+            .attr("href")?;
+        let url = Url::parse(href).ok()?;
+        let (_, query) = url.query_pairs().find(|(key, _)| key == "query")?;
+        let (latitude, longitude) = query.split_once(',')?;
+
+        Some(Coordinates {
+            latitude: latitude.trim().parse().ok()?,
+            longitude: longitude.trim().parse().ok()?,
+        })
+    }
 
-        Some(Event::new(
-            title,
-            EventDetails::new(String::new(), description, image_url),
-            link.to_string(),
-            Schedule::new(dates, String::new()),
-            venue,
-            Vec::new(),
-        ))
+    /// The first link in the event's content to a ticket shop or labelled as one (e.g. "Bilhetes").
+    /// Links back to agendalx are left out since they're never the shop
+    fn extract_ticket_url(document: &Html) -> Option<String> {
+        document.select(&EVENT_LINK_SELECTOR).find_map(|link| {
+            let url = Url::parse(link.value().attr("href")?.trim()).ok()?;
+            let host = url.host_str()?.trim_start_matches("www.");
+
+            if !matches!(url.scheme(), "http" | "https") || host.ends_with("agendalx.pt") {
+                return None;
+            }
+
+            let is_ticket_shop = TICKET_SHOP_HOSTS
+                .iter()
+                .any(|shop| host == *shop || host.ends_with(&format!(".{}", shop)));
+            let is_ticket_link = TICKET_LINK_TEXT.is_match(&link.text().collect::<String>())
+                || link
+                    .value()
+                    .attr("title")
+                    .is_some_and(|title| TICKET_LINK_TEXT.is_match(title));
+
+            (is_ticket_shop || is_ticket_link).then(|| url.to_string())
+        })
     }
 
     fn extract_meta_content(document: &Html, selector: &Selector) -> Option<String> {
@@ -284,13 +610,10 @@ impl AgendaCulturalAPI {
     }
 
     fn extract_text(document: &Html, selector: &Selector) -> Option<String> {
-        document.select(selector).next().map(|element| {
-            element
-                .text()
-                .collect::<String>()
-                .trim()
-                .to_string()
-        })
+        document
+            .select(selector)
+            .next()
+            .map(|element| element.text().collect::<String>().trim().to_string())
     }
 }
 
@@ -305,51 +628,75 @@ mod tests {
     async fn should_parse_event_by_date() {
         let february = NaiveDate::from_ymd_opt(2025, 2, 1).unwrap();
         let march = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
-        let events_per_month = AgendaCulturalAPI::parse_events_by_date(Vec::from([
-            EventResponse {
-                title: ResponseTitle {
-                    rendered: "Como sobreviver a um acontecimento".to_string(),
-                },
-                subtitle: SingleOrVec::Single("".to_string()),
-                description: vec![],
-                featured_media_large: "".to_string(),
-                link: "".to_string(),
-                string_dates: "".to_string(),
-                string_times: "".to_string(),
-                start_date: march,
-                venue: Default::default(),
-                tags: Default::default(),
-            },
-            EventResponse {
-                title: ResponseTitle {
-                    rendered: "Sonho de uma noite de verão".to_string(),
-                },
-                subtitle: SingleOrVec::Single("".to_string()),
-                description: vec![],
-                featured_media_large: "".to_string(),
-                link: "".to_string(),
-                string_dates: "".to_string(),
-                string_times: "".to_string(),
-                start_date: february,
-                venue: Default::default(),
-                tags: Default::default(),
-            },
-            EventResponse {
-                title: ResponseTitle {
-                    rendered: "Mães".to_string(),
-                },
-                subtitle: SingleOrVec::Single("".to_string()),
-                description: vec![],
-                featured_media_large: "".to_string(),
-                link: "".to_string(),
-                string_dates: "".to_string(),
-                string_times: "".to_string(),
-                start_date: march,
-                venue: Default::default(),
-                tags: Default::default(),
-            },
-        ]))
-        .await;
+        let mut events_per_month = BTreeMap::new();
+
+        AgendaCulturalAPI::default()
+            .parse_events_by_date(
+                Vec::from([
+                    EventResponse {
+                        title: ResponseTitle {
+                            rendered: "Como sobreviver a um acontecimento".to_string(),
+                        },
+                        subtitle: SingleOrVec::Single("".to_string()),
+                        description: vec![],
+                        featured_media_large: "".to_string(),
+                        link: "".to_string(),
+                        string_dates: "".to_string(),
+                        string_times: "".to_string(),
+                        start_date: march,
+                        last_date: None,
+                        occurrences: vec![],
+                        venue: Default::default(),
+                        tags: Default::default(),
+                        price_categories: vec![],
+                        price_value: "".to_string(),
+                        target_audience: vec![],
+                        accessibility: vec![],
+                    },
+                    EventResponse {
+                        title: ResponseTitle {
+                            rendered: "Sonho de uma noite de verão".to_string(),
+                        },
+                        subtitle: SingleOrVec::Single("".to_string()),
+                        description: vec![],
+                        featured_media_large: "".to_string(),
+                        link: "".to_string(),
+                        string_dates: "".to_string(),
+                        string_times: "".to_string(),
+                        start_date: february,
+                        last_date: None,
+                        occurrences: vec![],
+                        venue: Default::default(),
+                        tags: Default::default(),
+                        price_categories: vec![],
+                        price_value: "".to_string(),
+                        target_audience: vec![],
+                        accessibility: vec![],
+                    },
+                    EventResponse {
+                        title: ResponseTitle {
+                            rendered: "Mães".to_string(),
+                        },
+                        subtitle: SingleOrVec::Single("".to_string()),
+                        description: vec![],
+                        featured_media_large: "".to_string(),
+                        link: "".to_string(),
+                        string_dates: "".to_string(),
+                        string_times: "".to_string(),
+                        start_date: march,
+                        last_date: None,
+                        occurrences: vec![],
+                        venue: Default::default(),
+                        tags: Default::default(),
+                        price_categories: vec![],
+                        price_value: "".to_string(),
+                        target_audience: vec![],
+                        accessibility: vec![],
+                    },
+                ]),
+                &mut events_per_month,
+            )
+            .await;
 
         assert_eq!(events_per_month.len(), 2);
 
@@ -364,6 +711,88 @@ mod tests {
         assert_eq!(march_events[1].title, "Mães");
     }
 
+    #[test_log::test(tokio::test)]
+    async fn should_space_out_requests_to_the_same_host() {
+        let api = AgendaCulturalAPI::default().with_fetch_limits(PageFetchLimits {
+            max_concurrent_fetches: 2,
+            host_delay: Duration::from_millis(200),
+        });
+
+        api.wait_for_host("https://www.agendalx.pt/events/event/maes/")
+            .await;
+
+        let started_at = Instant::now();
+        api.wait_for_host("https://www.agendalx.pt/events/event/31-mulheres/")
+            .await;
+        assert!(started_at.elapsed() >= Duration::from_millis(150));
+
+        let started_at = Instant::now();
+        api.wait_for_host("https://www.example.com/events/event/maes/")
+            .await;
+        assert!(started_at.elapsed() < Duration::from_millis(150));
+    }
+
+    #[test_log::test]
+    fn should_extract_venue() {
+        let event_page =
+            read_to_string("res/tests/event_page.html").expect("Could not get test resource");
+
+        let venue = AgendaCulturalAPI::extract_venue(&Html::parse_document(&event_page));
+
+        assert_eq!(
+            venue,
+            Some(Venue {
+                id: None,
+                slug: "museu-colecao-berardo-arte-moderna-e-comtemporanea".to_string(),
+                name: "MAC/CCB".to_string(),
+                address: Some("Praça do Império (Centro Cultural de Belém)".to_string()),
+                coordinates: Some(Coordinates {
+                    latitude: 38.695503,
+                    longitude: -9.208354
+                }),
+            })
+        );
+    }
+
+    #[test_log::test]
+    fn should_extract_ticket_url() {
+        let document = Html::parse_document(
+            r#"<div class="entry-container">
+                <p><a href="https://www.agendalx.pt/?s=bilhetes">Bilhetes</a></p>
+                <p><a href="https://teatrodobairro.org">Teatro do Bairro</a></p>
+                <p><a href="https://teatrodobairro.org/galafoice">Comprar bilhetes</a></p>
+            </div>"#,
+        );
+
+        assert_eq!(
+            AgendaCulturalAPI::extract_ticket_url(&document).as_deref(),
+            Some("https://teatrodobairro.org/galafoice")
+        );
+    }
+
+    #[test_log::test]
+    fn should_extract_ticket_url_by_shop() {
+        let document = Html::parse_document(
+            r#"<div class="entry-container"><a href="https://ccb.bol.pt/Comprar/Bilhetes/1">aqui</a></div>"#,
+        );
+
+        assert_eq!(
+            AgendaCulturalAPI::extract_ticket_url(&document).as_deref(),
+            Some("https://ccb.bol.pt/Comprar/Bilhetes/1")
+        );
+    }
+
+    #[test_log::test]
+    fn when_event_page_has_no_ticket_link_should_not_extract_ticket_url() {
+        let event_page =
+            read_to_string("res/tests/event_page.html").expect("Could not get test resource");
+
+        assert_eq!(
+            AgendaCulturalAPI::extract_ticket_url(&Html::parse_document(&event_page)),
+            None
+        );
+    }
+
     #[test_log::test]
     fn should_extract_full_description() {
         let event_page =
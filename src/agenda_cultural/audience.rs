@@ -0,0 +1,255 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::fmt::{Display, Formatter};
+
+lazy_static! {
+    static ref AGE_RANGE: Regex =
+        Regex::new(r"(\d{1,2})\s*(?:anos)?\s*(?:-|a|aos|até aos)\s*(\d{1,2})\s*anos").unwrap();
+    static ref MINIMUM_AGE: Regex =
+        Regex::new(r"^m\s*/?\s*(\d{1,2})$|(?:maiores de|\+)\s*(\d{1,2})").unwrap();
+    static ref MAXIMUM_AGE: Regex = Regex::new(r"até\s*(?:aos)?\s*(\d{1,2})\s*anos").unwrap();
+}
+
+const CHILDREN_AUDIENCES: [&str; 3] = ["crianças", "infantil", "bebés"];
+
+/// Who an event is meant for and how it is made accessible,
+/// from the agendalx `target_audience` and `accessibility` lists
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Audience {
+    pub age_range: Option<AgeRange>,
+    /// Audiences that are not an age (e.g. "famílias", "escolas")
+    pub groups: Vec<String>,
+    pub accessibility: Vec<Accessibility>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AgeRange {
+    pub min: Option<u8>,
+    pub max: Option<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Accessibility {
+    /// Interpreted in Língua Gestual Portuguesa
+    SignLanguage,
+    AudioDescription,
+    /// Relaxed performance (sessão descontraída)
+    RelaxedPerformance,
+    Other(String),
+}
+
+impl Audience {
+    pub fn parse(target_audience: &[String], accessibility: &[String]) -> Self {
+        let mut audience = Audience::default();
+
+        for target in target_audience.iter().map(|target| target.trim()) {
+            if target.is_empty() {
+                continue;
+            }
+
+            match AgeRange::parse(target) {
+                Some(age_range) if audience.age_range.is_none() => {
+                    audience.age_range = Some(age_range)
+                }
+                Some(_) => {}
+                None => audience.groups.push(target.to_lowercase()),
+            }
+        }
+
+        audience.accessibility = accessibility
+            .iter()
+            .map(|accessibility| accessibility.trim())
+            .filter(|accessibility| !accessibility.is_empty())
+            .map(Accessibility::parse)
+            .collect();
+        audience.accessibility.dedup();
+
+        audience
+    }
+
+    pub fn is_for_children(&self) -> bool {
+        self.groups
+            .iter()
+            .any(|group| CHILDREN_AUDIENCES.contains(&group.as_str()))
+    }
+}
+
+impl AgeRange {
+    /// Reads ratings like "M/12", "maiores de 16" or "3 aos 6 anos"
+    fn parse(target: &str) -> Option<Self> {
+        let target = target.to_lowercase();
+        let age = |captures: &regex::Captures, group| {
+            captures
+                .get(group)
+                .and_then(|age: regex::Match| age.as_str().parse().ok())
+        };
+
+        if let Some(captures) = AGE_RANGE.captures(&target) {
+            return Some(AgeRange {
+                min: age(&captures, 1),
+                max: age(&captures, 2),
+            });
+        }
+
+        if let Some(captures) = MINIMUM_AGE.captures(&target) {
+            return Some(AgeRange {
+                min: age(&captures, 1).or_else(|| age(&captures, 2)),
+                max: None,
+            });
+        }
+
+        MAXIMUM_AGE.captures(&target).map(|captures| AgeRange {
+            min: None,
+            max: age(&captures, 1),
+        })
+    }
+}
+
+impl Accessibility {
+    fn parse(accessibility: &str) -> Self {
+        let normalized = accessibility.to_lowercase();
+
+        if normalized.contains("lgp") || normalized.contains("língua gestual") {
+            Accessibility::SignLanguage
+        } else if normalized.contains("audiodescri") || normalized.contains("áudio-descri") {
+            Accessibility::AudioDescription
+        } else if normalized.contains("descontraíd") || normalized.contains("relaxed") {
+            Accessibility::RelaxedPerformance
+        } else {
+            Accessibility::Other(accessibility.to_string())
+        }
+    }
+}
+
+impl Display for AgeRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (self.min, self.max) {
+            (Some(min), Some(max)) => write!(f, "{} aos {} anos", min, max),
+            (Some(min), None) => write!(f, "M/{}", min),
+            (None, Some(max)) => write!(f, "até aos {} anos", max),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+impl Display for Accessibility {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Accessibility::SignLanguage => write!(f, "interpretação em LGP"),
+            Accessibility::AudioDescription => write!(f, "audiodescrição"),
+            Accessibility::RelaxedPerformance => write!(f, "sessão descontraída"),
+            Accessibility::Other(accessibility) => write!(f, "{}", accessibility),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test_log::test]
+    fn should_parse_age_ratings() {
+        let age_range = |target: &str| AgeRange::parse(target).unwrap();
+
+        assert_eq!(
+            age_range("M/12"),
+            AgeRange {
+                min: Some(12),
+                max: None
+            }
+        );
+        assert_eq!(age_range("m6"), age_range("M/6"));
+        assert_eq!(
+            age_range("Maiores de 16"),
+            AgeRange {
+                min: Some(16),
+                max: None
+            }
+        );
+        assert_eq!(
+            age_range("3 aos 6 anos"),
+            AgeRange {
+                min: Some(3),
+                max: Some(6)
+            }
+        );
+        assert_eq!(age_range("6-12 anos"), age_range("6 aos 12 anos"));
+        assert_eq!(
+            age_range("até aos 3 anos"),
+            AgeRange {
+                min: None,
+                max: Some(3)
+            }
+        );
+    }
+
+    #[test_log::test]
+    fn should_keep_non_age_audiences_as_groups() {
+        let audience = Audience::parse(&to_strings(&["Famílias", "M/6", "Crianças"]), &[]);
+
+        assert_eq!(
+            audience.age_range,
+            Some(AgeRange {
+                min: Some(6),
+                max: None
+            })
+        );
+        assert_eq!(audience.groups, ["famílias", "crianças"]);
+        assert!(audience.is_for_children());
+    }
+
+    #[test_log::test]
+    fn should_parse_accessibility() {
+        let audience = Audience::parse(
+            &[],
+            &to_strings(&[
+                "Interpretação em LGP",
+                "Audiodescrição",
+                "Sessão descontraída",
+                "Acesso a cadeira de rodas",
+            ]),
+        );
+
+        assert_eq!(
+            audience.accessibility,
+            [
+                Accessibility::SignLanguage,
+                Accessibility::AudioDescription,
+                Accessibility::RelaxedPerformance,
+                Accessibility::Other("Acesso a cadeira de rodas".to_string()),
+            ]
+        );
+    }
+
+    #[test_log::test]
+    fn when_lists_are_empty_should_have_default_audience() {
+        assert_eq!(
+            Audience::parse(&to_strings(&["", " "]), &[]),
+            Audience::default()
+        );
+    }
+
+    #[test_log::test]
+    fn should_display_age_ranges() {
+        assert_eq!(
+            AgeRange {
+                min: Some(12),
+                max: None
+            }
+            .to_string(),
+            "M/12"
+        );
+        assert_eq!(
+            AgeRange {
+                min: Some(3),
+                max: Some(6)
+            }
+            .to_string(),
+            "3 aos 6 anos"
+        );
+    }
+}
@@ -1,7 +1,8 @@
-use super::model::{Event, EventDetails, Schedule};
+use super::audience::Audience;
+use super::model::{Event, EventDetails, Price, Schedule, Venue};
 use chrono::NaiveDate;
 use lazy_static::lazy_static;
-use regex::Regex;
+use regex::{Match, Regex};
 use serde::{de, Deserialize, Deserializer};
 use serde_either::SingleOrVec;
 use serde_json::Value;
@@ -22,14 +23,48 @@ pub struct EventResponse {
     pub string_times: String,
     #[serde(rename = "StartDate", deserialize_with = "deserialize_date")]
     pub start_date: NaiveDate,
+    #[serde(
+        rename = "LastDate",
+        default,
+        deserialize_with = "deserialize_optional_date"
+    )]
+    pub last_date: Option<NaiveDate>,
+    #[serde(rename = "occurences", default, deserialize_with = "deserialize_dates")]
+    pub occurrences: Vec<NaiveDate>,
     #[serde(deserialize_with = "deserialize_btreemap")]
     pub venue: BTreeMap<String, ResponseVenue>,
     #[serde(deserialize_with = "deserialize_btreemap", rename = "tags_name_list")]
     pub tags: BTreeMap<String, ResponseEventTag>,
+    /// Either "free", "paid" or "unknown"
+    #[serde(
+        rename = "price_cat",
+        default,
+        deserialize_with = "deserialize_strings"
+    )]
+    pub price_categories: Vec<String>,
+    #[serde(rename = "price_val", default, deserialize_with = "deserialize_str")]
+    pub price_value: String,
+    #[serde(default, deserialize_with = "deserialize_names")]
+    pub target_audience: Vec<String>,
+    #[serde(default, deserialize_with = "deserialize_names")]
+    pub accessibility: Vec<String>,
 }
 
 lazy_static! {
     static ref REMOVE_YEAR: Regex = Regex::new(r" *?(\d{4}) *?").unwrap();
+    /// Only amounts next to the currency, so ages (e.g. "M/12") aren't read as prices
+    static ref PRICE_AMOUNT: Regex = Regex::new(
+        r"(?i)€\s*(\d+)(?:[.,](\d{1,2}))?|(\d+)(?:[.,](\d{1,2}))?\s*(?:€|euros?\b)"
+    )
+    .unwrap();
+    /// The lower end of ranges written before the currency (e.g. "5 a 7€"), whose upper end
+    /// [PRICE_AMOUNT] already reads
+    static ref PRICE_RANGE_START: Regex = Regex::new(
+        r"(?i)(?:^|[^\w/.,])(\d+)(?:[.,](\d{1,2}))?\s*(?:-|–|/|\ba\b)\s*\d+(?:[.,]\d{1,2})?\s*(?:€|euros?\b)"
+    )
+    .unwrap();
+    static ref FREE_PRICE_VALUE: Regex =
+        Regex::new(r"(?i)gratuit|grátis|gratis|entrada livre").unwrap();
 }
 
 impl EventResponse {
@@ -43,19 +78,84 @@ impl EventResponse {
             self.title.rendered.to_string(),
             EventDetails::new(subtitle, description, self.featured_media_large.to_string()),
             self.link.to_string(),
-            Schedule::new(
-                Self::get_date_description(&self.string_dates),
-                self.string_times.to_string(),
-            ),
+            self.to_schedule(),
             self.venue
                 .iter()
                 .find(|(_, venue)| !venue.name.is_empty())
-                .map(|venue| venue.1.name.to_string())
+                .map(|(slug, venue)| {
+                    let slug = match venue.slug.is_empty() {
+                        true => slug,
+                        false => &venue.slug,
+                    };
+
+                    Venue::new(venue.id, slug, &venue.name)
+                })
                 .unwrap_or_else(|| {
                     warn!("No venue name found (omitting venue)");
-                    "".to_string()
+                    Venue::default()
                 }),
             self.tags.iter().map(|dto| dto.1.name.to_string()).collect(),
+            self.to_price(),
+            Audience::parse(&self.target_audience, &self.accessibility),
+        )
+    }
+
+    /// Free only when no amount is charged, since some are free just for part of the audience
+    /// (e.g. "Gratuito até aos 12 anos; adultos 8€")
+    fn to_price(&self) -> Price {
+        let amounts: Vec<u32> = PRICE_AMOUNT
+            .captures_iter(&self.price_value)
+            .filter_map(|captures| {
+                to_cents(
+                    captures.get(1).or_else(|| captures.get(3))?.as_str(),
+                    captures.get(2).or_else(|| captures.get(4)),
+                )
+            })
+            .chain(
+                PRICE_RANGE_START
+                    .captures_iter(&self.price_value)
+                    .filter_map(|captures| to_cents(captures.get(1)?.as_str(), captures.get(2))),
+            )
+            .collect();
+
+        let is_free = self
+            .price_categories
+            .iter()
+            .any(|category| category == "free")
+            || FREE_PRICE_VALUE.is_match(&self.price_value);
+
+        if is_free && amounts.iter().all(|amount| *amount == 0) {
+            return Price::Free;
+        }
+
+        match (amounts.iter().min(), amounts.iter().max()) {
+            (Some(0), Some(0)) => Price::Free,
+            (Some(min), Some(max)) if min == max => Price::Fixed(*min),
+            (Some(min), Some(max)) => Price::Range(*min, *max),
+            _ => Price::Unknown,
+        }
+    }
+
+    fn to_schedule(&self) -> Schedule {
+        let mut occurrences = self.occurrences.clone();
+
+        occurrences.sort();
+        occurrences.dedup();
+
+        let first_date = Some(self.start_date)
+            .filter(|date| *date != NaiveDate::MIN)
+            .or_else(|| occurrences.first().cloned());
+        let last_date = self
+            .last_date
+            .or_else(|| occurrences.last().cloned())
+            .or(first_date);
+
+        Schedule::new(
+            Self::get_date_description(&self.string_dates),
+            self.string_times.to_string(),
+            occurrences,
+            first_date,
+            last_date,
         )
     }
 
@@ -91,6 +191,10 @@ pub struct ResponseTitle {
 
 #[derive(Debug, Deserialize)]
 pub struct ResponseVenue {
+    #[serde(default, deserialize_with = "deserialize_id")]
+    pub id: Option<u32>,
+    #[serde(default, deserialize_with = "deserialize_str")]
+    pub slug: String,
     #[serde(deserialize_with = "deserialize_str")]
     pub name: String,
 }
@@ -113,6 +217,38 @@ where
     })
 }
 
+fn deserialize_strings<'de, D>(d: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match Value::deserialize(d)? {
+        Value::String(s) => vec![s],
+        Value::Array(values) => values
+            .iter()
+            .filter_map(|value| value.as_str().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    })
+}
+
+/// Names from either a list of strings or of terms (e.g. `[{ "name": "LGP" }]`), or a map of terms
+fn deserialize_names<'de, D>(d: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let to_name = |value: &Value| match value {
+        Value::String(name) => Some(name.to_string()),
+        Value::Object(term) => term.get("name")?.as_str().map(str::to_string),
+        _ => None,
+    };
+
+    Ok(match Value::deserialize(d)? {
+        Value::Array(values) => values.iter().filter_map(to_name).collect(),
+        Value::Object(terms) => terms.values().filter_map(to_name).collect(),
+        _ => Vec::new(),
+    })
+}
+
 fn deserialize_str<'de, D>(d: D) -> Result<String, D::Error>
 where
     D: Deserializer<'de>,
@@ -123,6 +259,18 @@ where
     })
 }
 
+/// IDs come as numbers, but sometimes as strings
+fn deserialize_id<'de, D>(d: D) -> Result<Option<u32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match Value::deserialize(d)? {
+        Value::Number(id) => id.as_u64().and_then(|id| id.try_into().ok()),
+        Value::String(id) => id.parse().ok(),
+        _ => None,
+    })
+}
+
 fn deserialize_date<'de, D>(d: D) -> Result<NaiveDate, D::Error>
 where
     D: Deserializer<'de>,
@@ -144,6 +292,50 @@ where
     }
 }
 
+fn deserialize_optional_date<'de, D>(d: D) -> Result<Option<NaiveDate>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match Value::deserialize(d)? {
+        Value::String(s) => parse_date(&s),
+        _ => None,
+    })
+}
+
+fn deserialize_dates<'de, D>(d: D) -> Result<Vec<NaiveDate>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match Value::deserialize(d)? {
+        Value::Array(values) => values
+            .iter()
+            .filter_map(|value| value.as_str().and_then(parse_date))
+            .collect(),
+        _ => Vec::new(),
+    })
+}
+
+fn parse_date(date: &str) -> Option<NaiveDate> {
+    if date.is_empty() {
+        return None;
+    }
+
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .inspect_err(|err| warn!("Failed to parse date '{}'. Err: {err}", date))
+        .ok()
+}
+
+fn to_cents(euros: &str, cents: Option<Match>) -> Option<u32> {
+    let euros: u32 = euros.parse().ok()?;
+    let cents: u32 = match cents.map(|cents| cents.as_str()) {
+        Some(cents) if cents.len() == 1 => cents.parse::<u32>().ok()? * 10,
+        Some(cents) => cents.parse().ok()?,
+        None => 0,
+    };
+
+    Some(euros * 100 + cents)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -241,6 +433,11 @@ mod tests {
             "{:?}",
             dto
         );
+
+        let venue = &dto.venue["teatro-iberico-2"];
+        assert_eq!(venue.id, Some(328));
+        assert_eq!(venue.slug, "teatro-iberico-2");
+        assert_eq!(venue.name, "Teatro Ibérico");
     }
 
     #[test_log::test]
@@ -315,4 +512,222 @@ mod tests {
             dto
         );
     }
+
+    #[test_log::test]
+    fn should_build_schedule_from_occurrences_and_last_date() {
+        let dto = serde_json::from_str::<EventResponse>(
+            r##"
+              {
+                "title": { "rendered": "Galafoice" },
+                "featured_media_large": "",
+                "subtitle": "",
+                "string_dates": "22 fevereiro a 1 março 2025",
+                "string_times": "s\u00e1b: 21h; dom: 17h",
+                "description": [],
+                "venue": [],
+                "tags_name_list": [],
+                "link": "https:\/\/www.agendalx.pt\/events\/event\/galafoice\/",
+                "occurences": ["2025-02-23", "2025-02-22", "2025-03-01", "2025-02-23", "not-a-date"],
+                "StartDate": "2025-02-22",
+                "LastDate": "2025-03-01"
+              }"##,
+        )
+        .unwrap();
+
+        let schedule = dto.to_schedule();
+
+        assert_eq!(
+            schedule.occurrences,
+            [
+                NaiveDate::from_ymd_opt(2025, 2, 22).unwrap(),
+                NaiveDate::from_ymd_opt(2025, 2, 23).unwrap(),
+                NaiveDate::from_ymd_opt(2025, 3, 1).unwrap(),
+            ]
+        );
+        assert_eq!(schedule.first_date, NaiveDate::from_ymd_opt(2025, 2, 22));
+        assert_eq!(schedule.last_date, NaiveDate::from_ymd_opt(2025, 3, 1));
+    }
+
+    #[test_log::test]
+    fn when_last_date_is_missing_should_use_last_occurrence() {
+        let dto = serde_json::from_str::<EventResponse>(
+            r##"
+              {
+                "title": { "rendered": "Galafoice" },
+                "featured_media_large": "",
+                "subtitle": "",
+                "string_dates": "22 fevereiro a 1 março 2025",
+                "string_times": "",
+                "description": [],
+                "venue": [],
+                "tags_name_list": [],
+                "link": "",
+                "occurences": ["2025-02-22", "2025-03-01"],
+                "StartDate": "2025-02-22",
+                "LastDate": ""
+              }"##,
+        )
+        .unwrap();
+
+        let schedule = dto.to_schedule();
+
+        assert_eq!(schedule.last_date, NaiveDate::from_ymd_opt(2025, 3, 1));
+    }
+
+    #[test_log::test]
+    fn when_there_are_no_occurrences_should_only_have_start_date() {
+        let dto = serde_json::from_str::<EventResponse>(
+            r##"
+              {
+                "title": { "rendered": "Galafoice" },
+                "featured_media_large": "",
+                "subtitle": "",
+                "string_dates": "22 fevereiro 2025",
+                "string_times": "",
+                "description": [],
+                "venue": [],
+                "tags_name_list": [],
+                "link": "",
+                "StartDate": "2025-02-22"
+              }"##,
+        )
+        .unwrap();
+
+        let schedule = dto.to_schedule();
+
+        assert!(schedule.occurrences.is_empty());
+        assert_eq!(schedule.first_date, NaiveDate::from_ymd_opt(2025, 2, 22));
+        assert_eq!(schedule.last_date, NaiveDate::from_ymd_opt(2025, 2, 22));
+    }
+
+    fn build_response_with_price(price_categories: &str, price_value: &str) -> EventResponse {
+        serde_json::from_str::<EventResponse>(&format!(
+            r##"
+              {{
+                "title": {{ "rendered": "Galafoice" }},
+                "featured_media_large": "",
+                "subtitle": "",
+                "string_dates": "",
+                "string_times": "",
+                "description": [],
+                "venue": [],
+                "tags_name_list": [],
+                "link": "",
+                "StartDate": "2025-02-22",
+                "price_cat": {price_categories},
+                "price_val": {price_value}
+              }}"##
+        ))
+        .unwrap()
+    }
+
+    #[test_log::test]
+    fn should_parse_fixed_price() {
+        assert_eq!(
+            build_response_with_price(r#"["paid"]"#, r#""9€""#).to_price(),
+            Price::Fixed(900)
+        );
+        assert_eq!(
+            build_response_with_price(r#"["paid"]"#, r#""7,5 €""#).to_price(),
+            Price::Fixed(750)
+        );
+    }
+
+    #[test_log::test]
+    fn should_parse_price_range() {
+        assert_eq!(
+            build_response_with_price(r#"["paid"]"#, r#""5€ a 12,50€""#).to_price(),
+            Price::Range(500, 1250)
+        );
+        assert_eq!(
+            build_response_with_price(r#"["paid"]"#, r#""12€ - 5€""#).to_price(),
+            Price::Range(500, 1200)
+        );
+        assert_eq!(
+            build_response_with_price(r#"["paid"]"#, r#""5 a 7€""#).to_price(),
+            Price::Range(500, 700)
+        );
+        assert_eq!(
+            build_response_with_price(r#"["paid"]"#, r#""5-7,50 €""#).to_price(),
+            Price::Range(500, 750)
+        );
+    }
+
+    #[test_log::test]
+    fn should_parse_free_price() {
+        assert_eq!(
+            build_response_with_price(r#"["free"]"#, r#""""#).to_price(),
+            Price::Free
+        );
+        assert_eq!(
+            build_response_with_price(r#"["unknown"]"#, r#""Entrada livre""#).to_price(),
+            Price::Free
+        );
+        assert_eq!(
+            build_response_with_price(r#"["paid"]"#, r#""0€""#).to_price(),
+            Price::Free
+        );
+    }
+
+    #[test_log::test]
+    fn when_only_part_of_audience_is_free_should_parse_amount() {
+        assert_eq!(
+            build_response_with_price(r#"["free"]"#, r#""Gratuito até aos 12 anos; adultos 8€""#)
+                .to_price(),
+            Price::Fixed(800)
+        );
+    }
+
+    #[test_log::test]
+    fn should_ignore_numbers_without_currency() {
+        assert_eq!(
+            build_response_with_price(r#"["paid"]"#, r#""M/12 · 10€""#).to_price(),
+            Price::Fixed(1000)
+        );
+        assert_eq!(
+            build_response_with_price(r#"["paid"]"#, r#""M/6 - 10€""#).to_price(),
+            Price::Fixed(1000)
+        );
+        assert_eq!(
+            build_response_with_price(r#"["paid"]"#, r#""€5 a 7 euros""#).to_price(),
+            Price::Range(500, 700)
+        );
+    }
+
+    #[test_log::test]
+    fn when_price_is_missing_should_be_unknown() {
+        assert_eq!(
+            build_response_with_price(r#"["unknown"]"#, r#""""#).to_price(),
+            Price::Unknown
+        );
+        assert_eq!(
+            build_response_with_price("false", "null").to_price(),
+            Price::Unknown
+        );
+    }
+
+    #[test_log::test]
+    fn should_deserialize_audience_and_accessibility_names() {
+        let dto = serde_json::from_str::<EventResponse>(
+            r##"
+              {
+                "title": { "rendered": "Galafoice" },
+                "featured_media_large": "",
+                "subtitle": "",
+                "string_dates": "",
+                "string_times": "",
+                "description": [],
+                "venue": [],
+                "tags_name_list": [],
+                "link": "",
+                "StartDate": "2025-02-22",
+                "target_audience": ["M/6", { "id": 1, "name": "Famílias" }],
+                "accessibility": { "lgp": { "id": 2, "slug": "lgp", "name": "Interpretação em LGP" } }
+              }"##,
+        )
+        .unwrap();
+
+        assert_eq!(dto.target_audience, ["M/6", "Famílias"]);
+        assert_eq!(dto.accessibility, ["Interpretação em LGP"]);
+    }
 }
@@ -0,0 +1,187 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use scraper::{ElementRef, Html};
+
+lazy_static! {
+    static ref WHITESPACE: Regex = Regex::new(r"[ \t\r\n\u{a0}]+").unwrap();
+    static ref SPACES_AROUND_NEWLINE: Regex = Regex::new(r" *\n *").unwrap();
+    static ref EXTRA_NEWLINES: Regex = Regex::new(r"\n{3,}").unwrap();
+}
+
+/// Characters with a meaning in Discord markdown
+const CONTROL_CHARACTERS: [char; 8] = ['\\', '*', '_', '~', '`', '|', '>', '#'];
+
+/// Converts an HTML fragment (e.g. an agendalx event description) to Discord markdown,
+/// keeping emphasis, paragraphs, lists and links
+pub fn html_to_markdown(html: &str) -> String {
+    let fragment = Html::parse_fragment(html);
+    let markdown = render_children(fragment.root_element());
+    let markdown = SPACES_AROUND_NEWLINE.replace_all(&markdown, "\n");
+
+    EXTRA_NEWLINES
+        .replace_all(&markdown, "\n\n")
+        .trim()
+        .to_string()
+}
+
+fn render_children(element: ElementRef) -> String {
+    element
+        .children()
+        .map(|child| match ElementRef::wrap(child) {
+            Some(child_element) => render_element(child_element),
+            None => child
+                .value()
+                .as_text()
+                .map(|text| escape(&WHITESPACE.replace_all(text, " ")))
+                .unwrap_or_default(),
+        })
+        .collect()
+}
+
+fn render_element(element: ElementRef) -> String {
+    match element.value().name() {
+        "script" | "style" => String::new(),
+        "br" => "\n".to_string(),
+        "p" | "div" | "section" | "blockquote" => block(&render_children(element)),
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => block(&emphasize(element, "**")),
+        "em" | "i" => emphasize(element, "*"),
+        "strong" | "b" => emphasize(element, "**"),
+        "u" => emphasize(element, "__"),
+        "s" | "del" | "strike" => emphasize(element, "~~"),
+        "ul" => render_list(element, |_| "-".to_string()),
+        "ol" => render_list(element, |index| format!("{}.", index + 1)),
+        "a" => render_link(element),
+        _ => render_children(element),
+    }
+}
+
+fn block(content: &str) -> String {
+    format!("\n\n{}\n\n", content.trim())
+}
+
+/// Wraps the content in `marker`, keeping its surrounding spaces outside so the markdown applies
+fn emphasize(element: ElementRef, marker: &str) -> String {
+    let content = render_children(element);
+    let trimmed = content.trim();
+
+    if trimmed.is_empty() {
+        return content;
+    }
+
+    let leading = &content[..content.len() - content.trim_start().len()];
+    let trailing = &content[content.trim_end().len()..];
+
+    format!("{}{}{}{}{}", leading, marker, trimmed, marker, trailing)
+}
+
+fn render_list(element: ElementRef, bullet: impl Fn(usize) -> String) -> String {
+    let items: Vec<String> = element
+        .children()
+        .filter_map(ElementRef::wrap)
+        .filter(|child| child.value().name() == "li")
+        .enumerate()
+        .map(|(index, item)| {
+            let content = render_children(item);
+
+            format!("{} {}", bullet(index), content.trim())
+        })
+        .collect();
+
+    block(&items.join("\n"))
+}
+
+/// Only absolute links are kept since relative ones wouldn't work in Discord
+fn render_link(element: ElementRef) -> String {
+    let text = render_children(element);
+    let href = element.value().attr("href").unwrap_or_default().trim();
+
+    if !href.starts_with("http://") && !href.starts_with("https://") {
+        return text;
+    }
+
+    if text.trim().is_empty() || text.trim() == escape(href) {
+        return href.to_string();
+    }
+
+    format!("[{}]({})", text.trim(), href.replace(')', "%29"))
+}
+
+/// Escapes text to show it as a link's label, where a bracket would end it early
+pub fn escape_link_label(text: &str) -> String {
+    escape(text).replace('[', "\\[").replace(']', "\\]")
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for character in text.chars() {
+        if CONTROL_CHARACTERS.contains(&character) {
+            escaped.push('\\');
+        }
+
+        escaped.push(character);
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_log::test]
+    fn should_keep_emphasis() {
+        assert_eq!(
+            html_to_markdown(
+                "<p><em>Rute e Lô</em> são os <strong>protagonistas </strong>desta história.</p>"
+            ),
+            "*Rute e Lô* são os **protagonistas** desta história."
+        );
+    }
+
+    #[test_log::test]
+    fn should_separate_paragraphs_and_keep_line_breaks() {
+        assert_eq!(
+            html_to_markdown("<p>Primeiro\n parágrafo</p><p>Segundo<br>com quebra</p>"),
+            "Primeiro parágrafo\n\nSegundo\ncom quebra"
+        );
+    }
+
+    #[test_log::test]
+    fn should_convert_lists() {
+        assert_eq!(
+            html_to_markdown("<p>Elenco:</p><ul><li>Rute</li><li> Lô </li></ul><ol><li>Um</li><li>Dois</li></ol>"),
+            "Elenco:\n\n- Rute\n- Lô\n\n1. Um\n2. Dois"
+        );
+    }
+
+    #[test_log::test]
+    fn should_convert_absolute_links() {
+        assert_eq!(
+            html_to_markdown(
+                r#"Bilhetes na <a href="https://bol.pt/evento">BOL</a> ou <a href="/?s=teatro">aqui</a>"#
+            ),
+            "Bilhetes na [BOL](https://bol.pt/evento) ou aqui"
+        );
+        assert_eq!(
+            html_to_markdown(r#"<a href="https://bairrobenfica.pt">https://bairrobenfica.pt</a>"#),
+            "https://bairrobenfica.pt"
+        );
+    }
+
+    #[test_log::test]
+    fn should_decode_entities_and_escape_discord_characters() {
+        assert_eq!(
+            html_to_markdown("M&amp;M&#8217;s&nbsp;*estreia* &lt;3 &mdash; 2_3"),
+            "M&M’s \\*estreia\\* <3 — 2\\_3"
+        );
+    }
+
+    #[test_log::test]
+    fn should_escape_link_labels() {
+        assert_eq!(
+            escape_link_label("Rua [antiga] do *Benformoso*, 2_A"),
+            "Rua \\[antiga\\] do \\*Benformoso\\*, 2\\_A"
+        );
+    }
+}
@@ -1,3 +1,9 @@
 pub mod api;
+pub mod audience;
 mod dto;
+pub mod markdown;
 pub mod model;
+pub mod ongoing;
+pub mod page_cache;
+pub mod source;
+pub mod timetable;
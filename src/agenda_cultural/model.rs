@@ -1,6 +1,19 @@
-use strum::Display;
+use crate::agenda_cultural::audience::Audience;
+use crate::agenda_cultural::timetable::Timetable;
+use crate::config::tags::{normalize, CHILDREN_TAG};
+use chrono::{Datelike, Months, NaiveDate};
+use lazy_static::lazy_static;
+use regex::Regex;
+use reqwest::Url;
+use std::fmt::{Display, Formatter};
 
-const CHILDREN_TAG: &str = "crianças";
+const FREE_TAG: &str = "gratuito";
+const MAP_SEARCH_URL: &str = "https://www.google.com/maps/search/";
+
+lazy_static! {
+    static ref CANCELLED: Regex = Regex::new(r"(?i)\bcancelad[oa]s?\b").unwrap();
+    static ref POSTPONED: Regex = Regex::new(r"(?i)\badiad[oa]s?\b").unwrap();
+}
 
 #[allow(dead_code)]
 #[derive(Debug, Clone)]
@@ -9,28 +22,171 @@ pub struct Event {
     pub details: EventDetails,
     pub link: String,
     pub occurring_at: Schedule,
-    pub venue: String,
+    pub venue: Venue,
     pub tags: Vec<String>,
     pub is_for_children: bool,
+    pub price: Price,
+    pub audience: Audience,
+    pub status: EventStatus,
+    /// The event's own ticket page, when its agendalx page links to one
+    pub ticket_url: Option<String>,
 }
 
 impl Event {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         title: String,
         details: EventDetails,
         link: String,
         occurring_at: Schedule,
-        venue: String,
+        venue: Venue,
         tags: Vec<String>,
+        price: Price,
+        audience: Audience,
     ) -> Self {
+        let status = EventStatus::detect(&title, &tags);
+
         Self {
             title,
             details,
             link,
             occurring_at,
             venue,
-            is_for_children: tags.iter().any(|tag| tag.to_lowercase() == CHILDREN_TAG),
+            is_for_children: tags.iter().any(|tag| normalize(tag) == CHILDREN_TAG)
+                || audience.is_for_children(),
+            price: match price {
+                // The parsed price wins, since partly paid events are tagged as free too
+                Price::Unknown if tags.iter().any(|tag| normalize(tag) == FREE_TAG) => Price::Free,
+                price => price,
+            },
             tags,
+            audience,
+            status,
+            ticket_url: None,
+        }
+    }
+}
+
+/// Where an event happens, as listed by agendalx
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Venue {
+    /// agendalx's WordPress term ID
+    pub id: Option<u32>,
+    /// e.g. "teatro-iberico-2"
+    pub slug: String,
+    pub name: String,
+    /// Street address, only found on the event page
+    pub address: Option<String>,
+    pub coordinates: Option<Coordinates>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl Venue {
+    pub fn new(id: Option<u32>, slug: &str, name: &str) -> Self {
+        Self {
+            id,
+            slug: slug.to_string(),
+            name: name.to_string(),
+            address: None,
+            coordinates: None,
+        }
+    }
+
+    /// Searches the coordinates when known, falling back to the address
+    pub fn map_url(&self) -> Option<String> {
+        let query = match (self.coordinates, &self.address) {
+            (Some(coordinates), _) => {
+                format!("{},{}", coordinates.latitude, coordinates.longitude)
+            }
+            (None, Some(address)) => format!("{}, {}", self.name, address),
+            (None, None) => return None,
+        };
+
+        Url::parse_with_params(MAP_SEARCH_URL, &[("api", "1"), ("query", &query)])
+            .ok()
+            .map(String::from)
+    }
+}
+
+impl Display for Venue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// Whether an event still happens as it was posted
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum EventStatus {
+    #[default]
+    Scheduled,
+    Cancelled,
+    Postponed,
+    /// No longer listed on agendalx before its last date
+    Removed,
+}
+
+impl EventStatus {
+    /// Reads "cancelado" or "adiado" from the title or tags
+    fn detect(title: &str, tags: &[String]) -> Self {
+        let texts = || std::iter::once(title).chain(tags.iter().map(String::as_str));
+
+        if texts().any(|text| CANCELLED.is_match(text)) {
+            EventStatus::Cancelled
+        } else if texts().any(|text| POSTPONED.is_match(text)) {
+            EventStatus::Postponed
+        } else {
+            EventStatus::Scheduled
+        }
+    }
+}
+
+impl Display for EventStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EventStatus::Scheduled => write!(f, "Agendado"),
+            EventStatus::Cancelled => write!(f, "Cancelado"),
+            EventStatus::Postponed => write!(f, "Adiado"),
+            EventStatus::Removed => write!(f, "Removido da agenda"),
+        }
+    }
+}
+
+/// Ticket price, with amounts in euro cents
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Price {
+    Free,
+    Fixed(u32),
+    /// Cheapest and most expensive tickets (e.g. "5€ a 10€")
+    Range(u32, u32),
+    Unknown,
+}
+
+impl Price {
+    fn format_euros(cents: u32) -> String {
+        match cents % 100 {
+            0 => format!("{} €", cents / 100),
+            remainder => format!("{},{:02} €", cents / 100, remainder),
+        }
+    }
+}
+
+impl Display for Price {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Price::Free => write!(f, "Gratuito"),
+            Price::Fixed(cents) => write!(f, "{}", Self::format_euros(*cents)),
+            Price::Range(min, max) => write!(
+                f,
+                "{} a {}",
+                Self::format_euros(*min),
+                Self::format_euros(*max)
+            ),
+            Price::Unknown => write!(f, "Sem informação"),
         }
     }
 }
@@ -59,16 +215,234 @@ impl EventDetails {
 pub struct Schedule {
     pub dates: String,
     pub times: String,
+    pub timetable: Timetable,
+    /// Every day the event happens on, in ascending order
+    pub occurrences: Vec<NaiveDate>,
+    pub first_date: Option<NaiveDate>,
+    pub last_date: Option<NaiveDate>,
 }
 
 impl Schedule {
-    pub fn new(dates: String, times: String) -> Self {
-        Self { dates, times }
+    pub fn new(
+        dates: String,
+        times: String,
+        occurrences: Vec<NaiveDate>,
+        first_date: Option<NaiveDate>,
+        last_date: Option<NaiveDate>,
+    ) -> Self {
+        Self {
+            dates,
+            timetable: Timetable::parse(&times),
+            times,
+            occurrences,
+            first_date,
+            last_date,
+        }
+    }
+
+    /// First day of every month the event happens in, from its occurrences when known
+    pub fn months(&self) -> Vec<NaiveDate> {
+        let to_month = |date: &NaiveDate| date.with_day(1).unwrap();
+
+        if !self.occurrences.is_empty() {
+            let mut months: Vec<NaiveDate> = self.occurrences.iter().map(to_month).collect();
+            months.dedup();
+            return months;
+        }
+
+        let (Some(first_date), Some(last_date)) = (self.first_date, self.last_date) else {
+            return Vec::new();
+        };
+
+        std::iter::successors(Some(to_month(&first_date)), |month| {
+            month.checked_add_months(Months::new(1))
+        })
+        .take_while(|month| *month <= last_date)
+        .collect()
+    }
+
+    /// Events without a last date happen on a single day
+    pub fn last_day(&self) -> Option<NaiveDate> {
+        self.last_date.or(self.first_date)
+    }
+
+    /// First day the event happens on from `from` until `to` (inclusive), from its occurrences when known
+    pub fn first_date_between(&self, from: NaiveDate, to: Option<NaiveDate>) -> Option<NaiveDate> {
+        let date = match self.occurrences.is_empty() {
+            false => *self.occurrences.iter().find(|date| **date >= from)?,
+            true => {
+                let first_date = self.first_date?;
+                let date = first_date.max(from);
+
+                if date > self.last_date.unwrap_or(first_date) {
+                    return None;
+                }
+
+                date
+            }
+        };
+
+        to.is_none_or(|to| date <= to).then_some(date)
+    }
+}
+
+/// An agendalx category followed by the bot (e.g. teatro, dança, música)
+#[derive(Debug, Clone, Eq, Hash, PartialEq)]
+pub struct Category {
+    /// As used by agendalx (e.g. "teatro", "danca")
+    pub slug: String,
+    pub display_name: String,
+}
+
+impl Category {
+    pub fn new(slug: &str, display_name: &str) -> Self {
+        Self {
+            slug: slug.to_string(),
+            display_name: display_name.to_string(),
+        }
+    }
+}
+
+impl Display for Category {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.display_name)
     }
 }
 
-#[derive(strum::IntoStaticStr, Debug, Display)]
-pub enum Category {
-    Teatro,
-    Artes,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_event(tags: Vec<String>, price: Price) -> Event {
+        build_titled_event("Galafoice", tags, price)
+    }
+
+    fn build_titled_event(title: &str, tags: Vec<String>, price: Price) -> Event {
+        Event::new(
+            title.to_string(),
+            EventDetails::new(String::new(), String::new(), String::new()),
+            String::new(),
+            Schedule::new(String::new(), String::new(), Vec::new(), None, None),
+            Venue::default(),
+            tags,
+            price,
+            Audience::default(),
+        )
+    }
+
+    #[test_log::test]
+    fn when_tagged_as_free_should_be_free() {
+        let event = build_event(vec!["Gratuito".to_string()], Price::Unknown);
+
+        assert_eq!(event.price, Price::Free);
+    }
+
+    #[test_log::test]
+    fn when_tagged_as_free_but_partly_paid_should_keep_price() {
+        let event = build_event(vec!["gratuito".to_string()], Price::Fixed(800));
+
+        assert_eq!(event.price, Price::Fixed(800));
+    }
+
+    #[test_log::test]
+    fn should_display_price_in_euros() {
+        assert_eq!(Price::Fixed(900).to_string(), "9 €");
+        assert_eq!(Price::Range(750, 1200).to_string(), "7,50 € a 12 €");
+        assert_eq!(Price::Free.to_string(), "Gratuito");
+    }
+
+    #[test_log::test]
+    fn should_link_venue_to_map() {
+        let mut venue = Venue::new(Some(346), "mac-ccb", "MAC/CCB");
+
+        assert_eq!(venue.map_url(), None);
+
+        venue.address = Some("Praça do Império".to_string());
+        assert_eq!(
+            venue.map_url().as_deref(),
+            Some("https://www.google.com/maps/search/?api=1&query=MAC%2FCCB%2C+Pra%C3%A7a+do+Imp%C3%A9rio")
+        );
+
+        venue.coordinates = Some(Coordinates {
+            latitude: 38.695503,
+            longitude: -9.208354,
+        });
+        assert_eq!(
+            venue.map_url().as_deref(),
+            Some("https://www.google.com/maps/search/?api=1&query=38.695503%2C-9.208354")
+        );
+    }
+
+    #[test_log::test]
+    fn should_list_months_the_event_happens_in() {
+        let date = |month, day| NaiveDate::from_ymd_opt(2025, month, day).unwrap();
+        let schedule = |occurrences, first_date, last_date| {
+            Schedule::new(
+                String::new(),
+                String::new(),
+                occurrences,
+                first_date,
+                last_date,
+            )
+        };
+
+        assert_eq!(
+            schedule(Vec::new(), Some(date(3, 20)), Some(date(5, 2))).months(),
+            [date(3, 1), date(4, 1), date(5, 1)]
+        );
+        assert_eq!(
+            schedule(vec![date(3, 20), date(3, 21), date(6, 1)], None, None).months(),
+            [date(3, 1), date(6, 1)]
+        );
+        assert!(schedule(Vec::new(), None, None).months().is_empty());
+    }
+
+    #[test_log::test]
+    fn should_find_first_date_the_event_happens_on_in_range() {
+        let date = |month, day| NaiveDate::from_ymd_opt(2025, month, day).unwrap();
+        let schedule = |occurrences, first_date, last_date| {
+            Schedule::new(
+                String::new(),
+                String::new(),
+                occurrences,
+                first_date,
+                last_date,
+            )
+        };
+        let running = schedule(Vec::new(), Some(date(3, 20)), Some(date(5, 2)));
+        let sessions = schedule(vec![date(3, 20), date(4, 10)], None, None);
+
+        assert_eq!(
+            running.first_date_between(date(4, 1), None),
+            Some(date(4, 1))
+        );
+        assert_eq!(
+            running.first_date_between(date(3, 1), Some(date(3, 10))),
+            None
+        );
+        assert_eq!(running.first_date_between(date(5, 3), None), None);
+        assert_eq!(
+            sessions.first_date_between(date(3, 21), Some(date(4, 30))),
+            Some(date(4, 10))
+        );
+        assert_eq!(
+            sessions.first_date_between(date(3, 21), Some(date(4, 9))),
+            None
+        );
+    }
+
+    #[test_log::test]
+    fn when_title_or_tags_mention_it_should_detect_status() {
+        let status = |title: &str, tags: &[&str]| {
+            let tags = tags.iter().map(|tag| tag.to_string()).collect();
+
+            build_titled_event(title, tags, Price::Unknown).status
+        };
+
+        assert_eq!(status("Galafoice", &[]), EventStatus::Scheduled);
+        assert_eq!(status("CANCELADO | Galafoice", &[]), EventStatus::Cancelled);
+        assert_eq!(status("Galafoice", &["Adiado"]), EventStatus::Postponed);
+        assert_eq!(status("Sessão adiada", &[]), EventStatus::Postponed);
+        assert_eq!(status("Os Adiadores", &[]), EventStatus::Scheduled);
+    }
 }
@@ -0,0 +1,104 @@
+use crate::agenda_cultural::model::Event;
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+
+/// Events that keep running in months after the one they started in, by those later months.
+/// Months before `current_month` are left out since their threads are no longer followed
+pub fn ongoing_events_by_month(
+    events_by_month: &BTreeMap<NaiveDate, Vec<Event>>,
+    current_month: NaiveDate,
+) -> BTreeMap<NaiveDate, Vec<Event>> {
+    let mut ongoing_events: BTreeMap<NaiveDate, Vec<Event>> = BTreeMap::new();
+
+    for (start_month, events) in events_by_month {
+        for event in events {
+            event
+                .occurring_at
+                .months()
+                .into_iter()
+                .filter(|month| month > start_month && *month >= current_month)
+                .for_each(|month| ongoing_events.entry(month).or_default().push(event.clone()));
+        }
+    }
+
+    ongoing_events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agenda_cultural::audience::Audience;
+    use crate::agenda_cultural::model::{EventDetails, Price, Schedule, Venue};
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, month, day).unwrap()
+    }
+
+    fn build_event(title: &str, first_date: NaiveDate, last_date: NaiveDate) -> Event {
+        Event::new(
+            title.to_string(),
+            EventDetails::new(String::new(), String::new(), String::new()),
+            title.to_string(),
+            Schedule::new(
+                String::new(),
+                String::new(),
+                Vec::new(),
+                Some(first_date),
+                Some(last_date),
+            ),
+            Venue::default(),
+            Vec::new(),
+            Price::Unknown,
+            Audience::default(),
+        )
+    }
+
+    fn titles(events: &[Event]) -> Vec<&str> {
+        events.iter().map(|event| event.title.as_str()).collect()
+    }
+
+    #[test_log::test]
+    fn should_list_events_in_the_later_months_they_run_in() {
+        let events_by_month = BTreeMap::from([
+            (
+                date(3, 1),
+                vec![
+                    build_event("Galafoice", date(3, 20), date(6, 2)),
+                    build_event("Mães", date(3, 5), date(3, 9)),
+                ],
+            ),
+            (
+                date(4, 1),
+                vec![build_event("Quadros", date(4, 10), date(5, 4))],
+            ),
+        ]);
+
+        let ongoing_events = ongoing_events_by_month(&events_by_month, date(3, 1));
+
+        assert_eq!(
+            ongoing_events.keys().cloned().collect::<Vec<_>>(),
+            [date(4, 1), date(5, 1), date(6, 1)]
+        );
+        assert_eq!(titles(&ongoing_events[&date(4, 1)]), ["Galafoice"]);
+        assert_eq!(
+            titles(&ongoing_events[&date(5, 1)]),
+            ["Galafoice", "Quadros"]
+        );
+        assert_eq!(titles(&ongoing_events[&date(6, 1)]), ["Galafoice"]);
+    }
+
+    #[test_log::test]
+    fn should_leave_out_months_already_past() {
+        let events_by_month = BTreeMap::from([(
+            date(1, 1),
+            vec![build_event("Galafoice", date(1, 20), date(4, 2))],
+        )]);
+
+        let ongoing_events = ongoing_events_by_month(&events_by_month, date(3, 1));
+
+        assert_eq!(
+            ongoing_events.keys().cloned().collect::<Vec<_>>(),
+            [date(3, 1), date(4, 1)]
+        );
+    }
+}
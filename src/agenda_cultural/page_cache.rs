@@ -0,0 +1,261 @@
+use crate::hashing::fnv1a;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::fs;
+use tokio::sync::Mutex;
+use tracing::{debug, trace, warn};
+
+/// On-disk cache of event pages, keyed by link
+#[derive(Debug, Clone)]
+pub struct PageCache {
+    directory: PathBuf,
+    /// How long a page is used without asking agendalx whether it changed
+    ttl: Duration,
+    /// Oldest pages are evicted once the cache grows past this
+    max_size_bytes: u64,
+    /// Of the cached pages, scanned on the first write and kept up to date after it
+    size_bytes: Arc<Mutex<Option<u64>>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CachedPage {
+    pub link: String,
+    pub body: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// Seconds since the Unix epoch
+    pub fetched_at: u64,
+}
+
+impl CachedPage {
+    pub fn new(
+        link: &str,
+        body: String,
+        etag: Option<String>,
+        last_modified: Option<String>,
+    ) -> Self {
+        Self {
+            link: link.to_string(),
+            body,
+            etag,
+            last_modified,
+            fetched_at: now(),
+        }
+    }
+}
+
+impl PageCache {
+    pub fn new(directory: impl Into<PathBuf>, ttl: Duration, max_size_bytes: u64) -> Self {
+        Self {
+            directory: directory.into(),
+            ttl,
+            max_size_bytes,
+            size_bytes: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn is_fresh(&self, page: &CachedPage) -> bool {
+        now().saturating_sub(page.fetched_at) < self.ttl.as_secs()
+    }
+
+    pub async fn get(&self, link: &str) -> Option<CachedPage> {
+        let content = fs::read_to_string(self.path(link)).await.ok()?;
+
+        match serde_json::from_str::<CachedPage>(&content) {
+            Ok(page) if page.link == link => Some(page),
+            Ok(_) => {
+                debug!("Cached page collides with another link: {}", link);
+                None
+            }
+            Err(err) => {
+                warn!("Ignoring corrupted cached page for '{}': {}", link, err);
+                None
+            }
+        }
+    }
+
+    pub async fn put(&self, page: &CachedPage) {
+        if let Err(err) = fs::create_dir_all(&self.directory).await {
+            warn!("Failed to create page cache directory: {}", err);
+            return;
+        }
+
+        let content = match serde_json::to_string(page) {
+            Ok(content) => content,
+            Err(err) => {
+                warn!("Failed to serialize page '{}': {}", page.link, err);
+                return;
+            }
+        };
+
+        let path = self.path(&page.link);
+        // Held while writing, so concurrent writes don't lose each other's sizes
+        let mut size_bytes = self.size_bytes.lock().await;
+        let replaced_size = fs::metadata(&path)
+            .await
+            .map_or(0, |metadata| metadata.len());
+        let written_size = content.len() as u64;
+
+        if let Err(err) = fs::write(path, content).await {
+            warn!("Failed to cache page '{}': {}", page.link, err);
+            return;
+        }
+
+        let total_size = match *size_bytes {
+            Some(total_size) => total_size.saturating_sub(replaced_size) + written_size,
+            None => self.scan().await.iter().map(|(_, size, _)| size).sum(),
+        };
+
+        *size_bytes = Some(match total_size > self.max_size_bytes {
+            true => self.evict_oldest().await,
+            false => total_size,
+        });
+    }
+
+    /// Modification time, size and path of every cached page
+    async fn scan(&self) -> Vec<(SystemTime, u64, PathBuf)> {
+        let Ok(mut entries) = fs::read_dir(&self.directory).await else {
+            return Vec::new();
+        };
+
+        let mut pages = Vec::new();
+
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            if let Ok(metadata) = entry.metadata().await {
+                let modified_at = metadata.modified().unwrap_or(UNIX_EPOCH);
+
+                pages.push((modified_at, metadata.len(), entry.path()));
+            }
+        }
+
+        pages
+    }
+
+    /// Removes the least recently written pages until the cache fits its size limit.
+    ///
+    /// Returns the size left
+    async fn evict_oldest(&self) -> u64 {
+        let mut pages = self.scan().await;
+        let mut total_size: u64 = pages.iter().map(|(_, size, _)| size).sum();
+
+        pages.sort_by_key(|(modified_at, _, _)| *modified_at);
+
+        for (_, size, path) in pages {
+            if total_size <= self.max_size_bytes {
+                break;
+            }
+
+            trace!("Evicting cached page {:?}", path);
+            match fs::remove_file(&path).await {
+                Ok(_) => total_size -= size,
+                Err(err) => warn!("Failed to evict cached page {:?}: {}", path, err),
+            }
+        }
+
+        total_size
+    }
+
+    fn path(&self, link: &str) -> PathBuf {
+        self.directory.join(format!("{:016x}.json", fnv1a(link)))
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn build_cache(ttl: Duration, max_size_bytes: u64) -> PageCache {
+        PageCache::new(
+            std::env::temp_dir().join(format!("aec-page-cache-{}", Uuid::new_v4())),
+            ttl,
+            max_size_bytes,
+        )
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn should_get_cached_page() {
+        let cache = build_cache(Duration::from_secs(60), 1_000_000);
+        let page = CachedPage::new(
+            "https://www.agendalx.pt/events/event/maes/",
+            "<html></html>".to_string(),
+            Some("\"abc\"".to_string()),
+            None,
+        );
+
+        cache.put(&page).await;
+
+        assert_eq!(cache.get(&page.link).await, Some(page.clone()));
+        assert!(cache.is_fresh(&page));
+        assert_eq!(
+            cache
+                .get("https://www.agendalx.pt/events/event/31-mulheres/")
+                .await,
+            None
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn when_ttl_expired_should_not_be_fresh() {
+        let cache = build_cache(Duration::from_secs(60), 1_000_000);
+        let mut page = CachedPage::new("https://www.agendalx.pt/", String::new(), None, None);
+        page.fetched_at -= 61;
+
+        assert!(!cache.is_fresh(&page));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn when_cache_is_too_big_should_evict_oldest_pages() {
+        let cache = build_cache(Duration::from_secs(60), 300);
+        let body = "a".repeat(100);
+        let links = [
+            "https://www.agendalx.pt/events/event/1/",
+            "https://www.agendalx.pt/events/event/2/",
+            "https://www.agendalx.pt/events/event/3/",
+        ];
+
+        for link in links {
+            cache
+                .put(&CachedPage::new(link, body.clone(), None, None))
+                .await;
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        assert_eq!(cache.get(links[0]).await, None);
+        assert!(cache.get(links[2]).await.is_some());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn should_keep_track_of_cache_size_across_writes() {
+        let cache = build_cache(Duration::from_secs(60), 1_000_000);
+        let link = "https://www.agendalx.pt/events/event/maes/";
+
+        cache
+            .put(&CachedPage::new(link, "a".repeat(100), None, None))
+            .await;
+        cache
+            .put(&CachedPage::new(link, "a".repeat(10), None, None))
+            .await;
+        cache
+            .put(&CachedPage::new(
+                "https://www.agendalx.pt/events/event/31-mulheres/",
+                "a".repeat(50),
+                None,
+                None,
+            ))
+            .await;
+
+        let scanned_size: u64 = cache.scan().await.iter().map(|(_, size, _)| size).sum();
+
+        assert_eq!(*cache.size_bytes.lock().await, Some(scanned_size));
+    }
+}
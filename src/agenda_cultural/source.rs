@@ -0,0 +1,23 @@
+use crate::agenda_cultural::api::APIError;
+use crate::agenda_cultural::model::{Category, Event};
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+use std::future::Future;
+
+/// A website listing upcoming cultural events (e.g. agendalx), from which the pipeline gathers
+/// what to post.
+pub trait EventSource {
+    /**
+    Returns events grouped by the month they start in, in ascending order
+    * event_limit: how many events to retrieve overall. If not specified, will retrieve everything
+    */
+    fn get_events_by_month(
+        &self,
+        category: &Category,
+        event_limit: Option<i32>,
+    ) -> impl Future<Output = Result<BTreeMap<NaiveDate, Vec<Event>>, APIError>> + Send;
+
+    /// Scrapes a single event directly off its page, for events no longer
+    /// returned by [`EventSource::get_events_by_month`].
+    fn scrape_event(&self, link: &str) -> impl Future<Output = Option<Event>> + Send;
+}
@@ -0,0 +1,461 @@
+use chrono::{NaiveTime, Timelike, Weekday};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::fmt::{Display, Formatter};
+use tracing::debug;
+
+lazy_static! {
+    static ref SEGMENT_SEPARATOR: Regex = Regex::new(r"\s*[;|\n]\s*").unwrap();
+    static ref DAYS_WITHOUT_COLON: Regex = Regex::new(r"^(.*?\D)\s+(\d.*)$").unwrap();
+    static ref LIST_SEPARATOR: Regex = Regex::new(r"\s*,\s*|\s+e\s+|\s*/\s*").unwrap();
+    static ref RANGE_SEPARATOR: Regex = Regex::new(r"\s+(?:a|à|às|as|até)\s+|\s*[-–—]\s*").unwrap();
+    static ref TIME: Regex =
+        Regex::new(r"^(\d{1,2})\s*(?:h|:|\.)\s*(\d{2})?\s*(?:m|min)?$").unwrap();
+}
+
+const WEEKDAY_LABELS: [&str; 7] = ["seg", "ter", "qua", "qui", "sex", "sáb", "dom"];
+
+/// Weekly showtimes parsed from the agendalx `string_times` (e.g. "sáb: 21h; dom: 17h")
+#[derive(Debug, Clone, PartialEq)]
+pub enum Timetable {
+    /// No times were announced
+    Empty,
+    /// Showtimes per weekday, ordered from Monday to Sunday
+    Weekly(Vec<(Weekday, Vec<ShowTime>)>),
+    /// Showtimes not tied to any weekday (e.g. "21h")
+    Daily(Vec<ShowTime>),
+    /// Fallback for text that could not be parsed (e.g. "vários horários")
+    Raw(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShowTime {
+    pub start: NaiveTime,
+    pub end: Option<NaiveTime>,
+}
+
+impl Timetable {
+    pub fn parse(times: &str) -> Self {
+        let times = times.trim().trim_end_matches(['.', ';']);
+
+        if times.is_empty() {
+            return Timetable::Empty;
+        }
+
+        Self::parse_segments(times).unwrap_or_else(|| {
+            debug!("Could not parse times '{}', keeping raw text", times);
+            Timetable::Raw(times.to_string())
+        })
+    }
+
+    fn parse_segments(times: &str) -> Option<Self> {
+        let mut weekly: Vec<(Weekday, Vec<ShowTime>)> = Vec::new();
+        let mut daily: Vec<ShowTime> = Vec::new();
+
+        for segment in SEGMENT_SEPARATOR.split(times) {
+            if segment.is_empty() {
+                continue;
+            }
+
+            match Self::split_days(segment) {
+                Some((days, show_times)) => {
+                    let show_times = Self::parse_show_times(show_times)?;
+
+                    for day in days {
+                        match weekly.iter_mut().find(|(weekday, _)| *weekday == day) {
+                            Some((_, existing)) => existing.extend(show_times.iter().cloned()),
+                            None => weekly.push((day, show_times.clone())),
+                        }
+                    }
+                }
+                None => daily.extend(Self::parse_show_times(segment)?),
+            }
+        }
+
+        match (weekly.is_empty(), daily.is_empty()) {
+            (false, true) => {
+                weekly.sort_by_key(|(weekday, _)| weekday.num_days_from_monday());
+                weekly
+                    .iter_mut()
+                    .for_each(|(_, show_times)| show_times.sort_by_key(|time| time.start));
+
+                Some(Timetable::Weekly(weekly))
+            }
+            (true, false) => Some(Timetable::Daily(daily)),
+            _ => None,
+        }
+    }
+
+    /// Splits a segment like "ter a sáb: 10h-18h" into its weekdays and the remaining times
+    fn split_days(segment: &str) -> Option<(Vec<Weekday>, &str)> {
+        if let Some((days, times)) = segment.split_once(':') {
+            if let Some(days) = parse_days(days) {
+                return Some((days, times));
+            }
+        }
+
+        let captures = DAYS_WITHOUT_COLON.captures(segment)?;
+        let days = parse_days(captures.get(1)?.as_str())?;
+
+        Some((days, captures.get(2)?.as_str()))
+    }
+
+    fn parse_show_times(times: &str) -> Option<Vec<ShowTime>> {
+        LIST_SEPARATOR
+            .split(times.trim())
+            .map(
+                |time| match RANGE_SEPARATOR.split(time).collect::<Vec<_>>()[..] {
+                    [start] => Some(ShowTime {
+                        start: parse_time(start)?,
+                        end: None,
+                    }),
+                    [start, end] => Some(ShowTime {
+                        start: parse_time(start)?,
+                        end: Some(parse_time(end)?),
+                    }),
+                    _ => None,
+                },
+            )
+            .collect()
+    }
+}
+
+fn parse_days(days: &str) -> Option<Vec<Weekday>> {
+    let days = normalize(days);
+
+    if matches!(
+        days.as_str(),
+        "todos os dias" | "diariamente" | "diario" | "de segunda a domingo"
+    ) {
+        return Some(all_weekdays().collect());
+    }
+
+    if days == "fim de semana" || days == "fins de semana" {
+        return Some(vec![Weekday::Sat, Weekday::Sun]);
+    }
+
+    let mut weekdays = Vec::new();
+
+    for day in LIST_SEPARATOR.split(&days) {
+        match RANGE_SEPARATOR.split(day).collect::<Vec<_>>()[..] {
+            [day] => weekdays.push(parse_weekday(day)?),
+            [from, to] => {
+                let (from, to) = (parse_weekday(from)?, parse_weekday(to)?);
+                let mut day = from;
+
+                weekdays.push(day);
+                while day != to {
+                    day = day.succ();
+                    weekdays.push(day);
+                }
+            }
+            _ => return None,
+        }
+    }
+
+    Some(weekdays)
+}
+
+fn parse_weekday(day: &str) -> Option<Weekday> {
+    match day.trim().trim_end_matches('.') {
+        "seg" | "segunda" => Some(Weekday::Mon),
+        "ter" | "terca" => Some(Weekday::Tue),
+        "qua" | "quarta" => Some(Weekday::Wed),
+        "qui" | "quinta" => Some(Weekday::Thu),
+        "sex" | "sexta" => Some(Weekday::Fri),
+        "sab" | "sabado" => Some(Weekday::Sat),
+        "dom" | "domingo" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn parse_time(time: &str) -> Option<NaiveTime> {
+    let captures = TIME.captures(time.trim())?;
+    let hour = captures.get(1)?.as_str().parse().ok()?;
+    let minute = captures
+        .get(2)
+        .map_or(Some(0), |minute| minute.as_str().parse().ok())?;
+
+    match hour {
+        24 => NaiveTime::from_hms_opt(0, minute, 0),
+        _ => NaiveTime::from_hms_opt(hour, minute, 0),
+    }
+}
+
+/// Lowercases and strips the accents and "-feira" suffixes used in Portuguese weekday names
+fn normalize(text: &str) -> String {
+    text.trim()
+        .to_lowercase()
+        .replace(['á', 'à', 'â'], "a")
+        .replace('ç', "c")
+        .replace("-feira", "")
+        .replace(" feira", "")
+}
+
+fn all_weekdays() -> impl Iterator<Item = Weekday> {
+    std::iter::successors(Some(Weekday::Mon), |day| Some(day.succ())).take(7)
+}
+
+fn weekday_label(weekday: Weekday) -> &'static str {
+    WEEKDAY_LABELS[weekday.num_days_from_monday() as usize]
+}
+
+fn format_show_times(show_times: &[ShowTime]) -> String {
+    show_times
+        .iter()
+        .map(ShowTime::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl Display for ShowTime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let format_time = |time: NaiveTime| match time.minute() {
+            0 => format!("{}h", time.hour()),
+            minute => format!("{}h{:02}", time.hour(), minute),
+        };
+
+        match self.end {
+            Some(end) => write!(f, "{}-{}", format_time(self.start), format_time(end)),
+            None => write!(f, "{}", format_time(self.start)),
+        }
+    }
+}
+
+impl Display for Timetable {
+    /// Groups consecutive weekdays sharing the same times (e.g. "ter a sáb: 10h-18h")
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Timetable::Empty => Ok(()),
+            Timetable::Raw(times) => write!(f, "{}", times),
+            Timetable::Daily(show_times) => write!(f, "{}", format_show_times(show_times)),
+            Timetable::Weekly(days) => {
+                let mut lines = Vec::new();
+                let mut index = 0;
+
+                while index < days.len() {
+                    let (first_day, show_times) = &days[index];
+                    let mut last = index;
+
+                    while last + 1 < days.len()
+                        && days[last + 1].0 == days[last].0.succ()
+                        && days[last + 1].1 == *show_times
+                    {
+                        last += 1;
+                    }
+
+                    let days_label = match last - index {
+                        0 => weekday_label(*first_day).to_string(),
+                        1 => format!(
+                            "{}, {}",
+                            weekday_label(*first_day),
+                            weekday_label(days[last].0)
+                        ),
+                        _ => format!(
+                            "{} a {}",
+                            weekday_label(*first_day),
+                            weekday_label(days[last].0)
+                        ),
+                    };
+
+                    lines.push(format!("{}: {}", days_label, format_show_times(show_times)));
+                    index = last + 1;
+                }
+
+                write!(f, "{}", lines.join("\n"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn at(hour: u32, minute: u32) -> ShowTime {
+        ShowTime {
+            start: time(hour, minute),
+            end: None,
+        }
+    }
+
+    fn between(start: (u32, u32), end: (u32, u32)) -> ShowTime {
+        ShowTime {
+            start: time(start.0, start.1),
+            end: Some(time(end.0, end.1)),
+        }
+    }
+
+    #[test_log::test]
+    fn should_parse_times_per_weekday() {
+        assert_eq!(
+            Timetable::parse("sáb: 21h; dom: 17h"),
+            Timetable::Weekly(vec![
+                (Weekday::Sat, vec![at(21, 0)]),
+                (Weekday::Sun, vec![at(17, 0)]),
+            ])
+        );
+    }
+
+    #[test_log::test]
+    fn should_parse_minutes() {
+        assert_eq!(
+            Timetable::parse("sex: 21h30; sáb: 21:30; dom: 17h00"),
+            Timetable::Weekly(vec![
+                (Weekday::Fri, vec![at(21, 30)]),
+                (Weekday::Sat, vec![at(21, 30)]),
+                (Weekday::Sun, vec![at(17, 0)]),
+            ])
+        );
+    }
+
+    #[test_log::test]
+    fn should_parse_weekday_ranges() {
+        assert_eq!(
+            Timetable::parse("ter a sáb: 10h-18h"),
+            Timetable::Weekly(vec![
+                (Weekday::Tue, vec![between((10, 0), (18, 0))]),
+                (Weekday::Wed, vec![between((10, 0), (18, 0))]),
+                (Weekday::Thu, vec![between((10, 0), (18, 0))]),
+                (Weekday::Fri, vec![between((10, 0), (18, 0))]),
+                (Weekday::Sat, vec![between((10, 0), (18, 0))]),
+            ])
+        );
+    }
+
+    #[test_log::test]
+    fn should_parse_weekday_ranges_wrapping_the_week() {
+        assert_eq!(
+            Timetable::parse("sex-seg: 22h"),
+            Timetable::Weekly(vec![
+                (Weekday::Mon, vec![at(22, 0)]),
+                (Weekday::Fri, vec![at(22, 0)]),
+                (Weekday::Sat, vec![at(22, 0)]),
+                (Weekday::Sun, vec![at(22, 0)]),
+            ])
+        );
+    }
+
+    #[test_log::test]
+    fn should_parse_weekday_lists_and_multiple_times() {
+        assert_eq!(
+            Timetable::parse("qua, qui e sex: 19h; sáb: 16h, 21h"),
+            Timetable::Weekly(vec![
+                (Weekday::Wed, vec![at(19, 0)]),
+                (Weekday::Thu, vec![at(19, 0)]),
+                (Weekday::Fri, vec![at(19, 0)]),
+                (Weekday::Sat, vec![at(16, 0), at(21, 0)]),
+            ])
+        );
+    }
+
+    #[test_log::test]
+    fn should_parse_full_weekday_names() {
+        assert_eq!(
+            Timetable::parse("Quinta-feira: 21h; Sábado e Domingo: 16h"),
+            Timetable::Weekly(vec![
+                (Weekday::Thu, vec![at(21, 0)]),
+                (Weekday::Sat, vec![at(16, 0)]),
+                (Weekday::Sun, vec![at(16, 0)]),
+            ])
+        );
+    }
+
+    #[test_log::test]
+    fn should_parse_time_ranges_written_in_portuguese() {
+        assert_eq!(
+            Timetable::parse("seg a sex: 10h às 18h"),
+            Timetable::parse("seg-sex: 10h-18h")
+        );
+    }
+
+    #[test_log::test]
+    fn should_parse_days_without_colon() {
+        assert_eq!(
+            Timetable::parse("sáb 21h; dom 17h"),
+            Timetable::parse("sáb: 21h; dom: 17h")
+        );
+    }
+
+    #[test_log::test]
+    fn should_parse_every_day_expressions() {
+        assert_eq!(
+            Timetable::parse("todos os dias: 10h-19h"),
+            Timetable::Weekly(
+                all_weekdays()
+                    .map(|weekday| (weekday, vec![between((10, 0), (19, 0))]))
+                    .collect()
+            )
+        );
+    }
+
+    #[test_log::test]
+    fn should_merge_repeated_weekdays() {
+        assert_eq!(
+            Timetable::parse("sáb: 21h; sáb e dom: 16h"),
+            Timetable::Weekly(vec![
+                (Weekday::Sat, vec![at(16, 0), at(21, 0)]),
+                (Weekday::Sun, vec![at(16, 0)]),
+            ])
+        );
+    }
+
+    #[test_log::test]
+    fn should_parse_times_without_weekdays() {
+        assert_eq!(
+            Timetable::parse("21h30"),
+            Timetable::Daily(vec![at(21, 30)])
+        );
+        assert_eq!(
+            Timetable::parse("10h-13h, 15h-19h"),
+            Timetable::Daily(vec![between((10, 0), (13, 0)), between((15, 0), (19, 0))])
+        );
+    }
+
+    #[test_log::test]
+    fn when_times_are_empty_should_be_empty() {
+        assert_eq!(Timetable::parse(""), Timetable::Empty);
+        assert_eq!(Timetable::parse("  "), Timetable::Empty);
+    }
+
+    #[test_log::test]
+    fn when_times_are_not_parseable_should_keep_raw_text() {
+        assert_eq!(
+            Timetable::parse("vários horários"),
+            Timetable::Raw("vários horários".to_string())
+        );
+        assert_eq!(
+            Timetable::parse("sáb: consultar programa"),
+            Timetable::Raw("sáb: consultar programa".to_string())
+        );
+        assert_eq!(
+            Timetable::parse("sáb: 21h; 17h"),
+            Timetable::Raw("sáb: 21h; 17h".to_string())
+        );
+    }
+
+    #[test_log::test]
+    fn should_display_grouped_weekdays() {
+        assert_eq!(
+            Timetable::parse("ter: 10h-18h; qua: 10h-18h; qui: 10h-18h; sáb: 21h; dom: 17h")
+                .to_string(),
+            "ter a qui: 10h-18h\nsáb: 21h\ndom: 17h"
+        );
+        assert_eq!(
+            Timetable::parse("sex: 21h30; sáb: 21h30; dom: 17h").to_string(),
+            "sex, sáb: 21h30\ndom: 17h"
+        );
+    }
+
+    #[test_log::test]
+    fn should_display_raw_text_as_is() {
+        assert_eq!(
+            Timetable::parse("vários horários").to_string(),
+            "vários horários"
+        );
+    }
+}
@@ -1,26 +1,436 @@
-use crate::agenda_cultural::model::Event;
-use crate::config::model::EmojiConfig;
-use crate::discord::api::{DiscordAPI, EventsThread};
-use chrono::NaiveDate;
-use serenity::all::{ChannelId, GuildChannel, Message, PartialGuild};
-use std::collections::BTreeMap;
-use tracing::{debug, trace};
+use crate::agenda_cultural::markdown::escape_link_label;
+use crate::agenda_cultural::model::{Category, Event, EventStatus};
+use crate::config::model::{Config, EmojiConfig};
+use crate::dedup::SharedEvent;
+use crate::discord::api::{
+    has_status, month_to_portuguese_display, thread_name_to_month, DiscordAPI, EventsThread,
+    STATUS_FIELD,
+};
+use crate::discord::backend::ChatBackend;
+use crate::discord::sent_events::{EventReference, SentEvent};
+use crate::posted_events::PostedEvent;
+use chrono::{Datelike, NaiveDate};
+use serenity::all::{ChannelId, GuildChannel, GuildId, Message};
+use std::collections::{BTreeMap, HashSet};
+use tracing::{debug, info, trace, warn};
+
+/// Edits the posted messages of events that changed since they were sent.
+/// Users interested in an event that got cancelled or postponed are notified
+pub async fn update_changed_events(
+    discord: &DiscordAPI<impl ChatBackend>,
+    events_by_month: &BTreeMap<NaiveDate, Vec<Event>>,
+    channel_id: ChannelId,
+    config: &Config,
+    highlight_free: bool,
+    save_for_later_emoji: char,
+) -> usize {
+    let sent_events = discord.sent_events.of_channel(channel_id);
+    let mut updated_count = 0;
+
+    for event in events_by_month.values().flatten() {
+        let Some(sent_event) = sent_events.get(&event.link) else {
+            continue;
+        };
+
+        discord
+            .sent_events
+            .set_last_date(channel_id, &event.link, event.occurring_at.last_day());
+
+        let ticket_shop_url = config.ticket_shop_url(&event.venue);
+        let fingerprint = discord.event_fingerprint(
+            event.clone(),
+            ticket_shop_url.clone(),
+            &config.ticket_shop_icon_url,
+            highlight_free,
+        );
+
+        if fingerprint == Some(sent_event.fingerprint) {
+            trace!("Event '{}' is unchanged", event.title);
+            continue;
+        }
+
+        let Some(mut message) = discord.get_sent_event_message(sent_event).await else {
+            continue;
+        };
+
+        let changed_fields = discord
+            .update_event_message(
+                &mut message,
+                event.clone(),
+                ticket_shop_url,
+                &config.ticket_shop_icon_url,
+                highlight_free,
+            )
+            .await;
+
+        discord.sent_events.refresh(channel_id, &message);
+
+        let Ok(changed_fields) = changed_fields else {
+            continue;
+        };
+
+        if changed_fields.is_empty() {
+            continue;
+        }
+
+        updated_count += 1;
+
+        if event.status != EventStatus::Scheduled
+            && changed_fields.iter().any(|field| field == STATUS_FIELD)
+        {
+            discord
+                .notify_status_change(
+                    &message,
+                    event.status,
+                    &config.voting_emojis,
+                    save_for_later_emoji,
+                )
+                .await;
+        }
+    }
+
+    updated_count
+}
+
+/// Marks posted events that are no longer listed although they can't have ended yet,
+/// i.e. those lasting until `today` or later, or in threads of months after `today`'s
+/// when their last day isn't known. Interested users are notified
+pub async fn mark_removed_events(
+    discord: &DiscordAPI<impl ChatBackend>,
+    guild_id: GuildId,
+    listed_links: &HashSet<String>,
+    channel_id: ChannelId,
+    today: NaiveDate,
+    config: &Config,
+    save_for_later_emoji: char,
+) -> usize {
+    let current_month = today.with_day(1).unwrap_or(today);
+    let threads = discord.get_channel_threads(guild_id, channel_id).await;
+    let mut removed_count = 0;
+
+    let upcoming_threads: HashSet<ChannelId> = threads
+        .iter()
+        .filter(|thread| {
+            thread_name_to_month(&thread.name).is_some_and(|month| month > current_month)
+        })
+        .map(|thread| thread.id)
+        .collect();
+
+    let unlisted_events = discord
+        .sent_events
+        .of_channel(channel_id)
+        .into_values()
+        .filter(|sent_event| {
+            let can_have_ended = match sent_event.last_date {
+                Some(last_date) => last_date < today,
+                None => !upcoming_threads.contains(&sent_event.thread_id),
+            };
+
+            !can_have_ended && !listed_links.contains(&sent_event.link)
+        });
+
+    for sent_event in unlisted_events {
+        let Some(mut message) = discord.get_sent_event_message(&sent_event).await else {
+            continue;
+        };
+
+        if has_status(&message) {
+            continue;
+        }
+
+        if discord
+            .mark_event_message_status(&mut message, EventStatus::Removed)
+            .await
+            .is_err()
+        {
+            continue;
+        }
+
+        discord.sent_events.refresh(channel_id, &message);
+        removed_count += 1;
+
+        let notified_count = discord
+            .notify_status_change(
+                &message,
+                EventStatus::Removed,
+                &config.voting_emojis,
+                save_for_later_emoji,
+            )
+            .await;
+
+        info!(
+            "Notified {} users that '{}' was removed",
+            notified_count, sent_event.link
+        );
+    }
+
+    removed_count
+}
+
+/// Links each event owned by another channel from the month thread it would be posted in
+pub async fn send_shared_event_references(
+    discord: &DiscordAPI<impl ChatBackend>,
+    guild_id: GuildId,
+    shared_events: BTreeMap<NaiveDate, Vec<SharedEvent>>,
+    channel_id: ChannelId,
+) -> usize {
+    let threads = discord.get_channel_threads(guild_id, channel_id).await;
+    let mut sent_count = 0;
+
+    for (date, shared_events) in shared_events {
+        let thread = discord.get_date_thread(&threads, channel_id, date).await;
+
+        for shared_event in shared_events {
+            let event = &shared_event.event;
+
+            if discord
+                .sent_events
+                .is_in_thread(channel_id, thread.thread_id, &event.link)
+            {
+                trace!("Event '{}' already in this channel", event.title);
+                continue;
+            }
+
+            let Some(owner_event) = discord
+                .sent_events
+                .get(shared_event.owner_channel_id, &event.link)
+            else {
+                debug!("Event '{}' not posted by its owner yet", event.title);
+                continue;
+            };
+
+            if let Ok(message) = discord
+                .send_event_reference(
+                    thread.thread_id,
+                    event,
+                    &owner_event.message_link(guild_id),
+                    "está publicado em",
+                )
+                .await
+            {
+                record_event_reference(discord, &message, channel_id);
+                sent_count += 1;
+            }
+        }
+    }
+
+    sent_count
+}
+
+/// Links events from the threads of the later months they keep running in
+pub async fn send_ongoing_event_references(
+    discord: &DiscordAPI<impl ChatBackend>,
+    guild_id: GuildId,
+    ongoing_events: BTreeMap<NaiveDate, Vec<Event>>,
+    channel_id: ChannelId,
+) -> usize {
+    let threads = discord.get_channel_threads(guild_id, channel_id).await;
+    let sent_events = discord.sent_events.of_channel(channel_id);
+    let mut sent_count = 0;
+
+    for (month, events) in ongoing_events {
+        let thread = discord.get_date_thread(&threads, channel_id, month).await;
+
+        for event in events {
+            let Some(sent_event) = sent_events.get(&event.link) else {
+                debug!("Ongoing event '{}' was not posted here", event.title);
+                continue;
+            };
+
+            if discord
+                .sent_events
+                .is_in_thread(channel_id, thread.thread_id, &event.link)
+            {
+                continue;
+            }
+
+            let note = format!(
+                "continua em cena{}, publicado em",
+                display_until(event.occurring_at.last_date)
+            );
+
+            if let Ok(message) = discord
+                .send_event_reference(
+                    thread.thread_id,
+                    &event,
+                    &sent_event.message_link(guild_id),
+                    &note,
+                )
+                .await
+            {
+                record_event_reference(discord, &message, channel_id);
+                sent_count += 1;
+            }
+        }
+    }
+
+    sent_count
+}
+
+fn record_event_reference(
+    discord: &DiscordAPI<impl ChatBackend>,
+    message: &Message,
+    channel_id: ChannelId,
+) {
+    if let Some(reference) = EventReference::from_message(message, channel_id) {
+        discord.sent_events.record_reference(reference);
+    }
+}
+
+/// Keeps a pinned message in each upcoming month's thread listing the events still running in it
+pub async fn update_ongoing_events_roll_ups(
+    discord: &DiscordAPI<impl ChatBackend>,
+    guild_id: GuildId,
+    ongoing_events: BTreeMap<NaiveDate, Vec<Event>>,
+    channel_id: ChannelId,
+    today: NaiveDate,
+) -> usize {
+    let current_month = today.with_day(1).unwrap_or(today);
+    let threads = discord.get_channel_threads(guild_id, channel_id).await;
+    let sent_events = discord.sent_events.of_channel(channel_id);
+    let mut thread_lines: BTreeMap<ChannelId, Vec<String>> = threads
+        .iter()
+        .filter(|thread| {
+            thread_name_to_month(&thread.name).is_some_and(|month| month >= current_month)
+        })
+        .map(|thread| (thread.id, Vec::new()))
+        .collect();
+
+    for (month, events) in ongoing_events {
+        let thread = discord.get_date_thread(&threads, channel_id, month).await;
+        let lines = events
+            .iter()
+            .map(|event| {
+                let link = sent_events
+                    .get(&event.link)
+                    .map_or(event.link.clone(), |sent_event| {
+                        sent_event.message_link(guild_id)
+                    });
+
+                format!(
+                    "• [{}]({}){}",
+                    escape_link_label(&event.title),
+                    link,
+                    display_until(event.occurring_at.last_date)
+                )
+            })
+            .collect();
+
+        thread_lines.insert(thread.thread_id, lines);
+    }
+
+    let mut updated_count = 0;
+
+    for (thread_id, lines) in thread_lines {
+        if let Ok(true) = discord
+            .update_ongoing_events_roll_up(thread_id, &lines)
+            .await
+        {
+            updated_count += 1;
+        }
+    }
+
+    updated_count
+}
+
+/// E.g. " até 12 junho"
+fn display_until(last_date: Option<NaiveDate>) -> String {
+    last_date.map_or(String::new(), |last_date| {
+        format!(
+            " até {} {}",
+            last_date.day(),
+            month_to_portuguese_display(&last_date).to_lowercase()
+        )
+    })
+}
+
+/// The events that were sent to the category's threads, linked to their messages
+pub fn get_posted_events(
+    discord: &DiscordAPI<impl ChatBackend>,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    events: Vec<Event>,
+) -> Vec<PostedEvent> {
+    let sent_events = discord.sent_events.of_channel(channel_id);
+
+    events
+        .into_iter()
+        .filter_map(|event| {
+            let sent_event = sent_events.get(&event.link)?;
+
+            Some(PostedEvent {
+                message_link: sent_event.message_link(guild_id),
+                event,
+            })
+        })
+        .collect()
+}
+
+/// Rebuilds the channel's sent events and references from its threads' history,
+/// unless they're indexed
+pub async fn index_sent_events(
+    discord: &DiscordAPI<impl ChatBackend>,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    category: &Category,
+) {
+    if discord.sent_events.is_indexed(channel_id) {
+        return;
+    }
+
+    let threads = discord.get_channel_threads(guild_id, channel_id).await;
+    let mut sent_events = Vec::new();
+    let mut references = Vec::new();
+
+    for thread in &threads {
+        for message in discord.get_all_messages(thread.id).await {
+            if let Some(sent_event) = SentEvent::from_message(&message, channel_id, &category.slug)
+            {
+                sent_events.push(sent_event);
+            } else if let Some(reference) = EventReference::from_message(&message, channel_id) {
+                references.push(reference);
+            }
+        }
+    }
+
+    info!(
+        "Indexed {} sent events and {} references of {} from {} threads",
+        sent_events.len(),
+        references.len(),
+        category,
+        threads.len()
+    );
+    discord
+        .sent_events
+        .index_channel(channel_id, sent_events, references);
+}
 
 pub async fn filter_new_events_by_thread(
-    discord: &DiscordAPI,
-    guild: &PartialGuild,
+    discord: &DiscordAPI<impl ChatBackend>,
+    guild_id: GuildId,
     events_by_month: BTreeMap<NaiveDate, Vec<Event>>,
     channel_id: ChannelId,
 ) -> BTreeMap<EventsThread, Vec<Event>> {
     trace!("Getting threads");
-    let threads = discord.get_channel_threads(guild, channel_id).await;
+    let threads = discord.get_channel_threads(guild_id, channel_id).await;
+    let thread_ids = threads.iter().map(|thread| thread.id).collect();
+    let forgotten_count = discord
+        .sent_events
+        .forget_missing_threads(channel_id, &thread_ids);
+
+    // Their events get posted again, as they would if the threads were never indexed
+    if forgotten_count > 0 {
+        warn!(
+            "Forgot {} sent events and references of deleted threads",
+            forgotten_count
+        );
+    }
 
     trace!("Sorting threads by month");
     let threads_by_month =
         get_threads_by_month(discord, channel_id, &events_by_month, &threads).await;
 
-    trace!("Getting sent events");
-    let sent_events = get_sent_events(discord, &threads).await;
+    let sent_events = discord.sent_events.links(channel_id);
 
     threads_by_month
         .into_iter()
@@ -36,25 +446,8 @@ pub async fn filter_new_events_by_thread(
         .collect()
 }
 
-async fn get_sent_events(discord: &DiscordAPI, threads: &[GuildChannel]) -> Vec<String> {
-    let mut sent_events = Vec::new();
-
-    for thread in threads.iter() {
-        let mut thread_events = discord.get_event_urls_sent(thread.id).await;
-
-        sent_events.append(&mut thread_events);
-
-        debug!(
-            "Thread '{}' has {} sent events",
-            thread.name,
-            sent_events.len()
-        );
-    }
-    sent_events
-}
-
 async fn get_threads_by_month(
-    discord: &DiscordAPI,
+    discord: &DiscordAPI<impl ChatBackend>,
     channel_id: ChannelId,
     events: &BTreeMap<NaiveDate, Vec<Event>>,
     active_threads: &[GuildChannel],
@@ -73,7 +466,7 @@ async fn get_threads_by_month(
 }
 
 pub async fn add_feature_reactions(
-    discord: &DiscordAPI,
+    discord: &DiscordAPI<impl ChatBackend>,
     message: &Message,
     voting_emojis: &[EmojiConfig; 5],
     save_for_later_emoji: char,
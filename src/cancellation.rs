@@ -0,0 +1,68 @@
+use tokio::sync::watch;
+
+/// Asks the passes to stop between events or threads, e.g. once the daemon is shutting down
+#[derive(Debug, Clone)]
+pub struct Cancellation {
+    cancelled: watch::Receiver<bool>,
+}
+
+impl Cancellation {
+    /// Along with the sender that cancels it, by sending `true`
+    pub fn channel() -> (watch::Sender<bool>, Self) {
+        let (sender, cancelled) = watch::channel(false);
+
+        (sender, Self { cancelled })
+    }
+
+    /// For one-off runs, which always finish
+    pub fn never() -> Self {
+        Self::channel().1
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.cancelled.borrow()
+    }
+
+    /// Completes once cancelled, or never if the sender is gone without cancelling
+    pub async fn cancelled(&mut self) {
+        if self
+            .cancelled
+            .wait_for(|cancelled| *cancelled)
+            .await
+            .is_err()
+        {
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::time::timeout;
+
+    #[test_log::test(tokio::test)]
+    async fn should_be_cancelled_once_sent() {
+        let (cancel, mut cancellation) = Cancellation::channel();
+
+        assert!(!cancellation.is_cancelled());
+
+        cancel.send(true).unwrap();
+
+        assert!(cancellation.is_cancelled());
+        assert!(timeout(Duration::from_secs(1), cancellation.cancelled())
+            .await
+            .is_ok());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn when_never_cancelled_should_not_complete() {
+        let mut cancellation = Cancellation::never();
+
+        assert!(!cancellation.is_cancelled());
+        assert!(timeout(Duration::from_millis(50), cancellation.cancelled())
+            .await
+            .is_err());
+    }
+}
@@ -1,36 +1,309 @@
-use crate::config::model::{Config, DebugConfig, EmojiConfig};
+use crate::agenda_cultural::api::PageFetchLimits;
+use crate::agenda_cultural::model::Category;
+use crate::agenda_cultural::page_cache::PageCache;
+use crate::config::model::{
+    CategoryConfig, Config, DaemonConfig, DebugConfig, EmojiConfig, FreeEventsPolicy,
+    LongRunningEventsPolicy, SharedEventsConfig,
+};
+use crate::config::tags::{normalize, TagFilter, TagLabels};
+use crate::config::ticket_shops::VenueTicketShops;
 use serenity::all::ChannelId;
 use std::collections::HashMap;
 use std::env;
+use std::time::Duration;
+use tracing::warn;
 
 pub fn load_config() -> Config {
-    let teatro_channel_id: ChannelId = load_channel_id_config("DISCORD_TEATRO_CHANNEL_ID");
-    let artes_channel_id: ChannelId = load_channel_id_config("DISCORD_ARTES_CHANNEL_ID");
+    let mut categories: Vec<CategoryConfig> = load_categories_config("CATEGORIES");
+    let free_events = load_free_events_config("FREE_EVENTS");
+    let tag_filters = load_tag_filters_config("TAG_FILTERS");
+    categories.iter_mut().for_each(|category_config| {
+        category_config.free_events = free_events
+            .get(&category_config.category.slug)
+            .cloned()
+            .unwrap_or_default();
+        category_config.tag_filter = tag_filters
+            .get(&category_config.category.slug)
+            .cloned()
+            .unwrap_or_default();
+    });
+    let shared_events = SharedEventsConfig {
+        owner_priority: load_list_config("SHARED_EVENTS_PRIORITY"),
+        link_in_other_channels: load_bool_config("SHARED_EVENTS_LINK", false),
+    };
+    sort_categories_by_priority(&mut categories, &shared_events.owner_priority);
     let voting_emojis: [EmojiConfig; 5] = load_voting_emojis_config("VOTING_EMOJIS");
     let gather_new_events: bool = load_bool_config("GATHER_NEW_EVENTS", true);
-    let venue_ticket_shop_url: HashMap<String, String> =
-        load_venue_ticket_shop_config("VENUE_TICKET_SHOP_URLS");
+    let venue_ticket_shops = load_venue_ticket_shop_config("VENUE_TICKET_SHOP_URLS");
     let ticket_shop_icon_url =
         env::var("TICKET_SHOP_ICON_URL").expect("TICKET_SHOP_ICON_URL not set");
+    let default_fetch_limits = PageFetchLimits::default();
+    let page_fetch_limits = PageFetchLimits {
+        max_concurrent_fetches: load_i32_config("AGENDALX_MAX_CONCURRENT_FETCHES")
+            .map_or(default_fetch_limits.max_concurrent_fetches, |limit| {
+                limit.max(1) as usize
+            }),
+        host_delay: load_i32_config("AGENDALX_FETCH_DELAY_MS")
+            .map_or(default_fetch_limits.host_delay, |delay| {
+                Duration::from_millis(delay.max(0) as u64)
+            }),
+    };
 
     let debug_config = DebugConfig {
         clear_channel: load_bool_config("DEBUG_CLEAR_CHANNEL", false),
         exit_after_clearing: load_bool_config("DEBUG_EXIT_AFTER_CLEARING", false),
         skip_sending: load_bool_config("DEBUG_SKIP_SENDING", false),
         skip_feature_reactions: load_bool_config("DEBUG_SKIP_FEATURE_REACTIONS", false),
-        skip_artes: load_bool_config("DEBUG_SKIP_ARTES", false),
+        skip_categories: load_skip_categories_config("DEBUG_SKIP_CATEGORIES"),
         event_limit: load_i32_config("DEBUG_EVENT_LIMIT"),
     };
 
     Config {
         debug_config,
-        teatro_channel_id,
-        artes_channel_id,
+        categories,
         voting_emojis,
         gather_new_events,
-        venue_ticket_shop_url,
+        venue_ticket_shops,
         ticket_shop_icon_url,
+        page_fetch_limits,
+        page_cache: load_page_cache_config("PAGE_CACHE_DIR"),
+        shared_events,
+        long_running_events: load_long_running_events_config("LONG_RUNNING_EVENTS"),
+        tag_labels: load_tag_labels_config("TAG_LABELS"),
+        daemon: load_daemon_config("DAEMON_MODE"),
+    }
+}
+
+/// Also skips Artes when the `DEBUG_SKIP_ARTES` flag of older setups is set
+fn load_skip_categories_config(name: &str) -> Vec<String> {
+    let skip_artes = env::var("DEBUG_SKIP_ARTES")
+        .ok()
+        .map(|_| load_bool_config("DEBUG_SKIP_ARTES", false));
+
+    if skip_artes.is_some() {
+        warn!(
+            "DEBUG_SKIP_ARTES is deprecated, add 'artes' to {} instead",
+            name
+        );
+    }
+
+    merge_skip_artes_config(load_list_config(name), skip_artes)
+}
+
+fn merge_skip_artes_config(
+    mut skip_categories: Vec<String>,
+    skip_artes: Option<bool>,
+) -> Vec<String> {
+    if skip_artes == Some(true) && !skip_categories.iter().any(|slug| slug == "artes") {
+        skip_categories.push("artes".to_string());
+    }
+
+    skip_categories
+}
+
+/// Intervals are in minutes, every 6 hours for events and every 10 minutes for reactions by default
+fn load_daemon_config(name: &str) -> Option<DaemonConfig> {
+    if !load_bool_config(name, false) {
+        return None;
     }
+
+    let load_minutes = |name: &str, default: i32| {
+        Duration::from_secs(load_i32_config(name).unwrap_or(default).max(1) as u64 * 60)
+    };
+
+    Some(DaemonConfig {
+        events_interval: load_minutes("DAEMON_EVENTS_INTERVAL_MINUTES", 6 * 60),
+        reactions_interval: load_minutes("DAEMON_REACTIONS_INTERVAL_MINUTES", 10),
+    })
+}
+
+/// One of `start` (default), `cross-post` or `roll-up`
+fn load_long_running_events_config(name: &str) -> LongRunningEventsPolicy {
+    match env::var(name) {
+        Ok(config) => parse_long_running_events_config(name, &config),
+        Err(_) => LongRunningEventsPolicy::default(),
+    }
+}
+
+fn parse_long_running_events_config(name: &str, config: &str) -> LongRunningEventsPolicy {
+    match config.trim() {
+        "start" => LongRunningEventsPolicy::StartMonthOnly,
+        "cross-post" => LongRunningEventsPolicy::CrossPost,
+        "roll-up" => LongRunningEventsPolicy::RollUp,
+        unknown => panic!(
+            "{} has an invalid policy. Expected 'start', 'cross-post' or 'roll-up' but got: {}",
+            name, unknown
+        ),
+    }
+}
+
+/// Moves the categories in `priority` to the front, in that order, so they own shared events
+fn sort_categories_by_priority(categories: &mut [CategoryConfig], priority: &[String]) {
+    categories.sort_by_key(|category_config| {
+        priority
+            .iter()
+            .position(|slug| *slug == category_config.category.slug)
+            .unwrap_or(priority.len())
+    });
+}
+
+/// Enabled by setting the directory to cache pages in. Pages are kept for
+/// `PAGE_CACHE_TTL_HOURS` (a week by default) and up to `PAGE_CACHE_MAX_MB` (100 MB by default).
+fn load_page_cache_config(name: &str) -> Option<PageCache> {
+    let directory = env::var(name).ok()?;
+    let ttl_hours = load_i32_config("PAGE_CACHE_TTL_HOURS").unwrap_or(24 * 7);
+    let max_size_mb = load_i32_config("PAGE_CACHE_MAX_MB").unwrap_or(100);
+
+    Some(PageCache::new(
+        directory,
+        Duration::from_secs(ttl_hours.max(0) as u64 * 60 * 60),
+        max_size_mb.max(0) as u64 * 1024 * 1024,
+    ))
+}
+
+/// Categories are semi-colon separated, each in the `slug:channel ID:display name` format
+/// (e.g. `teatro:123:Teatro;danca:234:Dança`).
+///
+/// When not set, falls back to the Teatro and Artes channels of older setups
+/// (`DISCORD_TEATRO_CHANNEL_ID` and `DISCORD_ARTES_CHANNEL_ID`).
+fn load_categories_config(name: &str) -> Vec<CategoryConfig> {
+    match env::var(name) {
+        Ok(config) => parse_categories_config(name, &config),
+        Err(_) => Vec::from([
+            CategoryConfig {
+                category: Category::new("artes", "Artes"),
+                channel_id: load_channel_id_config("DISCORD_ARTES_CHANNEL_ID"),
+                free_events: FreeEventsPolicy::default(),
+                tag_filter: TagFilter::default(),
+            },
+            CategoryConfig {
+                category: Category::new("teatro", "Teatro"),
+                channel_id: load_channel_id_config("DISCORD_TEATRO_CHANNEL_ID"),
+                free_events: FreeEventsPolicy::default(),
+                tag_filter: TagFilter::default(),
+            },
+        ]),
+    }
+}
+
+fn parse_categories_config(name: &str, config: &str) -> Vec<CategoryConfig> {
+    config
+        .split(";")
+        .filter(|category| !category.trim().is_empty())
+        .map(|category| {
+            let mut parts = category.trim().splitn(3, ":");
+            let slug = parts.next().unwrap_or_default().trim();
+            let channel_id = parts.next().unwrap_or_else(|| {
+                panic!(
+                    "{} must be in the slug:channel ID:display name format but got: {}",
+                    name, category
+                )
+            });
+            let display_name = parts.next().map(str::trim).unwrap_or(slug);
+
+            CategoryConfig {
+                category: Category::new(slug, display_name),
+                channel_id: channel_id.trim().parse().unwrap_or_else(|_| {
+                    panic!(
+                        "{} has an invalid Discord channel ID for '{}': {}",
+                        name, slug, channel_id
+                    )
+                }),
+                free_events: FreeEventsPolicy::default(),
+                tag_filter: TagFilter::default(),
+            }
+        })
+        .collect()
+}
+
+/// Semi-colon separated `slug:policy` pairs, where policy is either `all`, `only` or `highlight`
+/// (e.g. `teatro:highlight;musica:only`). Categories not listed post all events.
+fn load_free_events_config(name: &str) -> HashMap<String, FreeEventsPolicy> {
+    parse_free_events_config(name, &env::var(name).unwrap_or_default())
+}
+
+fn parse_free_events_config(name: &str, config: &str) -> HashMap<String, FreeEventsPolicy> {
+    config
+        .split(";")
+        .filter(|category| !category.trim().is_empty())
+        .map(|category| {
+            let (slug, policy) = category.trim().split_once(":").unwrap_or_else(|| {
+                panic!(
+                    "{} must be in the slug:policy format but got: {}",
+                    name, category
+                )
+            });
+
+            let policy = match policy.trim() {
+                "all" => FreeEventsPolicy::All,
+                "only" => FreeEventsPolicy::OnlyFree,
+                "highlight" => FreeEventsPolicy::HighlightFree,
+                unknown => panic!(
+                    "{} has an invalid policy for '{}'. Expected 'all', 'only' or 'highlight' but got: {}",
+                    name, slug, unknown
+                ),
+            };
+
+            (slug.trim().to_string(), policy)
+        })
+        .collect()
+}
+
+/// Semi-colon separated `slug:tags` pairs, where tags are comma-separated and the ones
+/// prefixed by `-` are excluded (e.g. `teatro:estreia,festival;danca:-crianças`).
+/// Categories not listed post events with any tags.
+fn load_tag_filters_config(name: &str) -> HashMap<String, TagFilter> {
+    parse_tag_filters_config(name, &env::var(name).unwrap_or_default())
+}
+
+fn parse_tag_filters_config(name: &str, config: &str) -> HashMap<String, TagFilter> {
+    config
+        .split(";")
+        .filter(|category| !category.trim().is_empty())
+        .map(|category| {
+            let (slug, tags) = category.trim().split_once(":").unwrap_or_else(|| {
+                panic!(
+                    "{} must be in the slug:tags format but got: {}",
+                    name, category
+                )
+            });
+            let mut tag_filter = TagFilter::default();
+
+            for tag in tags.split(",").filter(|tag| !tag.trim().is_empty()) {
+                match tag.trim().strip_prefix("-") {
+                    Some(excluded) => tag_filter.excluded.push(normalize(excluded)),
+                    None => tag_filter.included.push(normalize(tag)),
+                }
+            }
+
+            (slug.trim().to_string(), tag_filter)
+        })
+        .collect()
+}
+
+/// Semi-colon separated `tag:emoji` pairs (e.g. `gratuito:🆓;estreia:✨;festival:🎪`)
+fn load_tag_labels_config(name: &str) -> TagLabels {
+    parse_tag_labels_config(name, &env::var(name).unwrap_or_default())
+}
+
+fn parse_tag_labels_config(name: &str, config: &str) -> TagLabels {
+    let mut tag_labels = TagLabels::new();
+
+    config
+        .split(";")
+        .filter(|label| !label.trim().is_empty())
+        .for_each(|label| {
+            let (tag, emoji) = label.split_once(":").unwrap_or_else(|| {
+                panic!(
+                    "{} must be in the tag:emoji format but got: {}",
+                    name, label
+                )
+            });
+
+            tag_labels.add(tag, emoji);
+        });
+
+    tag_labels
 }
 
 fn load_channel_id_config(name: &str) -> ChannelId {
@@ -40,21 +313,32 @@ fn load_channel_id_config(name: &str) -> ChannelId {
         .unwrap_or_else(|_| panic!("{} is not a valid Discord channel ID", name))
 }
 
-pub fn load_venue_ticket_shop_config(name: &str) -> HashMap<String, String> {
+pub fn load_venue_ticket_shop_config(name: &str) -> VenueTicketShops {
     let config = env::var(name).unwrap_or_else(|_| panic!("{} must be set.", name));
 
-    let venue_ticket_shop: Vec<&str> = config.split(";").collect();
+    parse_venue_ticket_shop_config(name, &config)
+}
 
-    venue_ticket_shop
-        .iter()
-        .map(|v| {
-            let venue_to_ticket_shop = v
-                .split_once(":")
-                .expect("Emojis must be comma-separated in the Name:ID format");
+/// Semicolon-separated `Alias|Other alias:URL` entries, where aliases are venue slugs or names
+fn parse_venue_ticket_shop_config(name: &str, config: &str) -> VenueTicketShops {
+    let mut venue_ticket_shops = VenueTicketShops::new();
 
-            (venue_to_ticket_shop.0.into(), venue_to_ticket_shop.1.into())
-        })
-        .collect()
+    config
+        .split(";")
+        .filter(|entry| !entry.trim().is_empty())
+        .for_each(|entry| {
+            let (aliases, url) = entry.split_once(":").unwrap_or_else(|| {
+                panic!(
+                    "{} entries must be in the Venue|Alias:URL format but got: {}",
+                    name, entry
+                )
+            });
+            let aliases: Vec<&str> = aliases.split("|").collect();
+
+            venue_ticket_shops.add(&aliases, url);
+        });
+
+    venue_ticket_shops
 }
 
 pub fn load_voting_emojis_config(name: &str) -> [EmojiConfig; 5] {
@@ -95,6 +379,15 @@ fn load_bool_config(name: &str, default: bool) -> bool {
         })
 }
 
+fn load_list_config(name: &str) -> Vec<String> {
+    env::var(name)
+        .unwrap_or_default()
+        .split(";")
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
 fn load_i32_config(name: &str) -> Option<i32> {
     match env::var(name) {
         Ok(value) => {
@@ -105,3 +398,160 @@ fn load_i32_config(name: &str) -> Option<i32> {
         Err(_) => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agenda_cultural::model::Venue;
+
+    #[test_log::test]
+    fn should_parse_categories_in_order() {
+        let categories = parse_categories_config("CATEGORIES", "teatro:123:Teatro;danca:234:Dança");
+
+        assert_eq!(categories.len(), 2);
+        assert_eq!(categories[0].category, Category::new("teatro", "Teatro"));
+        assert_eq!(categories[0].channel_id, ChannelId::new(123));
+        assert_eq!(categories[1].category, Category::new("danca", "Dança"));
+        assert_eq!(categories[1].channel_id, ChannelId::new(234));
+    }
+
+    #[test_log::test]
+    fn when_category_has_no_display_name_should_use_slug() {
+        let categories = parse_categories_config("CATEGORIES", "opera:345;");
+
+        assert_eq!(categories.len(), 1);
+        assert_eq!(categories[0].category, Category::new("opera", "opera"));
+    }
+
+    #[test_log::test]
+    #[should_panic]
+    fn when_category_has_no_channel_should_panic() {
+        parse_categories_config("CATEGORIES", "musica");
+    }
+
+    #[test_log::test]
+    fn should_skip_artes_when_older_flag_is_set() {
+        assert_eq!(
+            merge_skip_artes_config(vec!["danca".to_string()], Some(true)),
+            ["danca", "artes"]
+        );
+        assert_eq!(
+            merge_skip_artes_config(vec!["artes".to_string()], Some(true)),
+            ["artes"]
+        );
+        assert!(merge_skip_artes_config(Vec::new(), Some(false)).is_empty());
+        assert!(merge_skip_artes_config(Vec::new(), None).is_empty());
+    }
+
+    #[test_log::test]
+    fn should_parse_long_running_events_policies() {
+        assert_eq!(
+            parse_long_running_events_config("LONG_RUNNING_EVENTS", "cross-post"),
+            LongRunningEventsPolicy::CrossPost
+        );
+        assert_eq!(
+            parse_long_running_events_config("LONG_RUNNING_EVENTS", " roll-up "),
+            LongRunningEventsPolicy::RollUp
+        );
+    }
+
+    #[test_log::test]
+    #[should_panic]
+    fn when_long_running_events_policy_is_unknown_should_panic() {
+        parse_long_running_events_config("LONG_RUNNING_EVENTS", "everywhere");
+    }
+
+    #[test_log::test]
+    fn should_parse_venue_ticket_shops_with_aliases() {
+        let venue_ticket_shops = parse_venue_ticket_shop_config(
+            "VENUE_TICKET_SHOP_URLS",
+            "Teatro Ibérico|teatro-iberico-2:https://iberico.pt;culturgest:https://culturgest.pt;",
+        );
+
+        assert_eq!(
+            venue_ticket_shops
+                .find(&Venue::new(None, "teatro-iberico-2", "Iberico"))
+                .as_deref(),
+            Some("https://iberico.pt")
+        );
+        assert_eq!(
+            venue_ticket_shops
+                .find(&Venue::new(None, "", "Teatro Iberico"))
+                .as_deref(),
+            Some("https://iberico.pt")
+        );
+        assert_eq!(
+            venue_ticket_shops
+                .find(&Venue::new(Some(3), "culturgest", "Culturgest"))
+                .as_deref(),
+            Some("https://culturgest.pt")
+        );
+    }
+
+    #[test_log::test]
+    #[should_panic]
+    fn when_venue_ticket_shop_has_no_url_should_panic() {
+        parse_venue_ticket_shop_config("VENUE_TICKET_SHOP_URLS", "Teatro Ibérico");
+    }
+
+    #[test_log::test]
+    fn should_parse_tag_filters() {
+        let tag_filters =
+            parse_tag_filters_config("TAG_FILTERS", "teatro:Estreia, festival;danca:-crianças");
+
+        assert_eq!(
+            tag_filters["teatro"],
+            TagFilter {
+                included: vec!["estreia".to_string(), "festival".to_string()],
+                excluded: Vec::new(),
+            }
+        );
+        assert_eq!(
+            tag_filters["danca"],
+            TagFilter {
+                included: Vec::new(),
+                excluded: vec!["crianças".to_string()],
+            }
+        );
+    }
+
+    #[test_log::test]
+    fn should_parse_tag_labels() {
+        let tag_labels = parse_tag_labels_config("TAG_LABELS", "gratuito:🆓;estreia:✨;");
+
+        assert_eq!(
+            tag_labels.labels(&["Estreia".to_string(), "gratuito".to_string()]),
+            [("🆓", "gratuito"), ("✨", "estreia")]
+        );
+    }
+
+    #[test_log::test]
+    fn should_run_priority_categories_first() {
+        let mut categories =
+            parse_categories_config("CATEGORIES", "artes:1:Artes;danca:2:Dança;teatro:3:Teatro");
+
+        sort_categories_by_priority(&mut categories, &["teatro".to_string()]);
+
+        let slugs: Vec<&str> = categories
+            .iter()
+            .map(|category_config| category_config.category.slug.as_str())
+            .collect();
+        assert_eq!(slugs, ["teatro", "artes", "danca"]);
+    }
+
+    #[test_log::test]
+    fn should_parse_free_events_policies() {
+        let policies =
+            parse_free_events_config("FREE_EVENTS", "teatro:highlight; musica:only;artes:all");
+
+        assert_eq!(policies["teatro"], FreeEventsPolicy::HighlightFree);
+        assert_eq!(policies["musica"], FreeEventsPolicy::OnlyFree);
+        assert_eq!(policies["artes"], FreeEventsPolicy::All);
+    }
+
+    #[test_log::test]
+    #[should_panic]
+    fn when_free_events_policy_is_unknown_should_panic() {
+        parse_free_events_config("FREE_EVENTS", "teatro:sometimes");
+    }
+}
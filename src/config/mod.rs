@@ -1,2 +1,4 @@
 pub mod env_loader;
 pub mod model;
+pub mod tags;
+pub mod ticket_shops;
@@ -1,16 +1,66 @@
+use crate::agenda_cultural::api::PageFetchLimits;
+use crate::agenda_cultural::model::{Category, Venue};
+use crate::agenda_cultural::page_cache::PageCache;
+use crate::config::tags::{TagFilter, TagLabels};
+use crate::config::ticket_shops::VenueTicketShops;
 use serenity::all::ChannelId;
-use std::collections::HashMap;
 use std::fmt::Display;
+use std::time::Duration;
 
 #[derive(Debug)]
 pub struct Config {
     pub debug_config: DebugConfig,
-    pub teatro_channel_id: ChannelId,
-    pub artes_channel_id: ChannelId,
+    /// In the order their pipelines run
+    pub categories: Vec<CategoryConfig>,
     pub voting_emojis: [EmojiConfig; 5],
-    pub venue_ticket_shop_url: HashMap<String, String>,
+    pub venue_ticket_shops: VenueTicketShops,
     pub ticket_shop_icon_url: String,
     pub gather_new_events: bool,
+    pub page_fetch_limits: PageFetchLimits,
+    /// Event pages are always downloaded when not set
+    pub page_cache: Option<PageCache>,
+    pub shared_events: SharedEventsConfig,
+    pub long_running_events: LongRunningEventsPolicy,
+    pub tag_labels: TagLabels,
+    /// Runs once and exits when not set
+    pub daemon: Option<DaemonConfig>,
+}
+
+/// Keeps the bot connected to the gateway, running each pipeline on its own schedule
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DaemonConfig {
+    /// Time between gathering and sending new events
+    pub events_interval: Duration,
+    /// Time between handling votes and save for later reactions
+    pub reactions_interval: Duration,
+}
+
+/// How events running over several months show up in the months after they start
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum LongRunningEventsPolicy {
+    /// Only posted in the thread of the month they start in
+    #[default]
+    StartMonthOnly,
+    /// Each later month's thread gets a short "still running" link to the event
+    CrossPost,
+    /// Each thread keeps a pinned "Em cena" message listing the events still running
+    RollUp,
+}
+
+/// How events listed under several categories are posted only once
+#[derive(Debug, Clone, Default)]
+pub struct SharedEventsConfig {
+    /// Slugs of the categories that own shared events first.
+    /// Their pipelines run first and the remaining ones follow in their configured order
+    pub owner_priority: Vec<String>,
+    /// Whether the other categories get a link to the owner's message
+    pub link_in_other_channels: bool,
+}
+
+impl Config {
+    pub fn ticket_shop_url(&self, venue: &Venue) -> Option<String> {
+        self.venue_ticket_shops.find(venue)
+    }
 }
 
 #[derive(Debug)]
@@ -19,10 +69,30 @@ pub struct DebugConfig {
     pub exit_after_clearing: bool,
     pub skip_sending: bool,
     pub skip_feature_reactions: bool,
-    pub skip_artes: bool,
+    /// Slugs of the categories whose pipelines won't run
+    pub skip_categories: Vec<String>,
     pub event_limit: Option<i32>,
 }
 
+#[derive(Debug, Clone)]
+pub struct CategoryConfig {
+    pub category: Category,
+    pub channel_id: ChannelId,
+    pub free_events: FreeEventsPolicy,
+    pub tag_filter: TagFilter,
+}
+
+/// How a category treats events tagged as free
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum FreeEventsPolicy {
+    #[default]
+    All,
+    /// Only free events are posted
+    OnlyFree,
+    /// All events are posted but free ones stand out
+    HighlightFree,
+}
+
 #[derive(Debug, Clone)]
 pub struct EmojiConfig {
     pub id: u64,
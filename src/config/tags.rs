@@ -0,0 +1,156 @@
+use crate::agenda_cultural::model::Event;
+
+/// The agendalx tag of events for children
+pub const CHILDREN_TAG: &str = "crianças";
+const CHILDREN_EMOJI: &str = "🧸";
+
+/// Emojis that label the events with some agendalx tags (e.g. "estreia" → "✨ estreia").
+/// Children's events are labelled with "🧸" unless configured otherwise
+#[derive(Debug, Clone)]
+pub struct TagLabels {
+    /// Lowercase tags and their emojis, in the order they're shown
+    emojis: Vec<(String, String)>,
+}
+
+impl Default for TagLabels {
+    fn default() -> Self {
+        Self {
+            emojis: vec![(CHILDREN_TAG.to_string(), CHILDREN_EMOJI.to_string())],
+        }
+    }
+}
+
+impl TagLabels {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the emoji of a tag that already has one
+    pub fn add(&mut self, tag: &str, emoji: &str) {
+        let tag = normalize(tag);
+        let emoji = emoji.trim().to_string();
+
+        match self
+            .emojis
+            .iter_mut()
+            .find(|(known_tag, _)| *known_tag == tag)
+        {
+            Some((_, known_emoji)) => *known_emoji = emoji,
+            None => self.emojis.push((tag, emoji)),
+        }
+    }
+
+    /// The emojis and tags of the tags the event has
+    pub fn labels(&self, tags: &[String]) -> Vec<(&str, &str)> {
+        self.emojis
+            .iter()
+            .filter(|(tag, _)| tags.iter().any(|event_tag| normalize(event_tag) == *tag))
+            .map(|(tag, emoji)| (emoji.as_str(), tag.as_str()))
+            .collect()
+    }
+}
+
+/// Which events of a category get posted, by their tags
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TagFilter {
+    /// Events need one of these, unless there are none
+    pub included: Vec<String>,
+    /// Events with any of these are left out
+    pub excluded: Vec<String>,
+}
+
+impl TagFilter {
+    pub fn is_empty(&self) -> bool {
+        self.included.is_empty() && self.excluded.is_empty()
+    }
+
+    pub fn allows(&self, event: &Event) -> bool {
+        let has_any = |filter_tags: &[String]| {
+            event
+                .tags
+                .iter()
+                .any(|tag| filter_tags.contains(&normalize(tag)))
+        };
+
+        (self.included.is_empty() || has_any(&self.included)) && !has_any(&self.excluded)
+    }
+}
+
+/// Tags are compared in lowercase, since agendalx isn't consistent in their case
+pub fn normalize(tag: &str) -> String {
+    tag.trim().to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agenda_cultural::audience::Audience;
+    use crate::agenda_cultural::model::{EventDetails, Price, Schedule, Venue};
+
+    fn build_event(tags: &[&str]) -> Event {
+        Event::new(
+            "Galafoice".to_string(),
+            EventDetails::new(String::new(), String::new(), String::new()),
+            String::new(),
+            Schedule::new(String::new(), String::new(), Vec::new(), None, None),
+            Venue::default(),
+            tags.iter().map(|tag| tag.to_string()).collect(),
+            Price::Unknown,
+            Audience::default(),
+        )
+    }
+
+    #[test_log::test]
+    fn should_label_tags_in_configured_order() {
+        let mut tag_labels = TagLabels::new();
+        tag_labels.add("Festival", "🎪");
+        tag_labels.add("estreia", "✨");
+        tag_labels.add("gratuito", "🆓");
+
+        assert_eq!(
+            tag_labels.labels(&["Estreia".to_string(), "festival".to_string()]),
+            [("🎪", "festival"), ("✨", "estreia")]
+        );
+        assert!(tag_labels.labels(&["teatro".to_string()]).is_empty());
+    }
+
+    #[test_log::test]
+    fn should_label_children_events_unless_configured_otherwise() {
+        let mut tag_labels = TagLabels::new();
+
+        assert_eq!(
+            tag_labels.labels(&["Crianças".to_string()]),
+            [("🧸", "crianças")]
+        );
+
+        tag_labels.add("crianças", "🎈");
+
+        assert_eq!(
+            tag_labels.labels(&["crianças".to_string()]),
+            [("🎈", "crianças")]
+        );
+    }
+
+    #[test_log::test]
+    fn should_filter_events_by_tags() {
+        let filter = TagFilter {
+            included: vec!["estreia".to_string(), "festival".to_string()],
+            excluded: vec!["crianças".to_string()],
+        };
+
+        assert!(filter.allows(&build_event(&["Estreia"])));
+        assert!(!filter.allows(&build_event(&["teatro"])));
+        assert!(!filter.allows(&build_event(&["estreia", "Crianças"])));
+    }
+
+    #[test_log::test]
+    fn when_filter_only_excludes_should_allow_untagged_events() {
+        let filter = TagFilter {
+            included: Vec::new(),
+            excluded: vec!["crianças".to_string()],
+        };
+
+        assert!(filter.allows(&build_event(&[])));
+        assert!(!filter.allows(&build_event(&["crianças"])));
+    }
+}
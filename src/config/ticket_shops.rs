@@ -0,0 +1,178 @@
+use crate::agenda_cultural::model::Venue;
+use std::collections::BTreeMap;
+
+/// Ticket shop URLs of the venues, each known by one or more aliases (slugs or names)
+#[derive(Debug, Clone, Default)]
+pub struct VenueTicketShops {
+    shops: Vec<VenueTicketShop>,
+}
+
+#[derive(Debug, Clone)]
+struct VenueTicketShop {
+    url: String,
+    /// Normalized aliases
+    keys: Vec<String>,
+}
+
+impl VenueTicketShops {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, aliases: &[&str], url: &str) {
+        let keys = aliases
+            .iter()
+            .map(|alias| normalize(alias))
+            .filter(|key| !key.is_empty())
+            .collect();
+
+        self.shops.push(VenueTicketShop {
+            url: url.trim().to_string(),
+            keys,
+        });
+    }
+
+    /// Matches the venue's slug or name regardless of case and accents.
+    /// A name made of an alias and a suffix (e.g. a hall, "Teatro Ibérico - Sala 2") also matches,
+    /// taking the longest such alias
+    pub fn find(&self, venue: &Venue) -> Option<String> {
+        let slug = normalize(&venue.slug);
+        let name = normalize(&venue.name);
+
+        let exact_match = self.shops.iter().find(|shop| {
+            shop.keys
+                .iter()
+                .any(|key| (!slug.is_empty() && *key == slug) || *key == name)
+        });
+
+        if let Some(shop) = exact_match {
+            return Some(shop.url.clone());
+        }
+
+        self.shops
+            .iter()
+            .flat_map(|shop| shop.keys.iter().map(move |key| (key, shop)))
+            .filter(|(key, _)| name.starts_with(&format!("{} ", key)))
+            .max_by_key(|(key, _)| key.len())
+            .map(|(_, shop)| shop.url.clone())
+    }
+
+    /// Venues of the events without a ticket shop, as "Name (slug)", so the config can be completed
+    pub fn unmapped_venues<'a>(&self, venues: impl IntoIterator<Item = &'a Venue>) -> Vec<String> {
+        venues
+            .into_iter()
+            .filter(|venue| !venue.name.is_empty() && self.find(venue).is_none())
+            .map(|venue| (normalize(&venue.name), venue))
+            .collect::<BTreeMap<_, _>>()
+            .into_values()
+            .map(|venue| match venue.slug.as_str() {
+                "" => venue.name.clone(),
+                slug => format!("{} ({})", venue.name, slug),
+            })
+            .collect()
+    }
+}
+
+/// Lowercase, without accents and with words separated by single spaces,
+/// so "Teatro Ibérico", "teatro-iberico" and "TEATRO  IBERICO" are the same
+pub(crate) fn normalize(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .map(|character| match character {
+            'á' | 'à' | 'â' | 'ã' | 'ä' => 'a',
+            'é' | 'è' | 'ê' | 'ë' => 'e',
+            'í' | 'ì' | 'î' | 'ï' => 'i',
+            'ó' | 'ò' | 'ô' | 'õ' | 'ö' => 'o',
+            'ú' | 'ù' | 'û' | 'ü' => 'u',
+            'ç' => 'c',
+            'ñ' => 'n',
+            character if character.is_alphanumeric() => character,
+            _ => ' ',
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_shops() -> VenueTicketShops {
+        let mut shops = VenueTicketShops::new();
+        shops.add(
+            &["Teatro Ibérico", "teatro-iberico-2"],
+            "https://iberico.pt",
+        );
+        shops.add(&["teatro-nacional-d-maria-ii"], "https://tndm.pt");
+        shops.add(&["Teatro"], "https://teatro.pt");
+        shops
+    }
+
+    #[test_log::test]
+    fn should_match_regardless_of_case_and_accents() {
+        let shops = build_shops();
+
+        assert_eq!(
+            shops
+                .find(&Venue::new(None, "", "TEATRO IBERICO"))
+                .as_deref(),
+            Some("https://iberico.pt")
+        );
+        assert_eq!(
+            shops
+                .find(&Venue::new(
+                    Some(1),
+                    "teatro-nacional-d-maria-ii",
+                    "TNDM II"
+                ))
+                .as_deref(),
+            Some("https://tndm.pt")
+        );
+    }
+
+    #[test_log::test]
+    fn should_match_any_alias() {
+        let shops = build_shops();
+
+        assert_eq!(
+            shops
+                .find(&Venue::new(Some(2), "teatro-iberico-2", "Ibérico"))
+                .as_deref(),
+            Some("https://iberico.pt")
+        );
+    }
+
+    #[test_log::test]
+    fn when_name_has_a_suffix_should_match_the_longest_alias() {
+        let shops = build_shops();
+
+        assert_eq!(
+            shops
+                .find(&Venue::new(None, "", "Teatro Ibérico - Sala 2"))
+                .as_deref(),
+            Some("https://iberico.pt")
+        );
+        assert_eq!(
+            shops.find(&Venue::new(None, "", "Teatroteca")).as_deref(),
+            None
+        );
+    }
+
+    #[test_log::test]
+    fn should_list_unmapped_venues_once() {
+        let shops = build_shops();
+        let venues = [
+            Venue::new(Some(3), "culturgest", "Culturgest"),
+            Venue::new(None, "", "Teatro Ibérico"),
+            Venue::new(Some(3), "culturgest", "Culturgest"),
+            Venue::new(None, "", "Bairro Benfica"),
+        ];
+
+        assert_eq!(
+            shops.unmapped_venues(&venues),
+            ["Bairro Benfica", "Culturgest (culturgest)"]
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use crate::agenda_cultural::source::EventSource;
+use crate::api::get_posted_events;
+use crate::cancellation::Cancellation;
+use crate::config::model::{CategoryConfig, Config, DaemonConfig};
+use crate::dedup::EventOwners;
+use crate::discord::api::DiscordAPI;
+use crate::discord::backend::ChatBackend;
+use crate::metrics::record_pipeline_run_duration;
+use crate::pipeline::{
+    backup_votes, gather_events, process_reactions, rewrite_reviews_from_replies,
+};
+use crate::posted_events::PostedEvents;
+use std::future::Future;
+use std::path::Path;
+use std::time::Instant;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{info, info_span, warn, Instrument};
+
+/// Runs both pipelines on their schedules, starting right away, until `shutdown` completes.
+/// A pass that already started stops at its next event or thread, so no event is left half sent.
+/// Each events pass refreshes the posted events that can be browsed, unless fetching them fails
+pub async fn run_daemon(
+    config: &Config,
+    daemon_config: DaemonConfig,
+    discord: &DiscordAPI<impl ChatBackend>,
+    source: &impl EventSource,
+    posted_events: &PostedEvents,
+    vote_backups_folder: &Path,
+    shutdown: impl Future<Output = ()>,
+) {
+    let mut events_schedule = interval(daemon_config.events_interval);
+    let mut reactions_schedule = interval(daemon_config.reactions_interval);
+
+    // A slow pass shouldn't be followed by a burst of the ones it delayed
+    events_schedule.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    reactions_schedule.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    let (cancel, cancellation) = Cancellation::channel();
+    let cancel_on_shutdown = async move {
+        shutdown.await;
+        info!("Shutting down once the running pass stops");
+        // Only fails once the passes are gone, which is after this
+        let _ = cancel.send(true);
+    };
+    let run_passes = async {
+        let mut cancelled = cancellation.clone();
+
+        loop {
+            tokio::select! {
+                biased;
+
+                _ = cancelled.cancelled() => break,
+                _ = events_schedule.tick() => {
+                    gather_all_events(config, discord, source, posted_events, &cancellation)
+                        .instrument(info_span!("gather_all_events"))
+                        .await
+                }
+                _ = reactions_schedule.tick() => {
+                    handle_all_reactions(config, discord, vote_backups_folder, &cancellation)
+                        .instrument(info_span!("handle_all_reactions"))
+                        .await
+                }
+            }
+        }
+    };
+
+    info!(
+        "Running as a daemon, gathering events every {:?} and handling reactions every {:?}",
+        daemon_config.events_interval, daemon_config.reactions_interval
+    );
+
+    tokio::join!(cancel_on_shutdown, run_passes);
+
+    info!("Daemon stopped");
+}
+
+/// Completes on SIGTERM or Ctrl+C
+pub async fn shutdown_signal() {
+    let mut terminate = signal(SignalKind::terminate()).expect("Failed to listen to SIGTERM");
+
+    tokio::select! {
+        _ = terminate.recv() => info!("Received SIGTERM"),
+        _ = tokio::signal::ctrl_c() => info!("Received Ctrl+C"),
+    }
+}
+
+async fn gather_all_events(
+    config: &Config,
+    discord: &DiscordAPI<impl ChatBackend>,
+    source: &impl EventSource,
+    posted_events: &PostedEvents,
+    cancellation: &Cancellation,
+) {
+    if !config.gather_new_events {
+        info!("Set to not gather new events");
+        return;
+    }
+
+    let mut event_owners = EventOwners::default();
+
+    for category_config in active_categories(config) {
+        if cancellation.is_cancelled() {
+            break;
+        }
+
+        let started_at = Instant::now();
+        let events = gather_events(
+            config,
+            discord,
+            source,
+            category_config,
+            &mut event_owners,
+            cancellation,
+        )
+        .await;
+        record_pipeline_run_duration(&category_config.category, started_at.elapsed());
+
+        let Some(events) = events else {
+            warn!(
+                "Keeping the previously posted events of {}",
+                category_config.category
+            );
+            continue;
+        };
+
+        let channel_id = category_config.channel_id;
+        let guild_id = discord.get_guild(channel_id).await;
+
+        posted_events.replace(
+            channel_id,
+            get_posted_events(discord, guild_id, channel_id, events),
+        );
+    }
+}
+
+async fn handle_all_reactions(
+    config: &Config,
+    discord: &DiscordAPI<impl ChatBackend>,
+    vote_backups_folder: &Path,
+    cancellation: &Cancellation,
+) {
+    let mut users_with_reactions = Vec::new();
+
+    for category_config in active_categories(config) {
+        for user_id in process_reactions(config, discord, category_config, cancellation).await {
+            if !users_with_reactions.contains(&user_id) {
+                users_with_reactions.push(user_id);
+            }
+        }
+    }
+
+    // The day's backup is only written once, so it shouldn't miss the votes of skipped threads
+    if cancellation.is_cancelled() {
+        info!("Cancelled before backing up the votes");
+        return;
+    }
+
+    rewrite_reviews_from_replies(discord, &users_with_reactions).await;
+
+    backup_votes(
+        discord,
+        users_with_reactions,
+        &config.voting_emojis,
+        vote_backups_folder,
+    )
+    .await;
+}
+
+fn active_categories(config: &Config) -> impl Iterator<Item = &CategoryConfig> {
+    config.categories.iter().filter(|category_config| {
+        !config
+            .debug_config
+            .skip_categories
+            .contains(&category_config.category.slug)
+    })
+}
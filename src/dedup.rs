@@ -0,0 +1,187 @@
+use crate::agenda_cultural::model::Event;
+use crate::discord::sent_events::SentEventsIndex;
+use chrono::NaiveDate;
+use serenity::all::ChannelId;
+use std::collections::{BTreeMap, HashMap};
+
+/// Channel that posts each event listed under several categories.
+/// An event stays with the channel it was already posted in, otherwise pipelines run
+/// in priority order, so the first channel to claim it owns it
+#[derive(Debug, Default)]
+pub struct EventOwners {
+    owners: HashMap<String, ChannelId>,
+}
+
+/// An event left to the channel that owns it
+#[derive(Debug, Clone)]
+pub struct SharedEvent {
+    pub event: Event,
+    pub owner_channel_id: ChannelId,
+}
+
+impl EventOwners {
+    /// Claims the unclaimed events for `channel_id` and takes out the ones owned by other channels,
+    /// which are returned by month
+    pub fn claim(
+        &mut self,
+        events_by_month: &mut BTreeMap<NaiveDate, Vec<Event>>,
+        channel_id: ChannelId,
+        sent_events: &SentEventsIndex,
+    ) -> BTreeMap<NaiveDate, Vec<SharedEvent>> {
+        let mut shared_events = BTreeMap::new();
+
+        for (date, events) in events_by_month.iter_mut() {
+            let (owned, shared): (Vec<Event>, Vec<Event>) = events.drain(..).partition(|event| {
+                let owner = self.owners.entry(event.link.clone()).or_insert_with(|| {
+                    // A lower priority channel posts it when the owner's events can't be fetched
+                    let posted_in = sent_events.channels_of(&event.link);
+
+                    match posted_in.first() {
+                        Some(first) if !posted_in.contains(&channel_id) => *first,
+                        _ => channel_id,
+                    }
+                });
+
+                *owner == channel_id
+            });
+
+            *events = owned;
+
+            if !shared.is_empty() {
+                shared_events.insert(
+                    *date,
+                    shared
+                        .into_iter()
+                        .map(|event| SharedEvent {
+                            owner_channel_id: self.owners[&event.link],
+                            event,
+                        })
+                        .collect(),
+                );
+            }
+        }
+
+        events_by_month.retain(|_, events| !events.is_empty());
+
+        shared_events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agenda_cultural::audience::Audience;
+    use crate::agenda_cultural::model::{EventDetails, Price, Schedule, Venue};
+    use crate::discord::sent_events::SentEvent;
+    use serenity::all::MessageId;
+
+    fn build_events(links: &[&str]) -> BTreeMap<NaiveDate, Vec<Event>> {
+        let events = links
+            .iter()
+            .map(|link| {
+                Event::new(
+                    link.to_string(),
+                    EventDetails::new(String::new(), String::new(), String::new()),
+                    link.to_string(),
+                    Schedule::new(String::new(), String::new(), Vec::new(), None, None),
+                    Venue::default(),
+                    Vec::new(),
+                    Price::Unknown,
+                    Audience::default(),
+                )
+            })
+            .collect();
+
+        BTreeMap::from([(NaiveDate::from_ymd_opt(2025, 3, 1).unwrap(), events)])
+    }
+
+    fn links(events_by_month: &BTreeMap<NaiveDate, Vec<Event>>) -> Vec<&str> {
+        events_by_month
+            .values()
+            .flatten()
+            .map(|event| event.link.as_str())
+            .collect()
+    }
+
+    #[test_log::test]
+    fn when_event_was_claimed_by_another_channel_should_be_shared() {
+        let teatro = ChannelId::new(1);
+        let artes = ChannelId::new(2);
+        let sent_events = SentEventsIndex::new();
+        let mut owners = EventOwners::default();
+        let mut teatro_events = build_events(&["galafoice", "maes"]);
+        let mut artes_events = build_events(&["maes", "quadros"]);
+
+        assert!(owners
+            .claim(&mut teatro_events, teatro, &sent_events)
+            .is_empty());
+        let shared_events = owners.claim(&mut artes_events, artes, &sent_events);
+
+        assert_eq!(links(&teatro_events), ["galafoice", "maes"]);
+        assert_eq!(links(&artes_events), ["quadros"]);
+
+        let shared_events: Vec<&SharedEvent> = shared_events.values().flatten().collect();
+        assert_eq!(shared_events.len(), 1);
+        assert_eq!(shared_events[0].event.link, "maes");
+        assert_eq!(shared_events[0].owner_channel_id, teatro);
+    }
+
+    #[test_log::test]
+    fn when_every_event_is_shared_should_drop_empty_months() {
+        let sent_events = SentEventsIndex::new();
+        let mut owners = EventOwners::default();
+        let mut teatro_events = build_events(&["maes"]);
+        let mut artes_events = build_events(&["maes"]);
+
+        owners.claim(&mut teatro_events, ChannelId::new(1), &sent_events);
+        owners.claim(&mut artes_events, ChannelId::new(2), &sent_events);
+
+        assert!(artes_events.is_empty());
+    }
+
+    #[test_log::test]
+    fn when_channel_claims_again_should_keep_its_events() {
+        let teatro = ChannelId::new(1);
+        let sent_events = SentEventsIndex::new();
+        let mut owners = EventOwners::default();
+        let mut teatro_events = build_events(&["maes"]);
+
+        owners.claim(&mut teatro_events, teatro, &sent_events);
+        let shared_events = owners.claim(&mut teatro_events, teatro, &sent_events);
+
+        assert!(shared_events.is_empty());
+        assert_eq!(links(&teatro_events), ["maes"]);
+    }
+
+    #[test_log::test]
+    fn when_event_was_posted_in_another_channel_should_stay_there() {
+        let teatro = ChannelId::new(1);
+        let artes = ChannelId::new(2);
+        let sent_events = SentEventsIndex::new();
+        let mut owners = EventOwners::default();
+        let mut teatro_events = build_events(&["galafoice", "maes"]);
+        let mut artes_events = build_events(&["maes"]);
+
+        sent_events.record(SentEvent {
+            link: "maes".to_string(),
+            channel_id: artes,
+            thread_id: ChannelId::new(3),
+            message_id: MessageId::new(4),
+            category: "artes".to_string(),
+            fingerprint: 42,
+            last_date: None,
+        });
+
+        let shared_events = owners.claim(&mut teatro_events, teatro, &sent_events);
+
+        assert!(owners
+            .claim(&mut artes_events, artes, &sent_events)
+            .is_empty());
+        assert_eq!(links(&teatro_events), ["galafoice"]);
+        assert_eq!(links(&artes_events), ["maes"]);
+
+        let shared_events: Vec<&SharedEvent> = shared_events.values().flatten().collect();
+        assert_eq!(shared_events.len(), 1);
+        assert_eq!(shared_events[0].owner_channel_id, artes);
+    }
+}
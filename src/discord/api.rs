@@ -1,24 +1,30 @@
-use crate::agenda_cultural::api::AgendaCulturalAPI;
-use crate::agenda_cultural::model::Event;
+use crate::agenda_cultural::audience::{Accessibility, Audience};
+use crate::agenda_cultural::markdown::escape_link_label;
+use crate::agenda_cultural::model::{Event, EventStatus, Price};
+use crate::agenda_cultural::source::EventSource;
+use crate::agenda_cultural::timetable::Timetable;
 use crate::config::model::EmojiConfig;
-use crate::metrics::{record_dm_review_rewrite, record_dm_review_sent, MetricResult};
+use crate::config::tags::{TagLabels, CHILDREN_TAG};
+use crate::config::ticket_shops::VenueTicketShops;
+use crate::discord::backend::{ChatBackend, SerenityChat, DEFAULT_PAGE_SIZE};
+use crate::discord::embed_limits::fit_embed;
+use crate::discord::fingerprint::EmbedContent;
+use crate::discord::message_locks::MessageLocks;
+use crate::discord::sent_events::{SentEvent, SentEventsIndex};
+use crate::metrics::{
+    record_dm_review_rewrite, record_dm_review_sent, record_status_notice_sent, MetricResult,
+};
 use chrono::{Datelike, NaiveDate};
-use futures::{StreamExt, TryStreamExt};
 use itertools::Itertools;
 use lazy_static::lazy_static;
 use regex::Regex;
 use serenity::all::ReactionType::{Custom, Unicode};
 use serenity::all::{
-    AutoArchiveDuration, ChannelType, Colour, CreateEmbedAuthor, CreateThread, CurrentUser,
-    EditThread, Embed, GatewayIntents, GetMessages, GuildChannel, Message, MessageId,
-    MessageReaction, MessageType, PartialGuild, PrivateChannel, ReactionType, User, UserId,
+    Colour, CreateEmbedAuthor, CurrentUser, Embed, GuildChannel, GuildId, Message, MessageId,
+    MessageReaction, MessageType, ReactionType, User, UserId,
 };
 use serenity::builder::{CreateEmbed, CreateMessage, EditMessage};
-use serenity::cache::Settings;
 use serenity::model::id::ChannelId;
-use serenity::prelude::SerenityError;
-use serenity::Client;
-use std::collections::HashMap;
 use std::env;
 use std::fmt::Debug;
 use tracing::field::debug;
@@ -39,17 +45,25 @@ const PORTUGUESE_MONTHS: [&str; 12] = [
     "Dezembro",
 ];
 
-const CHILDREN_LABEL: &str = "🧸 para crianças";
+const FREE_LABEL: &str = "🆓 entrada gratuita";
 const PROCESSED_COMMENT_EMOJI: char = '✅';
+pub const STATUS_FIELD: &str = "Estado";
+const REFERENCE_PREFIX: &str = "🔗";
+const ROLL_UP_HEADER: &str = "🎭 **Em cena**";
+const MESSAGE_CONTENT_LIMIT: usize = 2000;
 
 lazy_static! {
     static ref USER_MENTION_REGEX: Regex =
         Regex::new("<@(\\d+)>").expect("Failed to create mention regex");
 }
 
-pub struct DiscordAPI {
-    pub client: Client,
+pub struct DiscordAPI<B = SerenityChat> {
+    pub backend: B,
     pub own_user: CurrentUser,
+    pub sent_events: SentEventsIndex,
+    /// Shared by the gateway and the polling pass, which both handle reactions
+    pub message_locks: MessageLocks,
+    tag_labels: TagLabels,
 }
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
@@ -67,34 +81,40 @@ impl DiscordAPI {
     }
 
     pub async fn new(token: &str, cache_flag: bool) -> Self {
-        let intents = GatewayIntents::GUILD_MESSAGES
-            | GatewayIntents::MESSAGE_CONTENT
-            | GatewayIntents::GUILD_MESSAGE_REACTIONS;
-        let mut cache_settings = Settings::default();
-
-        cache_settings.cache_channels = cache_flag;
+        DiscordAPI::with_backend(SerenityChat::new(token, cache_flag).await).await
+    }
+}
 
-        let client = Client::builder(token, intents)
-            .cache_settings(cache_settings)
-            .await
-            .expect("Error creating discord client");
-        let own_user = client
-            .http
-            .get_current_user()
-            .await
-            .expect("Error getting user");
+impl<B: ChatBackend> DiscordAPI<B> {
+    pub async fn with_backend(backend: B) -> Self {
+        let own_user = backend.current_user().await.expect("Error getting user");
 
         debug!("Own user id is {}", own_user.id);
 
-        Self { client, own_user }
+        Self {
+            backend,
+            own_user,
+            sent_events: SentEventsIndex::new(),
+            message_locks: MessageLocks::default(),
+            tag_labels: TagLabels::default(),
+        }
+    }
+
+    pub fn with_tag_labels(mut self, tag_labels: TagLabels) -> Self {
+        self.tag_labels = tag_labels;
+        self
+    }
+
+    pub fn with_sent_events(mut self, sent_events: SentEventsIndex) -> Self {
+        self.sent_events = sent_events;
+        self
     }
 
     pub async fn get_messages(&self, channel_id: ChannelId) -> Vec<Message> {
-        channel_id
-            .messages_iter(&self.client.http)
-            .filter_map(|a| async { a.ok() })
-            .collect::<Vec<Message>>()
+        self.backend
+            .all_messages(channel_id)
             .await
+            .unwrap_or_default()
     }
 
     pub async fn send_event(
@@ -103,16 +123,18 @@ impl DiscordAPI {
         event: Event,
         ticket_shop_url: Option<String>,
         ticket_shop_icon_url: &str,
+        highlight_free: bool,
     ) -> Result<Message, DiscordError> {
         info!(channel_id = %channel_id, event = %event.title, "Sending event");
 
         let title = event.title.clone();
-        let embed = Self::build_event_embed(event, ticket_shop_url, ticket_shop_icon_url);
+        let embed =
+            self.build_event_embed(event, ticket_shop_url, ticket_shop_icon_url, highlight_free);
 
         let message_builder = CreateMessage::new().add_embed(embed.clone());
 
-        channel_id
-            .send_message(&self.client.http, message_builder)
+        self.backend
+            .send_message(channel_id, message_builder)
             .await
             .map_err(|err| {
                 error!("Failed sending event '{}' due to '{}'", title, err);
@@ -120,41 +142,396 @@ impl DiscordAPI {
             })
     }
 
+    /// Links to an event posted elsewhere (e.g. "está publicado em") instead of posting it again
+    pub async fn send_event_reference(
+        &self,
+        channel_id: ChannelId,
+        event: &Event,
+        event_message_link: &str,
+        note: &str,
+    ) -> Result<Message, DiscordError> {
+        info!(channel_id = %channel_id, event = %event.title, "Sending event reference");
+
+        let content = format!(
+            "{} **{}** {} {}\n<{}>",
+            REFERENCE_PREFIX, event.title, note, event_message_link, event.link
+        );
+
+        self.backend
+            .send_message(channel_id, CreateMessage::new().content(content))
+            .await
+            .map_err(|err| {
+                error!(
+                    "Failed sending event reference '{}' due to '{}'",
+                    event.title, err
+                );
+                DiscordError::Api
+            })
+    }
+
+    /// Keeps the thread's pinned "Em cena" message listing `lines`, removing it once empty.
+    ///
+    /// Returns whether the message was changed
+    pub async fn update_ongoing_events_roll_up(
+        &self,
+        thread_id: ChannelId,
+        lines: &[String],
+    ) -> Result<bool, DiscordError> {
+        let roll_up = self
+            .get_all_messages(thread_id)
+            .await
+            .into_iter()
+            .find(|message| {
+                message.author.id == self.own_user.id && is_ongoing_events_roll_up(message)
+            });
+
+        let mut content = ROLL_UP_HEADER.to_string();
+
+        for line in lines {
+            if content.chars().count() + line.chars().count() + 1 > MESSAGE_CONTENT_LIMIT {
+                warn!(
+                    "Roll-up of thread {} is too long, leaving events out",
+                    thread_id
+                );
+                break;
+            }
+
+            content = format!("{}\n{}", content, line);
+        }
+
+        let result = match (roll_up, lines.is_empty()) {
+            (None, true) => return Ok(false),
+            (Some(roll_up), true) => self.backend.delete_message(thread_id, roll_up.id).await,
+            (Some(roll_up), false) if roll_up.content == content => {
+                trace!("Roll-up of thread {} is up to date", thread_id);
+                return Ok(false);
+            }
+            (Some(roll_up), false) => self
+                .backend
+                .edit_message(thread_id, roll_up.id, EditMessage::new().content(content))
+                .await
+                .map(|_| ()),
+            (None, false) => {
+                let roll_up = self
+                    .backend
+                    .send_message(thread_id, CreateMessage::new().content(content))
+                    .await;
+
+                match roll_up {
+                    Ok(roll_up) => {
+                        let pinned = self.backend.pin(thread_id, roll_up.id).await;
+
+                        if pinned.is_ok() {
+                            self.delete_pin_notifications(thread_id, 1).await;
+                        }
+
+                        pinned
+                    }
+                    Err(err) => Err(err),
+                }
+            }
+        };
+
+        result.map(|_| true).map_err(|err| {
+            error!("Failed updating roll-up of thread {}: {}", thread_id, err);
+            DiscordError::Api
+        })
+    }
+
+    /// Edits the posted event's embed when it no longer matches the event.
+    /// Only the embed is edited, so reactions and the "Interessados" content are kept.
+    ///
+    /// Changing how embeds are built (e.g. adding a field) makes every posted event differ,
+    /// so the first run after such a change deliberately edits them all once to the new layout.
+    ///
+    /// Returns the names of the fields that changed
+    pub async fn update_event_message(
+        &self,
+        message: &mut Message,
+        event: Event,
+        ticket_shop_url: Option<String>,
+        ticket_shop_icon_url: &str,
+        highlight_free: bool,
+    ) -> Result<Vec<String>, DiscordError> {
+        let title = event.title.clone();
+        let embed =
+            self.build_event_embed(event, ticket_shop_url, ticket_shop_icon_url, highlight_free);
+
+        let (Some(posted), Some(fetched)) = (
+            message.embeds.first().map(EmbedContent::from),
+            EmbedContent::from_create_embed(&embed),
+        ) else {
+            warn!("Unable to compare posted event '{}'", title);
+            return Ok(Vec::new());
+        };
+
+        if posted.fingerprint() == fetched.fingerprint() {
+            trace!("Event '{}' is unchanged", title);
+            return Ok(Vec::new());
+        }
+
+        let changed_fields = posted.changed_fields(&fetched);
+        info!(
+            message_id = %message.id,
+            "Event '{}' changed ({}). Editing message",
+            title,
+            changed_fields.join(", ")
+        );
+
+        *message = self
+            .backend
+            .edit_message(
+                message.channel_id,
+                message.id,
+                EditMessage::new().embed(embed),
+            )
+            .await
+            .map_err(|err| {
+                error!("Failed editing event '{}' due to '{}'", title, err);
+                DiscordError::Api
+            })?;
+
+        Ok(changed_fields)
+    }
+
+    /// Of the embed the event would be posted with, to compare with a sent event's
+    pub fn event_fingerprint(
+        &self,
+        event: Event,
+        ticket_shop_url: Option<String>,
+        ticket_shop_icon_url: &str,
+        highlight_free: bool,
+    ) -> Option<u64> {
+        let embed =
+            self.build_event_embed(event, ticket_shop_url, ticket_shop_icon_url, highlight_free);
+
+        EmbedContent::from_create_embed(&embed).map(|content| content.fingerprint())
+    }
+
+    /// The message of an indexed event. Events whose message was deleted are forgotten,
+    /// so they get posted again
+    pub async fn get_sent_event_message(&self, sent_event: &SentEvent) -> Option<Message> {
+        match self
+            .backend
+            .message(sent_event.thread_id, sent_event.message_id)
+            .await
+        {
+            Ok(message) => Some(message),
+            Err(serenity::Error::Http(err))
+                if err
+                    .status_code()
+                    .is_some_and(|status| status.as_u16() == 404) =>
+            {
+                warn!(
+                    "Message of '{}' was deleted, forgetting it",
+                    sent_event.link
+                );
+                self.sent_events
+                    .forget(sent_event.channel_id, &sent_event.link);
+                None
+            }
+            Err(err) => {
+                error!("Failed to get message of '{}': {}", sent_event.link, err);
+                None
+            }
+        }
+    }
+
     fn build_event_embed(
+        &self,
         event: Event,
         ticket_shop_url: Option<String>,
         ticket_shop_icon_url: &str,
+        highlight_free: bool,
     ) -> CreateEmbed {
+        let title = event.title.clone();
         let mut description = event.details.description;
+        let is_highlighted = highlight_free && event.price == Price::Free;
+        let mut labels = build_event_labels(&event.audience, is_highlighted);
+        let mut tags = event.tags.clone();
 
+        // Also when only its audience tells it's for children
         if event.is_for_children {
-            description = format!("{}\n\n{CHILDREN_LABEL}", description.clone());
+            tags.push(CHILDREN_TAG.to_string());
         }
 
-        let mut author = CreateEmbedAuthor::new(&event.venue);
+        for (emoji, tag) in self.tag_labels.labels(&tags) {
+            if !labels.iter().any(|label| label.starts_with(emoji)) {
+                labels.push(format!("{} {}", emoji, tag));
+            }
+        }
 
-        if let Some(ticket_shop_url) = ticket_shop_url {
-            author = author.url(ticket_shop_url).icon_url(ticket_shop_icon_url);
+        if !labels.is_empty() {
+            description = format!("{}\n\n{}", description.clone(), labels.join("\n"));
         }
 
-        let embed_description = Self::truncate_embed_description(description);
+        let mut author = CreateEmbedAuthor::new(&event.venue.name);
 
-        CreateEmbed::new()
+        if let Some(ticket_shop_url) = event.ticket_url.clone().or(ticket_shop_url) {
+            author = author.url(ticket_shop_url).icon_url(ticket_shop_icon_url);
+        }
+
+        let mut embed = CreateEmbed::new()
             .title(event.title)
             .url(event.link)
-            .description(embed_description)
+            .description(description)
             .author(author)
-            .color(Colour::new(0x005eeb))
-            .field("Datas", event.occurring_at.dates, true)
-            .image(event.details.image_url)
+            .color(match is_highlighted {
+                true => Colour::new(0x1f8b4c),
+                false => Colour::new(0x005eeb),
+            })
+            .field("Datas", event.occurring_at.dates, true);
+
+        if event.occurring_at.timetable != Timetable::Empty {
+            embed = embed.field("Horários", event.occurring_at.timetable.to_string(), true);
+        }
+
+        if event.price != Price::Unknown {
+            embed = embed.field("Preço", event.price.to_string(), true);
+        }
+
+        if let Some(map_url) = event.venue.map_url() {
+            let place = match &event.venue.address {
+                Some(address) => escape_link_label(address),
+                None => "Ver no mapa".to_string(),
+            };
+
+            embed = embed.field("Local", format!("[{}]({})", place, map_url), true);
+        }
+
+        embed = embed.image(event.details.image_url);
+
+        fit_embed(match event.status {
+            EventStatus::Scheduled => embed,
+            status => mark_embed_status(embed, &title, status),
+        })
+    }
+
+    /// Marks a posted event whose event is no longer available (e.g. removed from the agenda)
+    pub async fn mark_event_message_status(
+        &self,
+        message: &mut Message,
+        status: EventStatus,
+    ) -> Result<(), DiscordError> {
+        let Some(embed) = message.embeds.first().cloned() else {
+            warn!("Event message {} has no embed", message.id);
+            return Err(DiscordError::Api);
+        };
+
+        let title = embed.title.clone().unwrap_or_default();
+        info!(message_id = %message.id, "Marking event '{}' as {}", title, status);
+
+        let embed = fit_embed(mark_embed_status(CreateEmbed::from(embed), &title, status));
+
+        *message = self
+            .backend
+            .edit_message(
+                message.channel_id,
+                message.id,
+                EditMessage::new().embed(embed),
+            )
+            .await
+            .map_err(|err| {
+                error!("Failed marking event '{}' due to '{}'", title, err);
+                DiscordError::Api
+            })?;
+
+        Ok(())
+    }
+
+    /// Sends a DM to whoever saved the event for later or voted on it.
+    ///
+    /// Returns how many users were notified
+    pub async fn notify_status_change(
+        &self,
+        event_message: &Message,
+        status: EventStatus,
+        vote_emojis: &[EmojiConfig; 5],
+        save_for_later_emoji: char,
+    ) -> usize {
+        let mut users: Vec<User> = match self
+            .backend
+            .reaction_users(
+                event_message.channel_id,
+                event_message.id,
+                ReactionType::from(save_for_later_emoji),
+            )
+            .await
+        {
+            Ok(users) => users,
+            Err(e) => {
+                error!("Failed to get save-for-later reaction users: {}", e);
+                Vec::new()
+            }
+        };
+
+        users.extend(
+            self.get_user_votes(event_message, vote_emojis)
+                .await
+                .into_iter()
+                .flatten(),
+        );
+        users.retain(|user| user.id != self.own_user.id && !user.bot);
+        users.sort_by_key(|user| user.id);
+        users.dedup_by_key(|user| user.id);
+
+        let title = event_message
+            .embeds
+            .first()
+            .and_then(|embed| embed.title.clone())
+            .unwrap_or_default()
+            .replace("~~", "");
+        let notice = match status {
+            EventStatus::Scheduled => return 0,
+            EventStatus::Cancelled => "foi cancelado",
+            EventStatus::Postponed => "foi adiado",
+            EventStatus::Removed => "deixou de constar na agenda",
+        };
+        let content = format!(
+            "O evento **{}** que guardaste ou votaste {}: {}",
+            title,
+            notice,
+            event_message.link()
+        );
+
+        let mut notified_count = 0;
+
+        for user in users {
+            let sent = match self.backend.dm_channel(user.id).await {
+                Ok(dm) => self
+                    .backend
+                    .send_message(dm, CreateMessage::new().content(&content))
+                    .await
+                    .map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+
+            match sent {
+                Ok(_) => {
+                    record_status_notice_sent(MetricResult::Ok);
+                    notified_count += 1;
+                }
+                Err(e) => {
+                    record_status_notice_sent(MetricResult::Error);
+                    warn!(
+                        "Failed to notify user '{}' of '{}': {}",
+                        user.name, title, e
+                    );
+                }
+            }
+        }
+
+        notified_count
     }
 
     pub async fn add_custom_reaction(&self, message: &Message, emoji: &EmojiConfig) {
         trace!("Adding reaction");
 
-        match message
+        match self
+            .backend
             .react(
-                &self.client.http,
+                message.channel_id,
+                message.id,
                 Custom {
                     animated: false,
                     id: emoji
@@ -180,20 +557,21 @@ impl DiscordAPI {
     }
 
     pub async fn get_all_messages(&self, channel_id: ChannelId) -> Vec<Message> {
-        channel_id
-            .messages_iter(&self.client.http)
-            .filter_map(|result| async {
-                result
-                    .inspect_err(|e| error!("Failed to fetch messages: {}", e))
-                    .ok()
-            })
-            .collect()
+        self.backend
+            .all_messages(channel_id)
             .await
+            .inspect_err(|e| error!("Failed to fetch messages: {}", e))
+            .unwrap_or_default()
     }
 
     pub async fn add_reaction_to_message(&self, message: &Message, emoji_char: char) {
-        let react_result = message
-            .react(&self.client.http, ReactionType::from(emoji_char))
+        let react_result = self
+            .backend
+            .react(
+                message.channel_id,
+                message.id,
+                ReactionType::from(emoji_char),
+            )
             .await;
 
         debug!(
@@ -228,14 +606,15 @@ impl DiscordAPI {
         //      message is fresh (no need to remove mentions)
         // Helps avoid calling the API for reaction_users, improving performance
         if message.content.is_empty()
-            && Self::has_no_user_emoji_reaction(message, &emoji_char.to_string())
+            && has_no_user_emoji_reaction(message, &emoji_char.to_string())
         {
             trace!("No user has ever saved for later");
             return false;
         }
 
-        let saved_for_later_user_ids: Vec<String> = match message
-            .reaction_users(&self.client.http, save_for_later_reaction, None, None)
+        let saved_for_later_user_ids: Vec<String> = match self
+            .backend
+            .reaction_users(message.channel_id, message.id, save_for_later_reaction)
             .await
         {
             Ok(users) => users
@@ -264,13 +643,13 @@ impl DiscordAPI {
         let mut newly_pinned = false;
 
         if saved_for_later_user_ids.is_empty() && message.pinned {
-            if let Err(e) = message.unpin(&self.client.http).await {
+            if let Err(e) = self.backend.unpin(message.channel_id, message.id).await {
                 error!("Failed to unpin message {}: {}", message.id, e);
             }
         }
 
         if !saved_for_later_user_ids.is_empty() && !message.pinned {
-            match message.pin(&self.client.http).await {
+            match self.backend.pin(message.channel_id, message.id).await {
                 Ok(_) => newly_pinned = true,
                 Err(e) => error!("Failed to pin message {}: {}", message.id, e),
             }
@@ -289,11 +668,16 @@ impl DiscordAPI {
             edit_message = edit_message.content("");
         }
 
-        if let Err(e) = message.edit(&self.client.http, edit_message).await {
-            error!(
+        match self
+            .backend
+            .edit_message(message.channel_id, message.id, edit_message)
+            .await
+        {
+            Ok(edited) => *message = edited,
+            Err(e) => error!(
                 "Failed to edit save-for-later message {}: {}",
                 message.id, e
-            );
+            ),
         }
 
         newly_pinned
@@ -322,7 +706,7 @@ impl DiscordAPI {
         }
 
         for message in pin_notifications {
-            if let Err(e) = message.delete(&self.client.http).await {
+            if let Err(e) = self.backend.delete_message(channel_id, message.id).await {
                 error!(
                     "Failed to delete pin notification message {}: {}",
                     message.id, e
@@ -332,8 +716,9 @@ impl DiscordAPI {
     }
 
     async fn find_pin_notifications(&self, channel_id: ChannelId, limit: usize) -> Vec<Message> {
-        match channel_id
-            .messages(&self.client.http, GetMessages::new().limit(limit as u8))
+        match self
+            .backend
+            .messages(channel_id, None, limit.min(u8::MAX as usize) as u8)
             .await
         {
             Ok(messages) => messages
@@ -350,11 +735,14 @@ impl DiscordAPI {
         }
     }
 
+    /// One message at a time, so a vote seen by both the gateway and the polling pass
+    /// gets a single review
     pub async fn send_privately_users_review(
         &self,
         event_message: &Message,
         vote_emojis: &[EmojiConfig; 5],
     ) -> Vec<UserId> {
+        let _message_lock = self.message_locks.lock(event_message.id).await;
         let mut users_with_reviews = Vec::new();
         let mut event_embed = event_message.embeds.first().cloned().unwrap();
         let event_url = event_embed.url.clone();
@@ -396,14 +784,14 @@ impl DiscordAPI {
         vote_emojis: &[EmojiConfig; 5],
         vote: usize,
     ) {
-        match user.create_dm_channel(&self.client.http).await {
+        match self.backend.dm_channel(user.id).await {
             Ok(dm) => {
                 trace!("Found user {} with vote {}", user.id, vote + 1);
 
-                match self.is_event_sent_in_dm(event_url, &dm).await {
+                match self.is_event_sent_in_dm(event_url, dm).await {
                     Ok(false) => {
                         info!("Sent vote {} for user {}", user.id, vote + 1);
-                        self.send_user_review_in_dm(&vote_emojis[vote], event_embed, &dm)
+                        self.send_user_review_in_dm(&vote_emojis[vote], event_embed, user, dm)
                             .await;
                     }
                     Ok(true) => {
@@ -431,13 +819,15 @@ impl DiscordAPI {
         let mut users_votes: [Vec<User>; 5] = [vec![], vec![], vec![], vec![], vec![]];
 
         for (index, voting_emoji) in vote_emojis.iter().enumerate() {
-            if Self::has_no_user_votes(event_message, voting_emoji) {
+            if has_no_user_votes(event_message, voting_emoji) {
                 continue;
             }
 
-            let users_that_reacted: Vec<User> = match event_message
+            let users_that_reacted: Vec<User> = match self
+                .backend
                 .reaction_users(
-                    &self.client.http,
+                    event_message.channel_id,
+                    event_message.id,
                     Custom {
                         animated: false,
                         id: voting_emoji
@@ -447,8 +837,6 @@ impl DiscordAPI {
                             .expect("Invalid emoji ID format"),
                         name: Some(voting_emoji.name.to_string()),
                     },
-                    None,
-                    None,
                 )
                 .await
             {
@@ -474,73 +862,15 @@ impl DiscordAPI {
         users_votes
     }
 
-    fn has_no_user_votes(event_message: &Message, voting_emoji: &EmojiConfig) -> bool {
-        let reaction = event_message.reactions.iter().find(|reaction| {
-            if let Custom { id, .. } = reaction.reaction_type {
-                id == voting_emoji.id
-            } else {
-                false
-            }
-        });
-
-        if let Some(reaction) = reaction {
-            Self::has_someone_reacted(reaction)
-        } else {
-            warn!(
-                "Message does not have reaction emoji '{}'!",
-                voting_emoji.name
-            );
-            false
-        }
-    }
-
-    fn message_has_bot_reaction(reactions: &[MessageReaction], emoji_char: &str) -> bool {
-        reactions.iter().any(|reaction| {
-            if let Unicode(char) = &reaction.reaction_type {
-                *char == emoji_char && reaction.me
-            } else {
-                false
-            }
-        })
-    }
-
-    fn has_no_user_emoji_reaction(event_message: &Message, emoji_char: &str) -> bool {
-        let reaction = event_message.reactions.iter().find(|reaction| {
-            if let Unicode(char) = &reaction.reaction_type {
-                *char == emoji_char
-            } else {
-                false
-            }
-        });
-
-        if let Some(reaction) = reaction {
-            Self::has_someone_reacted(reaction)
-        } else {
-            warn!("Message does not have saved for later emoji!");
-            false
-        }
-    }
-
-    fn has_someone_reacted(reaction: &MessageReaction) -> bool {
-        if reaction.count == 1 {
-            // No one has voted
-            if reaction.me {
-                return true;
-            } else {
-                warn!("Self did not react!")
-            }
-        }
-        false
-    }
-
     async fn send_user_review_in_dm(
         &self,
         vote_emoji: &EmojiConfig,
         event_embed: Embed,
-        dm: &PrivateChannel,
+        user: &User,
+        dm: ChannelId,
     ) {
         info!(
-            user_name = %dm.recipient.name,
+            user_name = %user.name,
             vote_emoji = %vote_emoji,
             event = %event_embed.title.as_deref().unwrap_or("no_title"),
             "Sending vote"
@@ -548,14 +878,15 @@ impl DiscordAPI {
 
         let comment = self.get_user_last_comment(dm).await;
 
-        let embed = Self::create_user_review_embed(
+        let embed = create_user_review_embed(
             vote_emoji,
             event_embed,
             comment.as_ref().map(|m| m.content.as_str()),
         );
 
-        match dm
-            .send_message(&self.client.http, CreateMessage::new().embed(embed))
+        match self
+            .backend
+            .send_message(dm, CreateMessage::new().embed(embed))
             .await
         {
             Ok(_) => {
@@ -567,41 +898,30 @@ impl DiscordAPI {
             }
             Err(e) => {
                 record_dm_review_sent(MetricResult::Error);
-                error!("Failed to send review DM to {}: {}", dm.recipient.name, e);
+                error!("Failed to send review DM to {}: {}", user.name, e);
             }
         }
     }
 
-    fn create_user_review_embed(
-        vote_emoji: &EmojiConfig,
-        event_embed: Embed,
-        comment: Option<&str>,
-    ) -> CreateEmbed {
-        match comment {
-            None => CreateEmbed::from(event_embed).field("Voto", vote_emoji.to_string(), true),
-            Some(comment) => CreateEmbed::from(event_embed)
-                .field("Voto", vote_emoji.to_string(), true)
-                .field("Comentários", comment, true),
-        }
-    }
-
+    #[allow(clippy::too_many_arguments)]
     pub async fn send_backfill_review(
         &self,
+        source: &impl EventSource,
         user_id: UserId,
         event_url: &str,
         vote_emoji: &EmojiConfig,
         comment: Option<&str>,
-        venue_ticket_shop_url: &HashMap<String, String>,
+        venue_ticket_shops: &VenueTicketShops,
         ticket_shop_icon_url: &str,
     ) -> Result<bool, ()> {
-        let Some(event) = AgendaCulturalAPI::scrape_event(event_url).await else {
+        let Some(event) = source.scrape_event(event_url).await else {
             warn!("Could not scrape event details for '{}'", event_url);
             return Ok(false);
         };
 
-        let ticket_shop_url = venue_ticket_shop_url.get(&event.venue).cloned();
+        let ticket_shop_url = venue_ticket_shops.find(&event.venue);
 
-        let dm = match user_id.create_dm_channel(&self.client.http).await {
+        let dm = match self.backend.dm_channel(user_id).await {
             Ok(dm) => dm,
             Err(e) => {
                 error!("Couldn't create DM channel for user '{}': {}", user_id, e);
@@ -609,7 +929,7 @@ impl DiscordAPI {
             }
         };
 
-        match self.is_event_sent_in_dm(event_url, &dm).await {
+        match self.is_event_sent_in_dm(event_url, dm).await {
             Ok(true) => {
                 warn!("Event already sent to user {}", user_id);
                 return Ok(false);
@@ -618,16 +938,17 @@ impl DiscordAPI {
             Ok(false) => {}
         }
 
-        let mut embed =
-            Self::build_event_embed(event, ticket_shop_url, ticket_shop_icon_url)
-                .field("Voto", vote_emoji.to_string(), true);
+        let mut embed = self
+            .build_event_embed(event, ticket_shop_url, ticket_shop_icon_url, false)
+            .field("Voto", vote_emoji.to_string(), true);
 
         if let Some(comment) = comment {
             embed = embed.field("Comentários", comment, true);
         }
 
-        match dm
-            .send_message(&self.client.http, CreateMessage::new().embed(embed))
+        match self
+            .backend
+            .send_message(dm, CreateMessage::new().embed(fit_embed(embed)))
             .await
         {
             Ok(_) => {
@@ -644,7 +965,7 @@ impl DiscordAPI {
     }
 
     pub async fn rewrite_reviews_from_dm_replies(&self, user_id: UserId) -> usize {
-        let dm = match user_id.create_dm_channel(&self.client.http).await {
+        let dm = match self.backend.dm_channel(user_id).await {
             Ok(dm) => dm,
             Err(e) => {
                 warn!("Couldn't create DM channel for user '{}': {}", user_id, e);
@@ -652,7 +973,7 @@ impl DiscordAPI {
             }
         };
 
-        let mut messages = match self.fetch_all_dm_messages(&dm).await {
+        let mut messages = match self.fetch_all_dm_messages(dm).await {
             Ok(messages) => messages,
             Err(_) => return 0,
         };
@@ -665,11 +986,11 @@ impl DiscordAPI {
         let mut rewritten_count = 0;
 
         for reply in &messages {
-            if !Self::is_message_a_rewrite_request(self.own_user.id, reply) {
+            if !is_message_a_rewrite_request(self.own_user.id, reply) {
                 continue;
             }
 
-            if self.rewrite_review_from_reply(&dm, reply).await {
+            if self.rewrite_review_from_reply(dm, reply).await {
                 rewritten_count += 1;
             }
         }
@@ -677,46 +998,19 @@ impl DiscordAPI {
         rewritten_count
     }
 
-    fn is_message_a_rewrite_request(own_user_id: UserId, reply: &Message) -> bool {
-        let is_a_user_message = reply.author.id != own_user_id;
-
-        is_a_user_message
-            && reply.referenced_message.as_ref().is_some_and(|referenced| {
-                let is_a_reply_to_bot_message = referenced.author.id == own_user_id;
-                let has_vote = referenced
-                    .embeds
-                    .first()
-                    .is_some_and(|embed| embed.fields.iter().any(|field| field.name == "Voto"));
-
-                is_a_reply_to_bot_message && has_vote
-            })
-            && !Self::message_has_bot_reaction(
-                &reply.reactions,
-                &PROCESSED_COMMENT_EMOJI.to_string(),
-            )
-    }
-
-    async fn fetch_all_dm_messages(
-        &self,
-        dm: &PrivateChannel,
-    ) -> Result<Vec<Message>, serenity::Error> {
+    async fn fetch_all_dm_messages(&self, dm: ChannelId) -> Result<Vec<Message>, serenity::Error> {
         let mut all_messages = Vec::new();
         let mut last_message_id: Option<MessageId> = None;
 
         loop {
-            let mut filter = GetMessages::default();
-
-            if let Some(id) = last_message_id {
-                filter = filter.before(id);
-            }
-
-            let page = dm.messages(&self.client.http, filter).await.map_err(|e| {
-                error!(
-                    "Failed to fetch DM messages for '{}': {}",
-                    dm.recipient.name, e
-                );
-                e
-            })?;
+            let page = self
+                .backend
+                .messages(dm, last_message_id, DEFAULT_PAGE_SIZE)
+                .await
+                .map_err(|e| {
+                    error!("Failed to fetch DM messages of {}: {}", dm, e);
+                    e
+                })?;
 
             match page.last() {
                 None => break,
@@ -729,7 +1023,7 @@ impl DiscordAPI {
         Ok(all_messages)
     }
 
-    async fn rewrite_review_from_reply(&self, dm: &PrivateChannel, reply: &Message) -> bool {
+    async fn rewrite_review_from_reply(&self, dm: ChannelId, reply: &Message) -> bool {
         let referenced = reply
             .referenced_message
             .as_ref()
@@ -748,7 +1042,7 @@ impl DiscordAPI {
             .value
             .clone();
 
-        let mut fresh = match self.client.http.get_message(dm.id, referenced.id).await {
+        let fresh = match self.backend.message(dm, referenced.id).await {
             Ok(message) => message,
             Err(e) => {
                 error!(
@@ -764,12 +1058,15 @@ impl DiscordAPI {
         };
         fresh_embed.fields = Vec::new();
 
-        let new_embed = CreateEmbed::from(fresh_embed)
-            .field("Voto", voto_value, true)
-            .field("Comentários", reply.content.clone(), true);
+        let new_embed = fit_embed(
+            CreateEmbed::from(fresh_embed)
+                .field("Voto", voto_value, true)
+                .field("Comentários", reply.content.clone(), true),
+        );
 
-        match fresh
-            .edit(&self.client.http, EditMessage::new().embed(new_embed))
+        match self
+            .backend
+            .edit_message(dm, fresh.id, EditMessage::new().embed(new_embed))
             .await
         {
             Ok(_) => {
@@ -789,55 +1086,46 @@ impl DiscordAPI {
         }
     }
 
-    async fn get_user_last_comment(&self, dm: &PrivateChannel) -> Option<Message> {
-        match dm.last_message_id {
-            Some(last_message_id) => {
-                self.client
-                    .http
-                    .get_message(dm.id, last_message_id)
-                    .await
-                    .inspect_err(|e| {
-                        warn!("Failed to get last message: {}", e);
-                    })
-                    .ok()
-                    .take_if(|msg| msg.author != *self.own_user)
-                    // a reply will be used in another feature
-                    .take_if(|msg| {
-                        let is_a_reply = msg.referenced_message.is_some();
-
-                        if is_a_reply {
-                            debug!("Ignoring last message since it's reply to another");
-                        }
+    async fn get_user_last_comment(&self, dm: ChannelId) -> Option<Message> {
+        self.backend
+            .messages(dm, None, 1)
+            .await
+            .inspect_err(|e| {
+                warn!("Failed to get last message: {}", e);
+            })
+            .ok()?
+            .into_iter()
+            .next()
+            .take_if(|msg| msg.author.id != self.own_user.id)
+            // a reply will be used in another feature
+            .take_if(|msg| {
+                let is_a_reply = msg.referenced_message.is_some();
+
+                if is_a_reply {
+                    debug!("Ignoring last message since it's reply to another");
+                }
 
-                        !is_a_reply
-                    })
-            }
-            None => None,
-        }
+                !is_a_reply
+            })
     }
 
     async fn is_event_sent_in_dm(
         &self,
         event_url: &str,
-        dm: &PrivateChannel,
+        dm: ChannelId,
     ) -> Result<bool, serenity::Error> {
         let mut last_message_id: Option<MessageId> = None;
         let mut searched_all_dms = false;
 
         while !searched_all_dms {
-            let mut filter = GetMessages::default();
-
-            if let Some(last_message_id) = last_message_id {
-                filter = filter.before(last_message_id)
-            }
-
-            let messages_iter = dm.messages(&self.client.http, filter).await.map_err(|e| {
-                error!(
-                    "Failed to fetch DM messages for '{}': {}",
-                    dm.recipient.name, e
-                );
-                e
-            })?;
+            let messages_iter = self
+                .backend
+                .messages(dm, last_message_id, DEFAULT_PAGE_SIZE)
+                .await
+                .map_err(|e| {
+                    error!("Failed to fetch DM messages of {}: {}", dm, e);
+                    e
+                })?;
 
             if messages_iter.iter().any(|msg| {
                 msg.embeds
@@ -860,70 +1148,59 @@ impl DiscordAPI {
         Ok(false)
     }
 
-    pub async fn get_guild(&self, channel_id: ChannelId) -> PartialGuild {
-        let guild_channel = channel_id
-            .to_channel(&self.client.http)
-            .await
-            .expect("Could not get channel")
-            .guild()
-            .expect("Channel does not appear to of a guild");
-        guild_channel
-            .guild_id
-            .to_partial_guild(&self.client.http)
+    pub async fn get_guild(&self, channel_id: ChannelId) -> GuildId {
+        self.backend
+            .guild_id(channel_id)
             .await
-            .unwrap()
+            .expect("Channel does not appear to of a guild")
     }
 
     pub async fn get_channel_threads(
         &self,
-        guild: &PartialGuild,
+        guild_id: GuildId,
         channel_id: ChannelId,
     ) -> Vec<GuildChannel> {
         self.unarchive_archived_threads(channel_id).await;
 
         debug("Unarchived archived threads");
 
-        let active_threads: Vec<GuildChannel> = guild
-            .get_active_threads(&self.client.http)
+        let active_threads: Vec<GuildChannel> = self
+            .backend
+            .active_threads(guild_id)
             .await
             .unwrap()
-            .threads
             .into_iter()
             .filter(|thread| thread.parent_id == Some(channel_id))
             .collect();
 
         debug!(
             "Found threads: [{:?}]",
-            Self::concat_thread_names(&active_threads)
+            concat_thread_names(&active_threads)
         );
 
         active_threads
     }
 
     async fn unarchive_archived_threads(&self, channel_id: ChannelId) {
-        let mut archived_threads = channel_id
-            .get_archived_public_threads(&self.client.http, None, None)
+        let archived_threads = self
+            .backend
+            .archived_public_threads(channel_id)
             .await
-            .expect("Could not get archived threads")
-            .threads;
+            .expect("Could not get archived threads");
 
         debug!(
             "Found archived threads: [{:?}]",
-            Self::concat_thread_names(&archived_threads)
+            concat_thread_names(&archived_threads)
         );
 
-        for thread in &mut archived_threads {
-            thread
-                .edit_thread(&self.client.http, EditThread::new().archived(false))
+        for thread in &archived_threads {
+            self.backend
+                .unarchive_thread(thread.id)
                 .await
                 .expect("Failed to unarchive archived threads!")
         }
     }
 
-    fn concat_thread_names(threads: &[GuildChannel]) -> String {
-        threads.iter().map(|thread| thread.name.as_str()).join(",")
-    }
-
     pub async fn get_date_thread(
         &self,
         threads: &[GuildChannel],
@@ -940,47 +1217,30 @@ impl DiscordAPI {
         }
 
         EventsThread::new(
-            channel_id
-                .create_thread(
-                    &self.client.http,
-                    CreateThread::new(format!("{month_in_portuguese} {year}"))
-                        .kind(ChannelType::PublicThread)
-                        .auto_archive_duration(AutoArchiveDuration::OneWeek),
-                )
+            self.backend
+                .create_thread(channel_id, &format!("{month_in_portuguese} {year}"))
                 .await
                 .unwrap()
                 .id,
         )
     }
 
-    pub async fn get_event_urls_sent(&self, channel_id: ChannelId) -> Vec<String> {
-        channel_id
-            .messages_iter(&self.client.http)
-            .map::<_, fn(_) -> Vec<Embed>>(|message: Result<Message, SerenityError>| {
-                message.expect("Error getting message").embeds
-            })
-            .concat()
-            .await
-            .iter()
-            .filter_map(|embed| embed.url.clone())
-            .collect()
-    }
-
     pub async fn delete_all_messages(&self, channel_id: &ChannelId) {
-        let messages = channel_id
-            .messages_iter(&self.client.http)
-            .try_collect::<Vec<Message>>()
+        let messages = self
+            .backend
+            .all_messages(*channel_id)
             .await
             .expect("Failed to fetch messages");
 
         self.delete_messages(channel_id, &messages).await;
+        self.sent_events.forget_channel(*channel_id);
 
-        let guild = self.get_guild(*channel_id).await;
-        let threads = self.get_channel_threads(&guild, *channel_id).await;
+        let guild_id = self.get_guild(*channel_id).await;
+        let threads = self.get_channel_threads(guild_id, *channel_id).await;
 
         for thread in threads {
-            thread
-                .delete(&self.client.http)
+            self.backend
+                .delete_channel(thread.id)
                 .await
                 .expect("Failed to delete threads!");
         }
@@ -989,35 +1249,196 @@ impl DiscordAPI {
     async fn delete_messages(&self, channel_id: &ChannelId, messages: &[Message]) {
         for chunk in messages.chunks(100) {
             debug!("Deleting {} messages", chunk.len());
-            let deletion_result = channel_id.delete_messages(&self.client.http, chunk).await;
+            let message_ids: Vec<MessageId> = chunk.iter().map(|message| message.id).collect();
+            let deletion_result = self
+                .backend
+                .delete_messages(*channel_id, &message_ids)
+                .await;
 
             if let Err(err) = deletion_result {
                 warn!("Failed due to: '{}'. Retrying individually", err);
 
-                for msg in chunk {
-                    msg.delete(&self.client.http)
+                for message_id in message_ids {
+                    self.backend
+                        .delete_message(*channel_id, message_id)
                         .await
                         .expect("Failed to delete one of the messages individually");
                 }
             }
         }
     }
+}
+
+pub fn is_event_reference(message: &Message) -> bool {
+    message.embeds.is_empty() && message.content.starts_with(REFERENCE_PREFIX)
+}
+
+pub fn is_ongoing_events_roll_up(message: &Message) -> bool {
+    message.embeds.is_empty() && message.content.starts_with(ROLL_UP_HEADER)
+}
+
+/// Whether it's one of the events the bot sent, which users vote on and save for later
+pub fn is_event_message(own_user_id: UserId, message: &Message) -> bool {
+    if message.author.id != own_user_id {
+        debug!(
+            "Ignoring message from a different user {}",
+            message.author.id
+        );
+        return false;
+    }
+
+    if message.kind != MessageType::Regular {
+        trace!("Ignoring non-regular message (id={})", message.id);
+        return false;
+    }
+
+    if is_event_reference(message) || is_ongoing_events_roll_up(message) {
+        trace!("Ignoring event reference or roll-up (id={})", message.id);
+        return false;
+    }
+
+    if message.embeds.is_empty() {
+        warn!(
+            "Found message without embed (id={}; content={})",
+            message.id, message.content
+        );
+        return false;
+    }
 
-    fn truncate_embed_description(description: String) -> String {
-        Self::truncate(description, 4096)
+    true
+}
+
+pub fn has_status(message: &Message) -> bool {
+    message
+        .embeds
+        .first()
+        .is_some_and(|embed| embed.fields.iter().any(|field| field.name == STATUS_FIELD))
+}
+
+/// Strikes the title through, colours it red and adds an "Estado" field
+fn mark_embed_status(embed: CreateEmbed, title: &str, status: EventStatus) -> CreateEmbed {
+    embed
+        .title(format!("~~{}~~", title))
+        .color(Colour::new(0xd62d20))
+        .field(STATUS_FIELD, status.to_string(), true)
+}
+
+/// Badges shown below the description (e.g. "👤 M/6", "🤟 interpretação em LGP")
+fn build_event_labels(audience: &Audience, is_highlighted: bool) -> Vec<String> {
+    let mut labels = Vec::new();
+
+    if is_highlighted {
+        labels.push(FREE_LABEL.to_string());
+    }
+
+    if let Some(age_range) = audience.age_range {
+        labels.push(format!("👤 {}", age_range));
+    }
+
+    for accessibility in &audience.accessibility {
+        let emoji = match accessibility {
+            Accessibility::SignLanguage => "🤟",
+            Accessibility::AudioDescription => "🎧",
+            Accessibility::RelaxedPerformance => "🌿",
+            Accessibility::Other(_) => "♿",
+        };
+
+        labels.push(format!("{} {}", emoji, accessibility));
+    }
+
+    labels
+}
+
+fn has_no_user_votes(event_message: &Message, voting_emoji: &EmojiConfig) -> bool {
+    let reaction = event_message.reactions.iter().find(|reaction| {
+        if let Custom { id, .. } = reaction.reaction_type {
+            id == voting_emoji.id
+        } else {
+            false
+        }
+    });
+
+    if let Some(reaction) = reaction {
+        has_someone_reacted(reaction)
+    } else {
+        warn!(
+            "Message does not have reaction emoji '{}'!",
+            voting_emoji.name
+        );
+        false
     }
+}
+
+fn message_has_bot_reaction(reactions: &[MessageReaction], emoji_char: &str) -> bool {
+    reactions.iter().any(|reaction| {
+        if let Unicode(char) = &reaction.reaction_type {
+            *char == emoji_char && reaction.me
+        } else {
+            false
+        }
+    })
+}
 
-    fn truncate(description: String, length: usize) -> String {
-        if description.len() > length {
-            let mut embed_description = description.clone();
+fn has_no_user_emoji_reaction(event_message: &Message, emoji_char: &str) -> bool {
+    let reaction = event_message.reactions.iter().find(|reaction| {
+        if let Unicode(char) = &reaction.reaction_type {
+            *char == emoji_char
+        } else {
+            false
+        }
+    });
 
-            embed_description.truncate(length);
+    if let Some(reaction) = reaction {
+        has_someone_reacted(reaction)
+    } else {
+        warn!("Message does not have saved for later emoji!");
+        false
+    }
+}
 
-            format!("{embed_description}...")
+fn has_someone_reacted(reaction: &MessageReaction) -> bool {
+    if reaction.count == 1 {
+        // No one has voted
+        if reaction.me {
+            return true;
         } else {
-            description
+            warn!("Self did not react!")
         }
     }
+    false
+}
+
+fn create_user_review_embed(
+    vote_emoji: &EmojiConfig,
+    event_embed: Embed,
+    comment: Option<&str>,
+) -> CreateEmbed {
+    fit_embed(match comment {
+        None => CreateEmbed::from(event_embed).field("Voto", vote_emoji.to_string(), true),
+        Some(comment) => CreateEmbed::from(event_embed)
+            .field("Voto", vote_emoji.to_string(), true)
+            .field("Comentários", comment, true),
+    })
+}
+
+fn is_message_a_rewrite_request(own_user_id: UserId, reply: &Message) -> bool {
+    let is_a_user_message = reply.author.id != own_user_id;
+
+    is_a_user_message
+        && reply.referenced_message.as_ref().is_some_and(|referenced| {
+            let is_a_reply_to_bot_message = referenced.author.id == own_user_id;
+            let has_vote = referenced
+                .embeds
+                .first()
+                .is_some_and(|embed| embed.fields.iter().any(|field| field.name == "Voto"));
+
+            is_a_reply_to_bot_message && has_vote
+        })
+        && !message_has_bot_reaction(&reply.reactions, &PROCESSED_COMMENT_EMOJI.to_string())
+}
+
+fn concat_thread_names(threads: &[GuildChannel]) -> String {
+    threads.iter().map(|thread| thread.name.as_str()).join(",")
 }
 
 #[cfg(test)]
@@ -1093,7 +1514,7 @@ mod tests {
     fn when_reply_is_from_bot_itself_should_return_false() {
         let reply = build_reply_to_bot_review(BOT_USER_ID, true, false);
 
-        assert!(!DiscordAPI::is_message_a_rewrite_request(
+        assert!(!is_message_a_rewrite_request(
             UserId::from(BOT_USER_ID),
             &reply
         ));
@@ -1103,7 +1524,7 @@ mod tests {
     fn when_message_is_not_a_reply_should_return_false() {
         let reply = build_message(OTHER_USER_ID, None);
 
-        assert!(!DiscordAPI::is_message_a_rewrite_request(
+        assert!(!is_message_a_rewrite_request(
             UserId::from(BOT_USER_ID),
             &reply
         ));
@@ -1115,7 +1536,7 @@ mod tests {
         let mut reply = build_message(OTHER_USER_ID, None);
         reply.referenced_message = Some(Box::new(referenced));
 
-        assert!(!DiscordAPI::is_message_a_rewrite_request(
+        assert!(!is_message_a_rewrite_request(
             UserId::from(BOT_USER_ID),
             &reply
         ));
@@ -1125,7 +1546,7 @@ mod tests {
     fn when_referenced_bot_message_has_no_vote_field_should_return_false() {
         let reply = build_reply_to_bot_review(OTHER_USER_ID, false, false);
 
-        assert!(!DiscordAPI::is_message_a_rewrite_request(
+        assert!(!is_message_a_rewrite_request(
             UserId::from(BOT_USER_ID),
             &reply
         ));
@@ -1135,7 +1556,7 @@ mod tests {
     fn when_reply_already_processed_should_return_false() {
         let reply = build_reply_to_bot_review(OTHER_USER_ID, true, true);
 
-        assert!(!DiscordAPI::is_message_a_rewrite_request(
+        assert!(!is_message_a_rewrite_request(
             UserId::from(BOT_USER_ID),
             &reply
         ));
@@ -1145,7 +1566,7 @@ mod tests {
     fn when_reply_is_a_valid_unprocessed_rewrite_request_should_return_true() {
         let reply = build_reply_to_bot_review(OTHER_USER_ID, true, false);
 
-        assert!(DiscordAPI::is_message_a_rewrite_request(
+        assert!(is_message_a_rewrite_request(
             UserId::from(BOT_USER_ID),
             &reply
         ));
@@ -1167,7 +1588,7 @@ mod tests {
         )
         .unwrap();
 
-        assert!(DiscordAPI::message_has_bot_reaction(&reactions, "✅"));
+        assert!(message_has_bot_reaction(&reactions, "✅"));
     }
 
     #[test_log::test]
@@ -1186,7 +1607,7 @@ mod tests {
         )
         .unwrap();
 
-        assert!(!DiscordAPI::message_has_bot_reaction(&reactions, "✅"));
+        assert!(!message_has_bot_reaction(&reactions, "✅"));
     }
 
     #[test_log::test]
@@ -1205,7 +1626,7 @@ mod tests {
         )
         .unwrap();
 
-        assert!(!DiscordAPI::message_has_bot_reaction(&reactions, "✅"));
+        assert!(!message_has_bot_reaction(&reactions, "✅"));
     }
 
     #[test_log::test]
@@ -1226,7 +1647,7 @@ mod tests {
         "#,
         )
         .unwrap();
-        let has_no_user_reactions = DiscordAPI::has_someone_reacted(&reaction);
+        let has_no_user_reactions = has_someone_reacted(&reaction);
 
         assert!(has_no_user_reactions);
     }
@@ -1249,7 +1670,7 @@ mod tests {
         "#,
         )
         .unwrap();
-        let has_no_user_reactions = DiscordAPI::has_someone_reacted(&reaction);
+        let has_no_user_reactions = has_someone_reacted(&reaction);
 
         assert!(!has_no_user_reactions);
     }
@@ -1272,16 +1693,49 @@ mod tests {
         "#,
         )
         .unwrap();
-        let has_no_user_reactions = DiscordAPI::has_someone_reacted(&reaction);
+        let has_no_user_reactions = has_someone_reacted(&reaction);
 
         assert!(!has_no_user_reactions);
     }
+
+    #[test_log::test]
+    fn should_build_audience_labels() {
+        let audience = Audience::parse(&["M/6".to_string()], &["Interpretação em LGP".to_string()]);
+
+        assert_eq!(
+            build_event_labels(&audience, false),
+            ["👤 M/6", "🤟 interpretação em LGP"]
+        );
+    }
+
+    #[test_log::test]
+    fn when_audience_is_unknown_should_have_no_labels() {
+        assert!(build_event_labels(&Audience::default(), false).is_empty());
+    }
+
+    #[test_log::test]
+    fn should_read_month_from_thread_name() {
+        assert_eq!(
+            thread_name_to_month("Março 2025"),
+            NaiveDate::from_ymd_opt(2025, 3, 1)
+        );
+        assert_eq!(thread_name_to_month("Março"), None);
+        assert_eq!(thread_name_to_month("Geral 2025"), None);
+    }
 }
 
 pub fn month_to_portuguese_display(date: &NaiveDate) -> String {
     PORTUGUESE_MONTHS[(date.month() - 1) as usize].to_string()
 }
 
+/// First day of the month of a thread named like "Março 2025"
+pub fn thread_name_to_month(thread_name: &str) -> Option<NaiveDate> {
+    let (month, year) = thread_name.rsplit_once(' ')?;
+    let month = PORTUGUESE_MONTHS.iter().position(|name| *name == month)?;
+
+    NaiveDate::from_ymd_opt(year.parse().ok()?, month as u32 + 1, 1)
+}
+
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
 pub struct EventsThread {
     pub thread_id: ChannelId,
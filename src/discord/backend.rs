@@ -0,0 +1,305 @@
+use futures::TryStreamExt;
+use serenity::all::{
+    AutoArchiveDuration, ChannelId, ChannelType, CreateMessage, CreateThread, CurrentUser,
+    EditMessage, EditThread, GatewayIntents, GetMessages, GuildChannel, GuildId, Message,
+    MessageId, ReactionType, User, UserId,
+};
+use serenity::cache::Settings;
+use serenity::client::ClientBuilder;
+use serenity::http::Http;
+use serenity::Client;
+use std::future::Future;
+use std::sync::Arc;
+
+pub type ChatResult<T> = Result<T, serenity::Error>;
+
+/// Messages Discord returns per page when no limit is given
+pub const DEFAULT_PAGE_SIZE: u8 = 50;
+
+/// The chat operations the pipeline relies on (e.g. Discord's HTTP API), so it can run
+/// against an in-memory chat in tests.
+/// Messages are listed newest first, as Discord does
+pub trait ChatBackend: Send + Sync {
+    fn current_user(&self) -> impl Future<Output = ChatResult<CurrentUser>> + Send;
+
+    fn guild_id(&self, channel_id: ChannelId) -> impl Future<Output = ChatResult<GuildId>> + Send;
+
+    fn channel_name(
+        &self,
+        channel_id: ChannelId,
+    ) -> impl Future<Output = ChatResult<String>> + Send;
+
+    /// Threads of any channel in the guild that aren't archived
+    fn active_threads(
+        &self,
+        guild_id: GuildId,
+    ) -> impl Future<Output = ChatResult<Vec<GuildChannel>>> + Send;
+
+    fn archived_public_threads(
+        &self,
+        channel_id: ChannelId,
+    ) -> impl Future<Output = ChatResult<Vec<GuildChannel>>> + Send;
+
+    fn unarchive_thread(&self, thread_id: ChannelId)
+        -> impl Future<Output = ChatResult<()>> + Send;
+
+    /// A public thread that is archived after a week of inactivity
+    fn create_thread(
+        &self,
+        channel_id: ChannelId,
+        name: &str,
+    ) -> impl Future<Output = ChatResult<GuildChannel>> + Send;
+
+    fn delete_channel(&self, channel_id: ChannelId) -> impl Future<Output = ChatResult<()>> + Send;
+
+    /// Every message of the channel
+    fn all_messages(
+        &self,
+        channel_id: ChannelId,
+    ) -> impl Future<Output = ChatResult<Vec<Message>>> + Send;
+
+    /// A page of up to `limit` messages, older than `before` when set
+    fn messages(
+        &self,
+        channel_id: ChannelId,
+        before: Option<MessageId>,
+        limit: u8,
+    ) -> impl Future<Output = ChatResult<Vec<Message>>> + Send;
+
+    fn message(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+    ) -> impl Future<Output = ChatResult<Message>> + Send;
+
+    fn send_message(
+        &self,
+        channel_id: ChannelId,
+        builder: CreateMessage,
+    ) -> impl Future<Output = ChatResult<Message>> + Send;
+
+    fn edit_message(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        builder: EditMessage,
+    ) -> impl Future<Output = ChatResult<Message>> + Send;
+
+    fn delete_message(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+    ) -> impl Future<Output = ChatResult<()>> + Send;
+
+    fn delete_messages(
+        &self,
+        channel_id: ChannelId,
+        message_ids: &[MessageId],
+    ) -> impl Future<Output = ChatResult<()>> + Send;
+
+    fn pin(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+    ) -> impl Future<Output = ChatResult<()>> + Send;
+
+    fn unpin(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+    ) -> impl Future<Output = ChatResult<()>> + Send;
+
+    fn react(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        reaction_type: ReactionType,
+    ) -> impl Future<Output = ChatResult<()>> + Send;
+
+    fn reaction_users(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        reaction_type: ReactionType,
+    ) -> impl Future<Output = ChatResult<Vec<User>>> + Send;
+
+    /// The DM channel with the user, created if needed
+    fn dm_channel(&self, user_id: UserId) -> impl Future<Output = ChatResult<ChannelId>> + Send;
+}
+
+/// A client with the intents the bot needs, to connect to the gateway or just for its HTTP API
+pub fn client_builder(token: &str, cache_flag: bool) -> ClientBuilder {
+    let intents = GatewayIntents::GUILD_MESSAGES
+        | GatewayIntents::MESSAGE_CONTENT
+        | GatewayIntents::GUILD_MESSAGE_REACTIONS;
+    let mut cache_settings = Settings::default();
+
+    cache_settings.cache_channels = cache_flag;
+
+    Client::builder(token, intents).cache_settings(cache_settings)
+}
+
+/// Discord, through its HTTP API
+pub struct SerenityChat {
+    pub http: Arc<Http>,
+}
+
+impl SerenityChat {
+    pub async fn new(token: &str, cache_flag: bool) -> Self {
+        let client = client_builder(token, cache_flag)
+            .await
+            .expect("Error creating discord client");
+
+        Self::from_client(&client)
+    }
+
+    /// Shares the HTTP API of a client, which may also be connected to the gateway
+    pub fn from_client(client: &Client) -> Self {
+        Self {
+            http: client.http.clone(),
+        }
+    }
+}
+
+impl ChatBackend for SerenityChat {
+    async fn current_user(&self) -> ChatResult<CurrentUser> {
+        self.http.get_current_user().await
+    }
+
+    async fn guild_id(&self, channel_id: ChannelId) -> ChatResult<GuildId> {
+        let channel = channel_id.to_channel(&self.http).await?;
+
+        channel
+            .guild()
+            .map(|guild_channel| guild_channel.guild_id)
+            .ok_or(serenity::Error::Other("Channel is not in a guild"))
+    }
+
+    async fn channel_name(&self, channel_id: ChannelId) -> ChatResult<String> {
+        channel_id.name(&self.http).await
+    }
+
+    async fn active_threads(&self, guild_id: GuildId) -> ChatResult<Vec<GuildChannel>> {
+        Ok(guild_id.get_active_threads(&self.http).await?.threads)
+    }
+
+    async fn archived_public_threads(
+        &self,
+        channel_id: ChannelId,
+    ) -> ChatResult<Vec<GuildChannel>> {
+        Ok(channel_id
+            .get_archived_public_threads(&self.http, None, None)
+            .await?
+            .threads)
+    }
+
+    async fn unarchive_thread(&self, thread_id: ChannelId) -> ChatResult<()> {
+        thread_id
+            .edit_thread(&self.http, EditThread::new().archived(false))
+            .await
+            .map(|_| ())
+    }
+
+    async fn create_thread(&self, channel_id: ChannelId, name: &str) -> ChatResult<GuildChannel> {
+        channel_id
+            .create_thread(
+                &self.http,
+                CreateThread::new(name)
+                    .kind(ChannelType::PublicThread)
+                    .auto_archive_duration(AutoArchiveDuration::OneWeek),
+            )
+            .await
+    }
+
+    async fn delete_channel(&self, channel_id: ChannelId) -> ChatResult<()> {
+        channel_id.delete(&self.http).await.map(|_| ())
+    }
+
+    async fn all_messages(&self, channel_id: ChannelId) -> ChatResult<Vec<Message>> {
+        channel_id.messages_iter(&self.http).try_collect().await
+    }
+
+    async fn messages(
+        &self,
+        channel_id: ChannelId,
+        before: Option<MessageId>,
+        limit: u8,
+    ) -> ChatResult<Vec<Message>> {
+        let mut filter = GetMessages::new().limit(limit);
+
+        if let Some(before) = before {
+            filter = filter.before(before);
+        }
+
+        channel_id.messages(&self.http, filter).await
+    }
+
+    async fn message(&self, channel_id: ChannelId, message_id: MessageId) -> ChatResult<Message> {
+        self.http.get_message(channel_id, message_id).await
+    }
+
+    async fn send_message(
+        &self,
+        channel_id: ChannelId,
+        builder: CreateMessage,
+    ) -> ChatResult<Message> {
+        channel_id.send_message(&self.http, builder).await
+    }
+
+    async fn edit_message(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        builder: EditMessage,
+    ) -> ChatResult<Message> {
+        channel_id
+            .edit_message(&self.http, message_id, builder)
+            .await
+    }
+
+    async fn delete_message(&self, channel_id: ChannelId, message_id: MessageId) -> ChatResult<()> {
+        channel_id.delete_message(&self.http, message_id).await
+    }
+
+    async fn delete_messages(
+        &self,
+        channel_id: ChannelId,
+        message_ids: &[MessageId],
+    ) -> ChatResult<()> {
+        channel_id.delete_messages(&self.http, message_ids).await
+    }
+
+    async fn pin(&self, channel_id: ChannelId, message_id: MessageId) -> ChatResult<()> {
+        channel_id.pin(&self.http, message_id).await
+    }
+
+    async fn unpin(&self, channel_id: ChannelId, message_id: MessageId) -> ChatResult<()> {
+        channel_id.unpin(&self.http, message_id).await
+    }
+
+    async fn react(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        reaction_type: ReactionType,
+    ) -> ChatResult<()> {
+        channel_id
+            .create_reaction(&self.http, message_id, reaction_type)
+            .await
+    }
+
+    async fn reaction_users(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        reaction_type: ReactionType,
+    ) -> ChatResult<Vec<User>> {
+        channel_id
+            .reaction_users(&self.http, message_id, reaction_type, None, None)
+            .await
+    }
+
+    async fn dm_channel(&self, user_id: UserId) -> ChatResult<ChannelId> {
+        user_id.create_dm_channel(&self.http).await.map(|dm| dm.id)
+    }
+}
@@ -1,20 +1,24 @@
 use crate::discord::api::DiscordAPI;
+use crate::discord::backend::{ChatBackend, DEFAULT_PAGE_SIZE};
 use serde::Serialize;
-use serenity::all::{GetMessages, Message, MessageType, UserId};
+use serenity::all::{Message, MessageType, UserId};
 use tracing::{error, info, instrument};
 
 #[instrument(skip(discord))]
-pub async fn backup_user_votes(discord: &DiscordAPI, user_id: UserId) -> Option<Vec<VoteRecord>> {
-    let dm_channel = user_id.create_dm_channel(&discord.client.http).await;
+pub async fn backup_user_votes(
+    discord: &DiscordAPI<impl ChatBackend>,
+    user_id: UserId,
+) -> Option<Vec<VoteRecord>> {
+    let dm_channel = discord.backend.dm_channel(user_id).await;
 
     if let Err(err) = dm_channel {
         error!("Failed to create DM channel! Error: {}", err);
         return None;
     }
 
-    let messages = dm_channel
-        .unwrap()
-        .messages(&discord.client.http, GetMessages::new())
+    let messages = discord
+        .backend
+        .messages(dm_channel.unwrap(), None, DEFAULT_PAGE_SIZE)
         .await;
 
     if let Err(err) = messages {
@@ -25,7 +29,7 @@ pub async fn backup_user_votes(discord: &DiscordAPI, user_id: UserId) -> Option<
     let messages: Vec<VoteRecord> = messages
         .unwrap()
         .iter()
-        .filter_map(|message| extract_vote(discord, user_id, message))
+        .filter_map(|message| extract_vote(discord.own_user.id, user_id, message))
         .collect();
 
     info!("Found {} votes", messages.len());
@@ -33,8 +37,8 @@ pub async fn backup_user_votes(discord: &DiscordAPI, user_id: UserId) -> Option<
     Some(messages)
 }
 
-fn extract_vote(discord: &DiscordAPI, user_id: UserId, message: &Message) -> Option<VoteRecord> {
-    if message.author.id != discord.own_user.id
+fn extract_vote(own_user_id: UserId, user_id: UserId, message: &Message) -> Option<VoteRecord> {
+    if message.author.id != own_user_id
         || message.kind != MessageType::Regular
         || message.embeds.is_empty()
     {
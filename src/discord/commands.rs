@@ -0,0 +1,398 @@
+use crate::discord::embed_limits::fit_embed;
+use crate::posted_events::{EventFilter, PostedEvent, PostedEvents};
+use chrono::{Datelike, NaiveDate, Utc};
+use serenity::all::{
+    ButtonStyle, Command, CommandInteraction, CommandOptionType, ComponentInteraction, Context,
+    CreateActionRow, CreateButton, CreateCommand, CreateCommandOption, CreateEmbed,
+    CreateEmbedFooter, CreateInteractionResponse, CreateInteractionResponseMessage, EventHandler,
+    Interaction, Ready, ResolvedValue,
+};
+use serenity::async_trait;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use tracing::{info, instrument, warn};
+
+pub const AGENDA_COMMAND: &str = "agenda";
+/// Events per embed, each in its own field
+pub const PAGE_SIZE: usize = 10;
+/// Older queries are forgotten, so their buttons stop working
+const MAX_QUERIES: usize = 100;
+const DATE_FORMATS: [&str; 2] = ["%d/%m/%Y", "%Y-%m-%d"];
+
+/// Answers `/agenda` with the posted events that match its filters, a page at a time
+pub struct AgendaCommandHandler {
+    posted_events: Arc<PostedEvents>,
+    queries: Mutex<Queries>,
+}
+
+#[derive(Default)]
+struct Queries {
+    last_id: u64,
+    filters: BTreeMap<u64, EventFilter>,
+}
+
+impl AgendaCommandHandler {
+    pub fn new(posted_events: Arc<PostedEvents>) -> Self {
+        Self {
+            posted_events,
+            queries: Mutex::new(Queries::default()),
+        }
+    }
+
+    fn remember(&self, filter: EventFilter) -> u64 {
+        let mut queries = self.queries.lock().unwrap();
+
+        queries.last_id += 1;
+        let query_id = queries.last_id;
+        queries.filters.insert(query_id, filter);
+
+        if queries.filters.len() > MAX_QUERIES {
+            queries.filters.pop_first();
+        }
+        query_id
+    }
+
+    fn recall(&self, query_id: u64) -> Option<EventFilter> {
+        self.queries.lock().unwrap().filters.get(&query_id).cloned()
+    }
+
+    #[instrument(skip_all, fields(user = %command.user.name))]
+    async fn answer_command(&self, ctx: &Context, command: &CommandInteraction) {
+        let options: Vec<(&str, ResolvedValue)> = command
+            .data
+            .options()
+            .into_iter()
+            .map(|option| (option.name, option.value))
+            .collect();
+
+        let response = match parse_filter(&options, Utc::now().date_naive()) {
+            Ok(filter) => {
+                let events = self.posted_events.find(&filter);
+                let query_id = self.remember(filter);
+
+                info!("Found {} events for {:?}", events.len(), options);
+                page_message(&events, query_id, 0)
+            }
+            Err(reason) => CreateInteractionResponseMessage::new().content(reason),
+        };
+
+        if let Err(err) = command
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::Message(response.ephemeral(true)),
+            )
+            .await
+        {
+            warn!("Failed to answer /{}: {}", AGENDA_COMMAND, err);
+        }
+    }
+
+    async fn turn_page(&self, ctx: &Context, component: &ComponentInteraction) {
+        let Some((query_id, page)) = parse_page_id(&component.data.custom_id) else {
+            return;
+        };
+
+        let response = match self.recall(query_id) {
+            Some(filter) => page_message(&self.posted_events.find(&filter), query_id, page),
+            None => CreateInteractionResponseMessage::new()
+                .content(format!(
+                    "Esta pesquisa expirou, volta a usar /{}",
+                    AGENDA_COMMAND
+                ))
+                .embeds(Vec::new())
+                .components(Vec::new()),
+        };
+
+        if let Err(err) = component
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::UpdateMessage(response),
+            )
+            .await
+        {
+            warn!("Failed to turn /{} page: {}", AGENDA_COMMAND, err);
+        }
+    }
+}
+
+#[async_trait]
+impl EventHandler for AgendaCommandHandler {
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        match interaction {
+            Interaction::Command(command) if command.data.name == AGENDA_COMMAND => {
+                self.answer_command(&ctx, &command).await
+            }
+            Interaction::Component(component) => self.turn_page(&ctx, &component).await,
+            _ => {}
+        }
+    }
+
+    async fn ready(&self, ctx: Context, _ready: Ready) {
+        match Command::create_global_command(&ctx.http, agenda_command()).await {
+            Ok(_) => info!("Registered /{}", AGENDA_COMMAND),
+            Err(err) => warn!("Failed to register /{}: {}", AGENDA_COMMAND, err),
+        }
+    }
+}
+
+fn agenda_command() -> CreateCommand {
+    let option = |kind, name: &str, description: &str| {
+        CreateCommandOption::new(kind, name, description).required(false)
+    };
+
+    CreateCommand::new(AGENDA_COMMAND)
+        .description("Procura os eventos já publicados")
+        .add_option(option(
+            CommandOptionType::Boolean,
+            "semana",
+            "Só os desta semana",
+        ))
+        .add_option(option(
+            CommandOptionType::String,
+            "de",
+            "A partir do dia, DD/MM/AAAA",
+        ))
+        .add_option(option(
+            CommandOptionType::String,
+            "ate",
+            "Até ao dia, DD/MM/AAAA",
+        ))
+        .add_option(option(
+            CommandOptionType::String,
+            "sala",
+            "Parte do nome da sala",
+        ))
+        .add_option(option(CommandOptionType::String, "tag", "Uma das tags"))
+        .add_option(option(
+            CommandOptionType::Boolean,
+            "gratuitos",
+            "Só os gratuitos",
+        ))
+        .add_option(option(
+            CommandOptionType::Boolean,
+            "criancas",
+            "Só os para crianças",
+        ))
+}
+
+/// The filter of the command's options, from today on unless a range is given.
+/// Fails with the reason to show the user
+pub fn parse_filter(
+    options: &[(&str, ResolvedValue)],
+    today: NaiveDate,
+) -> Result<EventFilter, String> {
+    let mut filter = EventFilter::from(today);
+
+    for (name, value) in options {
+        match (*name, value) {
+            ("semana", ResolvedValue::Boolean(true)) => {
+                let days_until_sunday = 6 - today.weekday().num_days_from_monday();
+
+                filter.from = today;
+                filter.to = today.checked_add_days(chrono::Days::new(days_until_sunday.into()));
+            }
+            ("de", ResolvedValue::String(date)) => filter.from = parse_date(date)?,
+            ("ate", ResolvedValue::String(date)) => filter.to = Some(parse_date(date)?),
+            ("sala", ResolvedValue::String(venue)) => filter.venue = Some(venue.to_string()),
+            ("tag", ResolvedValue::String(tag)) => filter.tag = Some(tag.to_string()),
+            ("gratuitos", ResolvedValue::Boolean(free_only)) => filter.free_only = *free_only,
+            ("criancas", ResolvedValue::Boolean(for_children)) => {
+                filter.for_children = *for_children
+            }
+            _ => {}
+        }
+    }
+
+    if filter.to.is_some_and(|to| to < filter.from) {
+        return Err("A data final é anterior à inicial".to_string());
+    }
+
+    Ok(filter)
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    DATE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(date.trim(), format).ok())
+        .ok_or_else(|| format!("Data inválida '{}', usa DD/MM/AAAA", date))
+}
+
+fn page_count(events: &[PostedEvent]) -> usize {
+    events.len().div_ceil(PAGE_SIZE).max(1)
+}
+
+/// The page's events, each linking to its message in the threads
+pub fn build_page(events: &[PostedEvent], page: usize) -> CreateEmbed {
+    let page_count = page_count(events);
+    let page = page.min(page_count - 1);
+
+    if events.is_empty() {
+        return CreateEmbed::new()
+            .title("Agenda")
+            .description("Nenhum evento encontrado");
+    }
+
+    let fields = events
+        .iter()
+        .skip(page * PAGE_SIZE)
+        .take(PAGE_SIZE)
+        .map(|posted_event| {
+            let event = &posted_event.event;
+
+            (
+                event.title.clone(),
+                format!(
+                    "{} · {} · [Ver evento]({})",
+                    event.occurring_at.dates, event.venue.name, posted_event.message_link
+                ),
+                false,
+            )
+        });
+
+    fit_embed(
+        CreateEmbed::new()
+            .title(format!("Agenda · {} eventos", events.len()))
+            .fields(fields)
+            .footer(CreateEmbedFooter::new(format!(
+                "Página {} de {}",
+                page + 1,
+                page_count
+            ))),
+    )
+}
+
+fn page_message(
+    events: &[PostedEvent],
+    query_id: u64,
+    page: usize,
+) -> CreateInteractionResponseMessage {
+    let page_count = page_count(events);
+    let page = page.min(page_count - 1);
+    let message = CreateInteractionResponseMessage::new()
+        .content("")
+        .embed(build_page(events, page));
+
+    if page_count == 1 {
+        return message.components(Vec::new());
+    }
+
+    let button = |label: &str, target: usize, disabled: bool| {
+        CreateButton::new(page_id(query_id, target))
+            .label(label)
+            .style(ButtonStyle::Secondary)
+            .disabled(disabled)
+    };
+
+    message.components(vec![CreateActionRow::Buttons(vec![
+        button("◀", page.saturating_sub(1), page == 0),
+        button("▶", page + 1, page + 1 == page_count),
+    ])])
+}
+
+fn page_id(query_id: u64, page: usize) -> String {
+    format!("{}:{}:{}", AGENDA_COMMAND, query_id, page)
+}
+
+fn parse_page_id(custom_id: &str) -> Option<(u64, usize)> {
+    let (query_id, page) = custom_id
+        .strip_prefix(AGENDA_COMMAND)?
+        .strip_prefix(':')?
+        .split_once(':')?;
+
+    Some((query_id.parse().ok()?, page.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agenda_cultural::audience::Audience;
+    use crate::agenda_cultural::model::{Event, EventDetails, Price, Schedule, Venue};
+    use crate::discord::embed_limits::to_embed;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 10, day).unwrap()
+    }
+
+    fn build_posted_events(count: usize) -> Vec<PostedEvent> {
+        (0..count)
+            .map(|index| PostedEvent {
+                event: Event::new(
+                    format!("Evento {}", index),
+                    EventDetails::new(String::new(), String::new(), String::new()),
+                    format!("https://example.com/{}", index),
+                    Schedule::new(
+                        "10 outubro 2025".to_string(),
+                        String::new(),
+                        Vec::new(),
+                        Some(date(10)),
+                        Some(date(10)),
+                    ),
+                    Venue::new(None, "", "Culturgest"),
+                    Vec::new(),
+                    Price::Free,
+                    Audience::default(),
+                ),
+                message_link: format!("https://discord.com/channels/1/2/{}", index),
+            })
+            .collect()
+    }
+
+    #[test_log::test]
+    fn should_parse_filter_from_options() {
+        let options = [
+            ("de", ResolvedValue::String("01/10/2025")),
+            ("ate", ResolvedValue::String("2025-10-20")),
+            ("sala", ResolvedValue::String("Culturgest")),
+            ("gratuitos", ResolvedValue::Boolean(true)),
+        ];
+
+        let filter = parse_filter(&options, date(5)).unwrap();
+
+        assert_eq!(
+            filter,
+            EventFilter {
+                to: Some(date(20)),
+                venue: Some("Culturgest".to_string()),
+                free_only: true,
+                ..EventFilter::from(date(1))
+            }
+        );
+    }
+
+    #[test_log::test]
+    fn should_filter_this_week_until_sunday() {
+        // A Wednesday
+        let filter = parse_filter(&[("semana", ResolvedValue::Boolean(true))], date(15)).unwrap();
+
+        assert_eq!(filter.from, date(15));
+        assert_eq!(filter.to, Some(date(19)));
+    }
+
+    #[test_log::test]
+    fn should_reject_invalid_dates_and_ranges() {
+        assert!(parse_filter(&[("de", ResolvedValue::String("amanhã"))], date(1)).is_err());
+        assert!(parse_filter(&[("ate", ResolvedValue::String("01/09/2025"))], date(1)).is_err());
+    }
+
+    #[test_log::test]
+    fn should_build_pages_linking_to_messages() {
+        let events = build_posted_events(PAGE_SIZE + 2);
+
+        let first_page = to_embed(&build_page(&events, 0)).unwrap();
+        let last_page = to_embed(&build_page(&events, 1)).unwrap();
+
+        assert_eq!(first_page.fields.len(), PAGE_SIZE);
+        assert_eq!(last_page.fields.len(), 2);
+        assert_eq!(last_page.fields[1].name, "Evento 11");
+        assert!(last_page.fields[1]
+            .value
+            .ends_with("[Ver evento](https://discord.com/channels/1/2/11)"));
+        assert_eq!(last_page.footer.unwrap().text, "Página 2 de 2");
+    }
+
+    #[test_log::test]
+    fn should_parse_page_ids() {
+        assert_eq!(parse_page_id(&page_id(3, 2)), Some((3, 2)));
+        assert_eq!(parse_page_id("other:3:2"), None);
+    }
+}
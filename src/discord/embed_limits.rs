@@ -0,0 +1,384 @@
+use serenity::all::{CreateEmbed, Embed};
+use std::fmt::{Display, Formatter};
+use tracing::warn;
+
+/// Discord's embed limits, in characters
+pub const TITLE_LIMIT: usize = 256;
+pub const DESCRIPTION_LIMIT: usize = 4096;
+pub const FIELD_COUNT_LIMIT: usize = 25;
+pub const FIELD_NAME_LIMIT: usize = 256;
+pub const FIELD_VALUE_LIMIT: usize = 1024;
+pub const FOOTER_LIMIT: usize = 2048;
+pub const AUTHOR_NAME_LIMIT: usize = 256;
+/// Of the title, description, field names and values, footer and author name together
+pub const TOTAL_LIMIT: usize = 6000;
+
+const ELLIPSIS: char = '…';
+/// How much of the text can be given up to cut it at a word instead of mid-word
+const MAX_WORD_BOUNDARY_LOSS: usize = 20;
+/// Longest first, so bold isn't taken for two italics
+const EMPHASIS_MARKERS: [&str; 4] = ["**", "__", "~~", "*"];
+
+/// A part of an embed that was shortened to fit
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trim {
+    /// e.g. "title", "description", "field 'Comentários'"
+    pub part: String,
+    pub original_length: usize,
+    pub length: usize,
+}
+
+impl Display for Trim {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.length {
+            0 => write!(f, "{} removed", self.part),
+            length => write!(
+                f,
+                "{} trimmed from {} to {} characters",
+                self.part, self.original_length, length
+            ),
+        }
+    }
+}
+
+/// Reads the embed a builder would send
+pub fn to_embed(embed: &CreateEmbed) -> Option<Embed> {
+    serde_json::to_value(embed)
+        .and_then(serde_json::from_value)
+        .ok()
+}
+
+/// Fits an embed in Discord's limits, logging what had to be trimmed
+pub fn fit_embed(embed: CreateEmbed) -> CreateEmbed {
+    let Some(mut fitted) = to_embed(&embed) else {
+        warn!("Unable to check the embed limits");
+        return embed;
+    };
+
+    let trims = fit(&mut fitted);
+
+    if !trims.is_empty() {
+        warn!(
+            "Embed '{}' exceeded Discord's limits: {}",
+            fitted.title.as_deref().unwrap_or_default(),
+            trims
+                .iter()
+                .map(Trim::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
+    CreateEmbed::from(fitted)
+}
+
+/// Shortens every part over its limit, and then the description and the last fields
+/// until the embed is within the total limit
+pub fn fit(embed: &mut Embed) -> Vec<Trim> {
+    let mut trims = Vec::new();
+
+    if let Some(title) = &mut embed.title {
+        trims.extend(truncate_part("title", title, TITLE_LIMIT));
+    }
+
+    if let Some(description) = &mut embed.description {
+        trims.extend(truncate_part("description", description, DESCRIPTION_LIMIT));
+    }
+
+    if let Some(author) = &mut embed.author {
+        trims.extend(truncate_part("author", &mut author.name, AUTHOR_NAME_LIMIT));
+    }
+
+    if let Some(footer) = &mut embed.footer {
+        trims.extend(truncate_part("footer", &mut footer.text, FOOTER_LIMIT));
+    }
+
+    for field in embed.fields.iter_mut().skip(FIELD_COUNT_LIMIT) {
+        trims.push(Trim {
+            part: format!("field '{}'", field.name),
+            original_length: field.value.chars().count(),
+            length: 0,
+        });
+    }
+    embed.fields.truncate(FIELD_COUNT_LIMIT);
+
+    for field in embed.fields.iter_mut() {
+        let part = format!("field '{}'", field.name);
+
+        trims.extend(truncate_part(&part, &mut field.value, FIELD_VALUE_LIMIT));
+        trims.extend(truncate_part(
+            &format!("name of {}", part),
+            &mut field.name,
+            FIELD_NAME_LIMIT,
+        ));
+    }
+
+    let excess = total_length(embed).saturating_sub(TOTAL_LIMIT);
+
+    if let (true, Some(description)) = (excess > 0, &mut embed.description) {
+        let limit = description.chars().count().saturating_sub(excess);
+
+        trims.extend(truncate_part("description", description, limit));
+    }
+
+    for index in (0..embed.fields.len()).rev() {
+        let excess = total_length(embed).saturating_sub(TOTAL_LIMIT);
+
+        if excess == 0 {
+            break;
+        }
+
+        let field = &mut embed.fields[index];
+        let limit = field.value.chars().count().saturating_sub(excess);
+
+        trims.extend(truncate_part(
+            &format!("field '{}'", field.name),
+            &mut field.value,
+            limit,
+        ));
+    }
+
+    trims
+}
+
+fn total_length(embed: &Embed) -> usize {
+    let length = |text: &Option<String>| text.as_deref().map_or(0, |text| text.chars().count());
+
+    length(&embed.title)
+        + length(&embed.description)
+        + embed
+            .author
+            .as_ref()
+            .map_or(0, |author| author.name.chars().count())
+        + embed
+            .footer
+            .as_ref()
+            .map_or(0, |footer| footer.text.chars().count())
+        + embed
+            .fields
+            .iter()
+            .map(|field| field.name.chars().count() + field.value.chars().count())
+            .sum::<usize>()
+}
+
+fn truncate_part(part: &str, text: &mut String, limit: usize) -> Option<Trim> {
+    let original_length = text.chars().count();
+
+    if original_length <= limit {
+        return None;
+    }
+
+    *text = truncate(text, limit);
+
+    Some(Trim {
+        part: part.to_string(),
+        original_length,
+        length: text.chars().count(),
+    })
+}
+
+/// Cuts the text to at most `limit` characters, ending in "…".
+/// The cut is made at a word boundary when that doesn't lose much of the text,
+/// and before any emphasis, link or escape it would leave open
+pub fn truncate(text: &str, limit: usize) -> String {
+    if text.chars().count() <= limit {
+        return text.to_string();
+    }
+
+    if limit == 0 {
+        return String::new();
+    }
+
+    let kept: String = text.chars().take(limit - 1).collect();
+    let word_boundary = kept
+        .char_indices()
+        .rev()
+        .take(MAX_WORD_BOUNDARY_LOSS)
+        .find(|(_, character)| character.is_whitespace())
+        .map(|(index, _)| index);
+
+    let mut kept = match word_boundary {
+        Some(index) => kept[..index].trim_end(),
+        None => kept.as_str(),
+    };
+
+    // Backing off can leave open what was closed past the new end, e.g. bold around a link
+    while let Some(index) = unclosed_markdown_start(kept) {
+        kept = kept[..index].trim_end();
+    }
+
+    format!("{}{}", kept, ELLIPSIS)
+}
+
+/// Where the earliest emphasis, link or escape that the text leaves open starts
+fn unclosed_markdown_start(text: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut emphasis_starts = [None; EMPHASIS_MARKERS.len()];
+    let mut link_start = None;
+    let mut in_link_url = false;
+    let mut escape_start = None;
+    let mut index = 0;
+
+    // Markdown is all ASCII, which never appears inside a multi-byte character
+    while index < bytes.len() {
+        if in_link_url {
+            if bytes[index] == b')' {
+                link_start = None;
+                in_link_url = false;
+            }
+
+            index += 1;
+            continue;
+        }
+
+        if bytes[index] == b'\\' {
+            if index + 1 == bytes.len() {
+                escape_start = Some(index);
+            }
+
+            index += 2;
+            continue;
+        }
+
+        if let Some(marker) = EMPHASIS_MARKERS
+            .iter()
+            .position(|marker| bytes[index..].starts_with(marker.as_bytes()))
+        {
+            emphasis_starts[marker] = match emphasis_starts[marker] {
+                Some(_) => None,
+                None => Some(index),
+            };
+            index += EMPHASIS_MARKERS[marker].len();
+            continue;
+        }
+
+        match bytes[index] {
+            b'[' if link_start.is_none() => link_start = Some(index),
+            b']' if link_start.is_some() => match bytes.get(index + 1) {
+                Some(b'(') => {
+                    in_link_url = true;
+                    index += 1;
+                }
+                // Only square brackets, not a link
+                Some(_) => link_start = None,
+                None => {}
+            },
+            _ => {}
+        }
+
+        index += 1;
+    }
+
+    emphasis_starts
+        .into_iter()
+        .chain([link_start, escape_start])
+        .flatten()
+        .min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serenity::all::CreateEmbedAuthor;
+
+    #[test_log::test]
+    fn should_truncate_at_word_boundary() {
+        assert_eq!(truncate("Rute e Lô são os protagonistas", 12), "Rute e Lô…");
+        assert_eq!(truncate("Rute e Lô", 9), "Rute e Lô");
+    }
+
+    #[test_log::test]
+    fn when_word_is_too_long_should_truncate_at_char_boundary() {
+        let text = "ã".repeat(50);
+
+        assert_eq!(truncate(&text, 10), format!("{}…", "ã".repeat(9)));
+    }
+
+    #[test_log::test]
+    fn should_not_leave_markdown_open() {
+        assert_eq!(
+            truncate(
+                "**Rute e Lô** em [Ticketline](https://ticketline.sapo.pt/evento/rute-e-lo)",
+                45
+            ),
+            "**Rute e Lô** em…"
+        );
+        assert_eq!(
+            truncate("Com **Rute e Lô são os protagonistas** desta história", 30),
+            "Com…"
+        );
+        assert_eq!(truncate("Preço\\*s", 7), "Preço…");
+    }
+
+    #[test_log::test]
+    fn when_markdown_is_closed_should_keep_it() {
+        assert_eq!(
+            truncate(
+                "Com **Rute e Lô** e [bilhetes](https://example.com) à venda já amanhã",
+                62
+            ),
+            "Com **Rute e Lô** e [bilhetes](https://example.com) à venda…"
+        );
+    }
+
+    #[test_log::test]
+    fn should_fit_parts_over_their_limits() {
+        let mut embed = to_embed(
+            &CreateEmbed::new()
+                .title("á".repeat(300))
+                .author(CreateEmbedAuthor::new("Teatro do Bairro"))
+                .field("Comentários", "é".repeat(2000), true),
+        )
+        .unwrap();
+
+        let trims = fit(&mut embed);
+
+        assert_eq!(embed.title.unwrap().chars().count(), TITLE_LIMIT);
+        assert_eq!(embed.fields[0].value.chars().count(), FIELD_VALUE_LIMIT);
+        assert_eq!(
+            trims,
+            [
+                Trim {
+                    part: "title".to_string(),
+                    original_length: 300,
+                    length: TITLE_LIMIT
+                },
+                Trim {
+                    part: "field 'Comentários'".to_string(),
+                    original_length: 2000,
+                    length: FIELD_VALUE_LIMIT
+                }
+            ]
+        );
+    }
+
+    #[test_log::test]
+    fn should_fit_total_length_by_shortening_description_first() {
+        let mut embed = to_embed(
+            &CreateEmbed::new()
+                .title("Galafoice")
+                .description("a ".repeat(2000))
+                .field("Datas", "b".repeat(1000), true)
+                .field("Horários", "c".repeat(1000), true)
+                .field("Comentários", "d".repeat(1000), true)
+                .field("Voto", "e".repeat(1000), true),
+        )
+        .unwrap();
+
+        let trims = fit(&mut embed);
+
+        assert!(total_length(&embed) <= TOTAL_LIMIT);
+        assert_eq!(trims.len(), 1);
+        assert_eq!(trims[0].part, "description");
+        assert_eq!(embed.fields[3].value.chars().count(), 1000);
+    }
+
+    #[test_log::test]
+    fn when_embed_fits_should_be_left_as_is() {
+        let mut embed =
+            to_embed(&CreateEmbed::new().title("Galafoice").description("Mães")).unwrap();
+
+        assert!(fit(&mut embed).is_empty());
+        assert_eq!(embed.description.as_deref(), Some("Mães"));
+    }
+}
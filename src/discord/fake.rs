@@ -0,0 +1,567 @@
+use crate::discord::backend::{ChatBackend, ChatResult};
+use serde_json::{json, Value};
+use serenity::all::{
+    ChannelId, ChannelType, CreateMessage, CurrentUser, EditMessage, Embed, GuildChannel, GuildId,
+    Message, MessageId, MessageReaction, MessageType, ReactionType, ThreadMetadata, Timestamp,
+    User, UserId,
+};
+use serenity::http::{ErrorResponse, HttpError, LightMethod};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// An in-memory chat that records the messages, threads and reactions the pipeline leaves,
+/// so it can be tested without Discord
+pub struct FakeChat {
+    state: Mutex<FakeChatState>,
+}
+
+struct FakeChatState {
+    last_id: u64,
+    bot: User,
+    guild_id: GuildId,
+    channel_names: HashMap<ChannelId, String>,
+    threads: Vec<GuildChannel>,
+    /// Oldest first
+    messages: HashMap<ChannelId, Vec<Message>>,
+    /// Users behind each reaction of the messages, in the order they reacted
+    reactions: HashMap<MessageId, Vec<(ReactionType, Vec<User>)>>,
+    dm_channels: HashMap<UserId, ChannelId>,
+}
+
+impl Default for FakeChat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FakeChat {
+    pub fn new() -> Self {
+        let mut bot = User::default();
+        bot.id = UserId::new(1);
+        bot.name = "alertaemcena".to_string();
+        bot.bot = true;
+
+        Self {
+            state: Mutex::new(FakeChatState {
+                last_id: 1,
+                bot,
+                guild_id: GuildId::new(2),
+                channel_names: HashMap::new(),
+                threads: Vec::new(),
+                messages: HashMap::new(),
+                reactions: HashMap::new(),
+                dm_channels: HashMap::new(),
+            }),
+        }
+    }
+
+    /// A text channel of the guild
+    pub fn add_channel(&self, name: &str) -> ChannelId {
+        let mut state = self.state.lock().unwrap();
+        let channel_id = ChannelId::new(state.next_id());
+
+        state.channel_names.insert(channel_id, name.to_string());
+        channel_id
+    }
+
+    pub fn add_user(&self, name: &str) -> User {
+        let mut state = self.state.lock().unwrap();
+        let mut user = User::default();
+
+        user.id = UserId::new(state.next_id());
+        user.name = name.to_string();
+        user
+    }
+
+    /// A message of someone else, replying to another one when set
+    pub fn send_as(
+        &self,
+        user: &User,
+        channel_id: ChannelId,
+        content: &str,
+        reply_to: Option<MessageId>,
+    ) -> Message {
+        let mut state = self.state.lock().unwrap();
+        let referenced_message = reply_to
+            .and_then(|message_id| state.find_message(channel_id, message_id))
+            .map(Box::new);
+        let mut message = state.new_message(channel_id, user.clone());
+
+        message.content = content.to_string();
+        message.referenced_message = referenced_message;
+        state.push_message(message)
+    }
+
+    pub fn react_as(
+        &self,
+        user: &User,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        reaction_type: ReactionType,
+    ) {
+        let mut state = self.state.lock().unwrap();
+
+        state.add_reaction(channel_id, message_id, user.clone(), reaction_type);
+    }
+
+    /// Messages of the channel, oldest first
+    pub fn messages_of(&self, channel_id: ChannelId) -> Vec<Message> {
+        let state = self.state.lock().unwrap();
+
+        state
+            .messages
+            .get(&channel_id)
+            .into_iter()
+            .flatten()
+            .map(|message| state.with_reactions(message.clone()))
+            .collect()
+    }
+
+    /// Threads of the channel, archived or not
+    pub fn threads_of(&self, channel_id: ChannelId) -> Vec<GuildChannel> {
+        self.state
+            .lock()
+            .unwrap()
+            .threads
+            .iter()
+            .filter(|thread| thread.parent_id == Some(channel_id))
+            .cloned()
+            .collect()
+    }
+}
+
+impl FakeChatState {
+    fn next_id(&mut self) -> u64 {
+        self.last_id += 1;
+        self.last_id
+    }
+
+    fn new_message(&mut self, channel_id: ChannelId, author: User) -> Message {
+        let mut message = Message::default();
+
+        message.id = MessageId::new(self.next_id());
+        message.channel_id = channel_id;
+        message.author = author;
+        message.timestamp = Timestamp::now();
+        message
+    }
+
+    fn push_message(&mut self, message: Message) -> Message {
+        self.messages
+            .entry(message.channel_id)
+            .or_default()
+            .push(message.clone());
+        message
+    }
+
+    fn find_message(&self, channel_id: ChannelId, message_id: MessageId) -> Option<Message> {
+        self.messages
+            .get(&channel_id)?
+            .iter()
+            .find(|message| message.id == message_id)
+            .map(|message| self.with_reactions(message.clone()))
+    }
+
+    fn message_mut(
+        &mut self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+    ) -> Option<&mut Message> {
+        self.messages
+            .get_mut(&channel_id)?
+            .iter_mut()
+            .find(|message| message.id == message_id)
+    }
+
+    fn add_reaction(
+        &mut self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        user: User,
+        reaction_type: ReactionType,
+    ) -> bool {
+        if self.find_message(channel_id, message_id).is_none() {
+            return false;
+        }
+
+        let reactions = self.reactions.entry(message_id).or_default();
+        let index = match reactions
+            .iter()
+            .position(|(existing, _)| *existing == reaction_type)
+        {
+            Some(index) => index,
+            None => {
+                reactions.push((reaction_type, Vec::new()));
+                reactions.len() - 1
+            }
+        };
+        let users = &mut reactions[index].1;
+
+        if !users.iter().any(|existing| existing.id == user.id) {
+            users.push(user);
+        }
+
+        true
+    }
+
+    /// Messages read from Discord come with their reaction counts
+    fn with_reactions(&self, mut message: Message) -> Message {
+        message.reactions = self
+            .reactions
+            .get(&message.id)
+            .into_iter()
+            .flatten()
+            .map(|(reaction_type, users)| {
+                serde_json::from_value::<MessageReaction>(json!({
+                    "count": users.len(),
+                    "count_details": { "burst": 0, "normal": users.len() },
+                    "me": users.iter().any(|user| user.id == self.bot.id),
+                    "me_burst": false,
+                    "emoji": reaction_type,
+                    "burst_colors": [],
+                }))
+                .expect("Invalid fake reaction")
+            })
+            .collect();
+        message
+    }
+
+    /// Newest first, as Discord lists them
+    fn newest_first(&self, channel_id: ChannelId) -> Vec<Message> {
+        self.messages
+            .get(&channel_id)
+            .into_iter()
+            .flatten()
+            .rev()
+            .map(|message| self.with_reactions(message.clone()))
+            .collect()
+    }
+
+    fn is_known_channel(&self, channel_id: ChannelId) -> bool {
+        self.channel_names.contains_key(&channel_id)
+            || self.threads.iter().any(|thread| thread.id == channel_id)
+    }
+}
+
+/// The content and embeds set by a message builder, if any
+fn builder_content(builder: &impl serde::Serialize) -> (Option<String>, Option<Vec<Embed>>) {
+    let value = serde_json::to_value(builder).expect("Invalid message builder");
+    let content = value
+        .get("content")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    let embeds = value
+        .get("embeds")
+        .cloned()
+        .map(|embeds| serde_json::from_value(embeds).expect("Invalid embeds"));
+
+    (content, embeds)
+}
+
+/// What Discord answers for a deleted message, which the pipeline tells apart from other errors
+async fn unknown_message_error() -> serenity::Error {
+    let response = serenity_http::Response::builder()
+        .status(404)
+        .body(r#"{"code": 10008, "message": "Unknown Message"}"#)
+        .expect("Failed to build unknown message response");
+    let error_response =
+        ErrorResponse::from_response(response.into(), LightMethod::Get.reqwest_method()).await;
+
+    serenity::Error::Http(HttpError::UnsuccessfulRequest(error_response))
+}
+
+impl ChatBackend for FakeChat {
+    async fn current_user(&self) -> ChatResult<CurrentUser> {
+        let bot = self.state.lock().unwrap().bot.clone();
+
+        Ok(serde_json::from_value(json!(bot)).expect("Invalid fake user"))
+    }
+
+    async fn guild_id(&self, channel_id: ChannelId) -> ChatResult<GuildId> {
+        let state = self.state.lock().unwrap();
+
+        match state.is_known_channel(channel_id) {
+            true => Ok(state.guild_id),
+            false => Err(serenity::Error::Other("Unknown channel")),
+        }
+    }
+
+    async fn channel_name(&self, channel_id: ChannelId) -> ChatResult<String> {
+        let state = self.state.lock().unwrap();
+
+        state
+            .channel_names
+            .get(&channel_id)
+            .cloned()
+            .or_else(|| {
+                state
+                    .threads
+                    .iter()
+                    .find(|thread| thread.id == channel_id)
+                    .map(|thread| thread.name.clone())
+            })
+            .ok_or(serenity::Error::Other("Unknown channel"))
+    }
+
+    async fn active_threads(&self, guild_id: GuildId) -> ChatResult<Vec<GuildChannel>> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .threads
+            .iter()
+            .filter(|thread| thread.guild_id == guild_id)
+            .filter(|thread| {
+                !thread
+                    .thread_metadata
+                    .is_some_and(|metadata| metadata.archived)
+            })
+            .cloned()
+            .collect())
+    }
+
+    async fn archived_public_threads(
+        &self,
+        channel_id: ChannelId,
+    ) -> ChatResult<Vec<GuildChannel>> {
+        Ok(self
+            .threads_of(channel_id)
+            .into_iter()
+            .filter(|thread| {
+                thread
+                    .thread_metadata
+                    .is_some_and(|metadata| metadata.archived)
+            })
+            .collect())
+    }
+
+    async fn unarchive_thread(&self, thread_id: ChannelId) -> ChatResult<()> {
+        let mut state = self.state.lock().unwrap();
+        let metadata = state
+            .threads
+            .iter_mut()
+            .find(|thread| thread.id == thread_id)
+            .and_then(|thread| thread.thread_metadata.as_mut())
+            .ok_or(serenity::Error::Other("Unknown thread"))?;
+
+        metadata.archived = false;
+        Ok(())
+    }
+
+    async fn create_thread(&self, channel_id: ChannelId, name: &str) -> ChatResult<GuildChannel> {
+        let mut state = self.state.lock().unwrap();
+
+        if !state.channel_names.contains_key(&channel_id) {
+            return Err(serenity::Error::Other("Unknown channel"));
+        }
+
+        let mut thread = GuildChannel::default();
+
+        thread.id = ChannelId::new(state.next_id());
+        thread.guild_id = state.guild_id;
+        thread.parent_id = Some(channel_id);
+        thread.name = name.to_string();
+        thread.kind = ChannelType::PublicThread;
+        thread.thread_metadata = Some(
+            serde_json::from_value::<ThreadMetadata>(json!({
+                "archived": false,
+                "auto_archive_duration": 10080,
+                "locked": false,
+            }))
+            .expect("Invalid fake thread metadata"),
+        );
+
+        state.threads.push(thread.clone());
+        Ok(thread)
+    }
+
+    async fn delete_channel(&self, channel_id: ChannelId) -> ChatResult<()> {
+        let mut state = self.state.lock().unwrap();
+
+        state.threads.retain(|thread| thread.id != channel_id);
+        state.channel_names.remove(&channel_id);
+        state.messages.remove(&channel_id);
+        Ok(())
+    }
+
+    async fn all_messages(&self, channel_id: ChannelId) -> ChatResult<Vec<Message>> {
+        Ok(self.state.lock().unwrap().newest_first(channel_id))
+    }
+
+    async fn messages(
+        &self,
+        channel_id: ChannelId,
+        before: Option<MessageId>,
+        limit: u8,
+    ) -> ChatResult<Vec<Message>> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .newest_first(channel_id)
+            .into_iter()
+            .filter(|message| before.is_none_or(|before| message.id < before))
+            .take(limit as usize)
+            .collect())
+    }
+
+    async fn message(&self, channel_id: ChannelId, message_id: MessageId) -> ChatResult<Message> {
+        let message = self
+            .state
+            .lock()
+            .unwrap()
+            .find_message(channel_id, message_id);
+
+        match message {
+            Some(message) => Ok(message),
+            None => Err(unknown_message_error().await),
+        }
+    }
+
+    async fn send_message(
+        &self,
+        channel_id: ChannelId,
+        builder: CreateMessage,
+    ) -> ChatResult<Message> {
+        let (content, embeds) = builder_content(&builder);
+        let mut state = self.state.lock().unwrap();
+        let is_dm = state.dm_channels.values().any(|dm| *dm == channel_id);
+
+        if !is_dm && !state.is_known_channel(channel_id) {
+            return Err(serenity::Error::Other("Unknown channel"));
+        }
+
+        let bot = state.bot.clone();
+        let mut message = state.new_message(channel_id, bot);
+
+        message.content = content.unwrap_or_default();
+        message.embeds = embeds.unwrap_or_default();
+        Ok(state.push_message(message))
+    }
+
+    async fn edit_message(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        builder: EditMessage,
+    ) -> ChatResult<Message> {
+        let (content, embeds) = builder_content(&builder);
+        let mut state = self.state.lock().unwrap();
+        let message = state
+            .message_mut(channel_id, message_id)
+            .ok_or(serenity::Error::Other("Unknown message"))?;
+
+        if let Some(content) = content {
+            message.content = content;
+        }
+
+        if let Some(embeds) = embeds {
+            message.embeds = embeds;
+        }
+
+        let message = message.clone();
+
+        Ok(state.with_reactions(message))
+    }
+
+    async fn delete_message(&self, channel_id: ChannelId, message_id: MessageId) -> ChatResult<()> {
+        let mut state = self.state.lock().unwrap();
+
+        state
+            .message_mut(channel_id, message_id)
+            .ok_or(serenity::Error::Other("Unknown message"))?;
+        state
+            .messages
+            .entry(channel_id)
+            .or_default()
+            .retain(|message| message.id != message_id);
+        state.reactions.remove(&message_id);
+        Ok(())
+    }
+
+    async fn delete_messages(
+        &self,
+        channel_id: ChannelId,
+        message_ids: &[MessageId],
+    ) -> ChatResult<()> {
+        for message_id in message_ids {
+            self.delete_message(channel_id, *message_id).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Like Discord, announces the pin with a system message
+    async fn pin(&self, channel_id: ChannelId, message_id: MessageId) -> ChatResult<()> {
+        let mut state = self.state.lock().unwrap();
+
+        state
+            .message_mut(channel_id, message_id)
+            .ok_or(serenity::Error::Other("Unknown message"))?
+            .pinned = true;
+
+        let bot = state.bot.clone();
+        let mut notification = state.new_message(channel_id, bot);
+
+        notification.kind = MessageType::PinsAdd;
+        state.push_message(notification);
+        Ok(())
+    }
+
+    async fn unpin(&self, channel_id: ChannelId, message_id: MessageId) -> ChatResult<()> {
+        self.state
+            .lock()
+            .unwrap()
+            .message_mut(channel_id, message_id)
+            .ok_or(serenity::Error::Other("Unknown message"))?
+            .pinned = false;
+        Ok(())
+    }
+
+    async fn react(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        reaction_type: ReactionType,
+    ) -> ChatResult<()> {
+        let mut state = self.state.lock().unwrap();
+        let bot = state.bot.clone();
+
+        match state.add_reaction(channel_id, message_id, bot, reaction_type) {
+            true => Ok(()),
+            false => Err(serenity::Error::Other("Unknown message")),
+        }
+    }
+
+    async fn reaction_users(
+        &self,
+        _channel_id: ChannelId,
+        message_id: MessageId,
+        reaction_type: ReactionType,
+    ) -> ChatResult<Vec<User>> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .reactions
+            .get(&message_id)
+            .into_iter()
+            .flatten()
+            .find(|(existing, _)| *existing == reaction_type)
+            .map(|(_, users)| users.clone())
+            .unwrap_or_default())
+    }
+
+    async fn dm_channel(&self, user_id: UserId) -> ChatResult<ChannelId> {
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(dm) = state.dm_channels.get(&user_id) {
+            return Ok(*dm);
+        }
+
+        let dm = ChannelId::new(state.next_id());
+
+        state.dm_channels.insert(user_id, dm);
+        Ok(dm)
+    }
+}
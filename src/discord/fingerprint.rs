@@ -0,0 +1,174 @@
+use crate::discord::embed_limits::to_embed;
+use crate::hashing::fnv1a;
+use serenity::all::{CreateEmbed, Embed};
+
+/// The parts of a posted event embed that come from the event, to tell whether it changed
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbedContent {
+    pub title: String,
+    pub url: String,
+    pub description: String,
+    pub author: String,
+    pub author_url: String,
+    pub image_url: String,
+    pub colour: u32,
+    /// Name and value of each field (e.g. "Datas", "Horários")
+    pub fields: Vec<(String, String)>,
+}
+
+impl EmbedContent {
+    /// Reads the content an embed would have once posted, as Discord stores it
+    pub fn from_create_embed(embed: &CreateEmbed) -> Option<Self> {
+        to_embed(embed).map(|embed| Self::from(&embed))
+    }
+
+    pub fn fingerprint(&self) -> u64 {
+        let mut parts = vec![
+            self.title.as_str(),
+            self.url.as_str(),
+            self.description.as_str(),
+            self.author.as_str(),
+            self.author_url.as_str(),
+            self.image_url.as_str(),
+        ];
+        let colour = self.colour.to_string();
+        parts.push(&colour);
+        parts.extend(
+            self.fields
+                .iter()
+                .flat_map(|(name, value)| [name.as_str(), value.as_str()]),
+        );
+
+        fnv1a(&parts.join("\u{1f}"))
+    }
+
+    /// Names of what differs from `other`, for logging
+    pub fn changed_fields(&self, other: &EmbedContent) -> Vec<String> {
+        let mut changed = Vec::new();
+        let mut compare = |name: &str, changed_field: bool| {
+            if changed_field {
+                changed.push(name.to_string());
+            }
+        };
+
+        compare("title", self.title != other.title);
+        compare("url", self.url != other.url);
+        compare("description", self.description != other.description);
+        compare(
+            "venue",
+            self.author != other.author || self.author_url != other.author_url,
+        );
+        compare("image", self.image_url != other.image_url);
+        compare("colour", self.colour != other.colour);
+
+        let field_names = self
+            .fields
+            .iter()
+            .chain(other.fields.iter())
+            .map(|(name, _)| name);
+
+        for name in field_names {
+            let value = |fields: &[(String, String)]| {
+                fields
+                    .iter()
+                    .find(|(field_name, _)| field_name == name)
+                    .map(|(_, value)| value.clone())
+            };
+
+            if value(&self.fields) != value(&other.fields) && !changed.contains(name) {
+                changed.push(name.to_string());
+            }
+        }
+
+        changed
+    }
+}
+
+impl From<&Embed> for EmbedContent {
+    fn from(embed: &Embed) -> Self {
+        let text = |text: &Option<String>| text.as_deref().unwrap_or_default().trim().to_string();
+
+        Self {
+            title: text(&embed.title),
+            url: text(&embed.url),
+            description: text(&embed.description),
+            author: embed
+                .author
+                .as_ref()
+                .map(|author| author.name.trim().to_string())
+                .unwrap_or_default(),
+            author_url: embed
+                .author
+                .as_ref()
+                .and_then(|author| author.url.clone())
+                .unwrap_or_default(),
+            image_url: embed
+                .image
+                .as_ref()
+                .map(|image| image.url.clone())
+                .unwrap_or_default(),
+            colour: embed.colour.map(|colour| colour.0).unwrap_or_default(),
+            fields: embed
+                .fields
+                .iter()
+                .map(|field| {
+                    (
+                        field.name.trim().to_string(),
+                        field.value.trim().to_string(),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serenity::all::{Colour, CreateEmbedAuthor};
+
+    fn build_embed(dates: &str, image_url: &str) -> CreateEmbed {
+        CreateEmbed::new()
+            .title("Galafoice")
+            .url("https://www.agendalx.pt/events/event/galafoice/")
+            .description("Espetáculo inaugural de uma trilogia autobiográfica")
+            .author(CreateEmbedAuthor::new("Teatro do Bairro"))
+            .color(Colour::new(0x005eeb))
+            .field("Datas", dates, true)
+            .image(image_url)
+    }
+
+    #[test_log::test]
+    fn when_embeds_match_should_have_same_fingerprint() {
+        let posted = EmbedContent::from_create_embed(&build_embed(
+            "22 fevereiro a 1 março",
+            "https://www.agendalx.pt/galafoice.jpg",
+        ))
+        .unwrap();
+        let fetched = EmbedContent::from_create_embed(&build_embed(
+            "22 fevereiro a 1 março ",
+            "https://www.agendalx.pt/galafoice.jpg",
+        ))
+        .unwrap();
+
+        assert_eq!(posted.fingerprint(), fetched.fingerprint());
+        assert!(posted.changed_fields(&fetched).is_empty());
+    }
+
+    #[test_log::test]
+    fn when_embeds_differ_should_list_changed_fields() {
+        let posted = EmbedContent::from_create_embed(&build_embed(
+            "22 fevereiro a 1 março",
+            "https://www.agendalx.pt/galafoice.jpg",
+        ))
+        .unwrap();
+        let fetched = EmbedContent::from_create_embed(
+            &build_embed("22 fevereiro a 8 março", "https://www.agendalx.pt/new.jpg")
+                .field("Preço", "9 €", true),
+        )
+        .unwrap();
+
+        assert_ne!(posted.fingerprint(), fetched.fingerprint());
+        assert_eq!(posted.changed_fields(&fetched), ["image", "Datas", "Preço"]);
+    }
+}
@@ -0,0 +1,140 @@
+use crate::config::model::EmojiConfig;
+use crate::discord::api::{is_event_message, DiscordAPI};
+use crate::discord::backend::{ChatBackend, SerenityChat};
+use serenity::all::{
+    ChannelId, Context, EventHandler, MessageId, Reaction, ReactionType, Ready, UserId,
+};
+use serenity::async_trait;
+use std::sync::{Arc, OnceLock};
+use tracing::{debug, info, instrument, trace, warn};
+
+/// Handles votes and saves for later as soon as users react, through the gateway.
+/// The polling pass still catches up on the reactions made while disconnected
+pub struct ReactionHandler<B = SerenityChat> {
+    discord: OnceLock<Arc<DiscordAPI<B>>>,
+    voting_emojis: [EmojiConfig; 5],
+    save_for_later_emoji: char,
+}
+
+impl<B: ChatBackend> ReactionHandler<B> {
+    pub fn new(voting_emojis: [EmojiConfig; 5], save_for_later_emoji: char) -> Self {
+        Self {
+            discord: OnceLock::new(),
+            voting_emojis,
+            save_for_later_emoji,
+        }
+    }
+
+    /// Reactions are ignored until it's set, since the client must be built before the API
+    pub fn set_discord(&self, discord: Arc<DiscordAPI<B>>) {
+        if self.discord.set(discord).is_err() {
+            warn!("Reaction handler already had a Discord API set");
+        }
+    }
+
+    /// Called for both added and removed reactions, since removing a save for later may unpin
+    #[instrument(skip(self, reaction_type))]
+    pub async fn handle_reaction(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        user_id: Option<UserId>,
+        message_author_id: Option<UserId>,
+        reaction_type: &ReactionType,
+        is_removed: bool,
+    ) {
+        let Some(discord) = self.discord.get() else {
+            debug!("Ignoring reaction received before the Discord API was set");
+            return;
+        };
+        let own_user_id = discord.own_user.id;
+
+        if user_id == Some(own_user_id) {
+            trace!("Ignoring own reaction");
+            return;
+        }
+
+        if message_author_id.is_some_and(|author_id| author_id != own_user_id) {
+            trace!("Ignoring reaction to another user's message");
+            return;
+        }
+
+        let is_save_for_later = *reaction_type == ReactionType::from(self.save_for_later_emoji);
+        let is_vote = self.voting_emojis.iter().any(|emoji| {
+            matches!(reaction_type, ReactionType::Custom { id, .. } if id.get() == emoji.id)
+        });
+
+        if !is_save_for_later && !is_vote {
+            trace!("Ignoring reaction {}", reaction_type);
+            return;
+        }
+
+        if is_vote && is_removed {
+            trace!("Ignoring removed vote, which has no review to send");
+            return;
+        }
+
+        // Votes take the lock while sending the reviews, since the polling pass sends them too
+        let _save_for_later_lock = match is_save_for_later {
+            true => Some(discord.message_locks.lock(message_id).await),
+            false => None,
+        };
+        let mut message = match discord.backend.message(channel_id, message_id).await {
+            Ok(message) => message,
+            Err(err) => {
+                warn!("Failed to get message that got a reaction: {}", err);
+                return;
+            }
+        };
+
+        if !is_event_message(own_user_id, &message) {
+            return;
+        }
+
+        if is_save_for_later {
+            if discord
+                .tag_save_for_later_reactions(&mut message, self.save_for_later_emoji)
+                .await
+            {
+                discord.delete_pin_notifications(channel_id, 1).await;
+            }
+        } else {
+            let users = discord
+                .send_privately_users_review(&message, &self.voting_emojis)
+                .await;
+
+            info!("Handled the votes of {} users right away", users.len());
+        }
+    }
+}
+
+#[async_trait]
+impl<B: ChatBackend + 'static> EventHandler for ReactionHandler<B> {
+    async fn reaction_add(&self, _ctx: Context, reaction: Reaction) {
+        self.handle_reaction(
+            reaction.channel_id,
+            reaction.message_id,
+            reaction.user_id,
+            reaction.message_author_id,
+            &reaction.emoji,
+            false,
+        )
+        .await;
+    }
+
+    async fn reaction_remove(&self, _ctx: Context, reaction: Reaction) {
+        self.handle_reaction(
+            reaction.channel_id,
+            reaction.message_id,
+            reaction.user_id,
+            reaction.message_author_id,
+            &reaction.emoji,
+            true,
+        )
+        .await;
+    }
+
+    async fn ready(&self, _ctx: Context, ready: Ready) {
+        info!("Connected to the gateway as {}", ready.user.name);
+    }
+}
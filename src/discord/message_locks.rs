@@ -0,0 +1,78 @@
+use serenity::all::MessageId;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+/// A lock per message, so its reactions are handled one at a time, whether through the gateway
+/// or the polling pass, and two quick votes can't both send the same review
+#[derive(Debug, Default)]
+pub struct MessageLocks {
+    locks: Mutex<HashMap<MessageId, Arc<AsyncMutex<()>>>>,
+}
+
+impl MessageLocks {
+    pub async fn lock(&self, message_id: MessageId) -> OwnedMutexGuard<()> {
+        let lock = {
+            let mut locks = self.locks.lock().unwrap();
+
+            // Those only kept here are neither held nor waited for
+            locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+            locks.entry(message_id).or_default().clone()
+        };
+
+        lock.lock_owned().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::time::timeout;
+
+    #[test_log::test(tokio::test)]
+    async fn should_handle_reactions_to_a_message_one_at_a_time() {
+        let message_locks = MessageLocks::default();
+        let first_message_lock = message_locks.lock(MessageId::new(1)).await;
+
+        assert!(timeout(
+            Duration::from_millis(50),
+            message_locks.lock(MessageId::new(1))
+        )
+        .await
+        .is_err());
+        assert!(timeout(
+            Duration::from_millis(50),
+            message_locks.lock(MessageId::new(2))
+        )
+        .await
+        .is_ok());
+
+        drop(first_message_lock);
+
+        assert!(timeout(
+            Duration::from_millis(50),
+            message_locks.lock(MessageId::new(1))
+        )
+        .await
+        .is_ok());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn should_drop_locks_no_one_holds() {
+        let message_locks = MessageLocks::default();
+
+        drop(message_locks.lock(MessageId::new(1)).await);
+        let _second_message_lock = message_locks.lock(MessageId::new(2)).await;
+
+        assert_eq!(
+            message_locks
+                .locks
+                .lock()
+                .unwrap()
+                .keys()
+                .collect::<Vec<_>>(),
+            [&MessageId::new(2)]
+        );
+    }
+}
@@ -1,2 +1,11 @@
 pub mod api;
+pub mod backend;
 pub mod backup;
+pub mod commands;
+pub mod embed_limits;
+#[cfg(any(test, feature = "fake-chat"))]
+pub mod fake;
+pub mod fingerprint;
+pub mod gateway;
+pub mod message_locks;
+pub mod sent_events;
@@ -0,0 +1,576 @@
+use crate::discord::api::is_event_reference;
+use crate::discord::fingerprint::EmbedContent;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serenity::all::{ChannelId, GuildId, Message, MessageId};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::RwLock;
+use tokio::fs;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+/// Where an event was posted, so its message can be found without reading the threads
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SentEvent {
+    pub link: String,
+    pub channel_id: ChannelId,
+    pub thread_id: ChannelId,
+    pub message_id: MessageId,
+    /// Slug of the channel's category
+    pub category: String,
+    /// Of the embed as posted, to tell whether the event changed without fetching the message
+    pub fingerprint: u64,
+    /// Last day the event happens on, unknown until it's listed again after a rebuild
+    #[serde(default)]
+    pub last_date: Option<NaiveDate>,
+}
+
+impl SentEvent {
+    /// None unless the message is a posted event
+    pub fn from_message(message: &Message, channel_id: ChannelId, category: &str) -> Option<Self> {
+        let embed = message.embeds.first()?;
+
+        Some(Self {
+            link: embed.url.clone()?,
+            channel_id,
+            thread_id: message.channel_id,
+            message_id: message.id,
+            category: category.to_string(),
+            fingerprint: EmbedContent::from(embed).fingerprint(),
+            last_date: None,
+        })
+    }
+
+    pub fn with_last_date(mut self, last_date: Option<NaiveDate>) -> Self {
+        self.last_date = last_date;
+        self
+    }
+
+    pub fn message_link(&self, guild_id: GuildId) -> String {
+        self.message_id.link(self.thread_id, Some(guild_id))
+    }
+}
+
+/// A message linking to an event posted in another thread, e.g. by another category
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventReference {
+    pub link: String,
+    /// Of the category the thread is in
+    pub channel_id: ChannelId,
+    pub thread_id: ChannelId,
+    pub message_id: MessageId,
+}
+
+impl EventReference {
+    /// None unless the message is an event reference, which ends in the event's link
+    pub fn from_message(message: &Message, channel_id: ChannelId) -> Option<Self> {
+        if !is_event_reference(message) {
+            return None;
+        }
+
+        let link = message
+            .content
+            .lines()
+            .last()?
+            .strip_prefix('<')?
+            .strip_suffix('>')?;
+
+        Some(Self {
+            link: link.to_string(),
+            channel_id,
+            thread_id: message.channel_id,
+            message_id: message.id,
+        })
+    }
+}
+
+/// As stored, with the indexed channels listed apart since some have no events
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct IndexFile {
+    channels: Vec<ChannelId>,
+    events: Vec<SentEvent>,
+    #[serde(default)]
+    references: Vec<EventReference>,
+}
+
+/// The events posted and referenced in each channel, saved to a JSON file between runs.
+/// Channels missing from it have to be rebuilt from their threads' history
+#[derive(Debug, Default)]
+pub struct SentEventsIndex {
+    /// None to only keep it in memory
+    path: Option<PathBuf>,
+    channels: RwLock<HashMap<ChannelId, IndexedChannel>>,
+    /// Saves share the temporary file, so they take turns writing and renaming it
+    save_lock: Mutex<()>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct IndexedChannel {
+    /// By link
+    events: HashMap<String, SentEvent>,
+    /// By thread and link
+    references: HashMap<(ChannelId, String), EventReference>,
+}
+
+impl IndexedChannel {
+    fn new(sent_events: Vec<SentEvent>, references: Vec<EventReference>) -> Self {
+        Self {
+            events: sent_events
+                .into_iter()
+                .map(|sent_event| (sent_event.link.clone(), sent_event))
+                .collect(),
+            references: references
+                .into_iter()
+                .map(|reference| ((reference.thread_id, reference.link.clone()), reference))
+                .collect(),
+        }
+    }
+}
+
+impl SentEventsIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts empty when the file is missing or unreadable, so every channel gets rebuilt
+    pub async fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let index_file = match fs::read_to_string(&path).await {
+            Ok(content) => serde_json::from_str::<IndexFile>(&content).unwrap_or_else(|err| {
+                warn!("Ignoring corrupted sent events index {:?}: {}", path, err);
+                IndexFile::default()
+            }),
+            Err(err) => {
+                info!(
+                    "No sent events index at {:?} ({}), rebuilding it",
+                    path, err
+                );
+                IndexFile::default()
+            }
+        };
+
+        let mut channels: HashMap<ChannelId, IndexedChannel> = index_file
+            .channels
+            .into_iter()
+            .map(|channel_id| (channel_id, IndexedChannel::default()))
+            .collect();
+
+        for sent_event in index_file.events {
+            channels
+                .entry(sent_event.channel_id)
+                .or_default()
+                .events
+                .insert(sent_event.link.clone(), sent_event);
+        }
+
+        for reference in index_file.references {
+            channels
+                .entry(reference.channel_id)
+                .or_default()
+                .references
+                .insert((reference.thread_id, reference.link.clone()), reference);
+        }
+
+        Self {
+            path: Some(path),
+            channels: RwLock::new(channels),
+            save_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn is_indexed(&self, channel_id: ChannelId) -> bool {
+        self.channels.read().unwrap().contains_key(&channel_id)
+    }
+
+    /// Replaces what's known of the channel, e.g. once rebuilt from its history
+    pub fn index_channel(
+        &self,
+        channel_id: ChannelId,
+        sent_events: Vec<SentEvent>,
+        references: Vec<EventReference>,
+    ) {
+        self.channels
+            .write()
+            .unwrap()
+            .insert(channel_id, IndexedChannel::new(sent_events, references));
+    }
+
+    pub fn record(&self, sent_event: SentEvent) {
+        self.channels
+            .write()
+            .unwrap()
+            .entry(sent_event.channel_id)
+            .or_default()
+            .events
+            .insert(sent_event.link.clone(), sent_event);
+    }
+
+    pub fn record_reference(&self, reference: EventReference) {
+        self.channels
+            .write()
+            .unwrap()
+            .entry(reference.channel_id)
+            .or_default()
+            .references
+            .insert((reference.thread_id, reference.link.clone()), reference);
+    }
+
+    /// Keeps the fingerprint of an edited event message
+    pub fn refresh(&self, channel_id: ChannelId, message: &Message) {
+        let mut channels = self.channels.write().unwrap();
+        let sent_event = message
+            .embeds
+            .first()
+            .and_then(|embed| embed.url.as_ref())
+            .and_then(|link| channels.get_mut(&channel_id)?.events.get_mut(link));
+
+        if let (Some(sent_event), Some(embed)) = (sent_event, message.embeds.first()) {
+            sent_event.fingerprint = EmbedContent::from(embed).fingerprint();
+        }
+    }
+
+    pub fn set_last_date(&self, channel_id: ChannelId, link: &str, last_date: Option<NaiveDate>) {
+        if let Some(sent_event) = self
+            .channels
+            .write()
+            .unwrap()
+            .get_mut(&channel_id)
+            .and_then(|indexed_channel| indexed_channel.events.get_mut(link))
+        {
+            sent_event.last_date = last_date;
+        }
+    }
+
+    pub fn forget(&self, channel_id: ChannelId, link: &str) {
+        if let Some(indexed_channel) = self.channels.write().unwrap().get_mut(&channel_id) {
+            indexed_channel.events.remove(link);
+        }
+    }
+
+    /// Forgets the events and references of the channel's threads that no longer exist,
+    /// returning how many
+    pub fn forget_missing_threads(
+        &self,
+        channel_id: ChannelId,
+        thread_ids: &HashSet<ChannelId>,
+    ) -> usize {
+        let mut channels = self.channels.write().unwrap();
+        let Some(indexed_channel) = channels.get_mut(&channel_id) else {
+            return 0;
+        };
+        let count = indexed_channel.events.len() + indexed_channel.references.len();
+
+        indexed_channel
+            .events
+            .retain(|_, sent_event| thread_ids.contains(&sent_event.thread_id));
+        indexed_channel
+            .references
+            .retain(|(thread_id, _), _| thread_ids.contains(thread_id));
+
+        count - indexed_channel.events.len() - indexed_channel.references.len()
+    }
+
+    /// The channel will have to be rebuilt, e.g. once its messages were deleted
+    pub fn forget_channel(&self, channel_id: ChannelId) {
+        self.channels.write().unwrap().remove(&channel_id);
+    }
+
+    pub fn get(&self, channel_id: ChannelId, link: &str) -> Option<SentEvent> {
+        self.channels
+            .read()
+            .unwrap()
+            .get(&channel_id)?
+            .events
+            .get(link)
+            .cloned()
+    }
+
+    /// The channel's events by link
+    pub fn of_channel(&self, channel_id: ChannelId) -> HashMap<String, SentEvent> {
+        self.channels
+            .read()
+            .unwrap()
+            .get(&channel_id)
+            .map(|indexed_channel| indexed_channel.events.clone())
+            .unwrap_or_default()
+    }
+
+    /// The events posted in one of the channel's threads, oldest first
+    pub fn of_thread(&self, channel_id: ChannelId, thread_id: ChannelId) -> Vec<SentEvent> {
+        let mut sent_events: Vec<SentEvent> = self
+            .of_channel(channel_id)
+            .into_values()
+            .filter(|sent_event| sent_event.thread_id == thread_id)
+            .collect();
+
+        sent_events.sort_by_key(|sent_event| sent_event.message_id);
+        sent_events
+    }
+
+    pub fn links(&self, channel_id: ChannelId) -> HashSet<String> {
+        self.channels
+            .read()
+            .unwrap()
+            .get(&channel_id)
+            .map(|indexed_channel| indexed_channel.events.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Those the event was posted in, in order
+    pub fn channels_of(&self, link: &str) -> Vec<ChannelId> {
+        let mut channel_ids: Vec<ChannelId> = self
+            .channels
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(_, indexed_channel)| indexed_channel.events.contains_key(link))
+            .map(|(channel_id, _)| *channel_id)
+            .collect();
+
+        channel_ids.sort();
+        channel_ids
+    }
+
+    /// Whether the event was posted or referenced in the thread
+    pub fn is_in_thread(&self, channel_id: ChannelId, thread_id: ChannelId, link: &str) -> bool {
+        let channels = self.channels.read().unwrap();
+        let Some(indexed_channel) = channels.get(&channel_id) else {
+            return false;
+        };
+
+        indexed_channel
+            .events
+            .get(link)
+            .is_some_and(|sent_event| sent_event.thread_id == thread_id)
+            || indexed_channel
+                .references
+                .contains_key(&(thread_id, link.to_string()))
+    }
+
+    /// Written to a temporary file first, so a crash never leaves it half written
+    pub async fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        // Before reading the channels, so the last save to write holds the latest events
+        let _saving = self.save_lock.lock().await;
+
+        let content = {
+            let channels = self.channels.read().unwrap();
+            let mut index_file = IndexFile {
+                channels: channels.keys().copied().collect(),
+                events: channels
+                    .values()
+                    .flat_map(|indexed_channel| indexed_channel.events.values())
+                    .cloned()
+                    .collect(),
+                references: channels
+                    .values()
+                    .flat_map(|indexed_channel| indexed_channel.references.values())
+                    .cloned()
+                    .collect(),
+            };
+
+            index_file.channels.sort();
+            index_file
+                .events
+                .sort_by(|event, other| event.link.cmp(&other.link));
+            index_file
+                .references
+                .sort_by_key(|reference| reference.message_id);
+            serde_json::to_string_pretty(&index_file)
+        };
+
+        let content = match content {
+            Ok(content) => content,
+            Err(err) => {
+                warn!("Failed to serialize the sent events index: {}", err);
+                return;
+            }
+        };
+
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            if let Err(err) = fs::create_dir_all(parent).await {
+                warn!("Failed to create sent events index folder: {}", err);
+                return;
+            }
+        }
+
+        let temporary_path = path.with_extension("json.tmp");
+
+        if let Err(err) = fs::write(&temporary_path, content).await {
+            warn!("Failed to write sent events index: {}", err);
+            return;
+        }
+
+        match fs::rename(&temporary_path, path).await {
+            Ok(_) => debug!("Saved sent events index to {:?}", path),
+            Err(err) => warn!("Failed to replace sent events index: {}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn build_sent_event(channel_id: u64, link: &str) -> SentEvent {
+        SentEvent {
+            link: link.to_string(),
+            channel_id: ChannelId::new(channel_id),
+            thread_id: ChannelId::new(channel_id + 100),
+            message_id: MessageId::new(7),
+            category: "teatro".to_string(),
+            fingerprint: 42,
+            last_date: NaiveDate::from_ymd_opt(2025, 3, 1),
+        }
+    }
+
+    fn build_reference(channel_id: u64, link: &str) -> EventReference {
+        EventReference {
+            link: link.to_string(),
+            channel_id: ChannelId::new(channel_id),
+            thread_id: ChannelId::new(channel_id + 101),
+            message_id: MessageId::new(8),
+        }
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn should_keep_indexed_channels_between_loads() {
+        let path = std::env::temp_dir().join(format!("sent_events_{}.json", Uuid::new_v4()));
+        let index = SentEventsIndex::load(&path).await;
+
+        index.record(build_sent_event(1, "https://example.com/hamlet"));
+        index.record_reference(build_reference(1, "https://example.com/medeia"));
+        index.index_channel(ChannelId::new(2), Vec::new(), Vec::new());
+        index.save().await;
+
+        let loaded = SentEventsIndex::load(&path).await;
+
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            loaded.get(ChannelId::new(1), "https://example.com/hamlet"),
+            Some(build_sent_event(1, "https://example.com/hamlet"))
+        );
+        assert!(loaded.is_in_thread(
+            ChannelId::new(1),
+            ChannelId::new(102),
+            "https://example.com/medeia"
+        ));
+        assert!(loaded.is_indexed(ChannelId::new(2)));
+        assert!(!loaded.is_indexed(ChannelId::new(3)));
+    }
+
+    #[test_log::test(tokio::test(flavor = "multi_thread"))]
+    async fn when_saved_concurrently_should_keep_latest_events() {
+        let path = std::env::temp_dir().join(format!("sent_events_{}.json", Uuid::new_v4()));
+        let index = SentEventsIndex::load(&path).await;
+        let links: Vec<String> = (0..10)
+            .map(|event| format!("https://example.com/events/{}", event))
+            .collect();
+
+        futures::future::join_all(links.iter().map(|link| {
+            index.record(build_sent_event(1, link));
+            index.save()
+        }))
+        .await;
+
+        let loaded = SentEventsIndex::load(&path).await;
+
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(!path.with_extension("json.tmp").exists());
+        for link in &links {
+            assert!(loaded.get(ChannelId::new(1), link).is_some());
+        }
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn when_file_is_corrupted_should_start_empty() {
+        let path = std::env::temp_dir().join(format!("sent_events_{}.json", Uuid::new_v4()));
+
+        std::fs::write(&path, "{").unwrap();
+
+        let index = SentEventsIndex::load(&path).await;
+
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(!index.is_indexed(ChannelId::new(1)));
+    }
+
+    #[test_log::test]
+    fn should_forget_events_and_channels() {
+        let index = SentEventsIndex::new();
+
+        index.record(build_sent_event(1, "https://example.com/hamlet"));
+        index.record(build_sent_event(1, "https://example.com/medeia"));
+        index.forget(ChannelId::new(1), "https://example.com/hamlet");
+
+        assert_eq!(
+            index.links(ChannelId::new(1)),
+            HashSet::from(["https://example.com/medeia".to_string()])
+        );
+
+        index.forget_channel(ChannelId::new(1));
+
+        assert!(!index.is_indexed(ChannelId::new(1)));
+    }
+
+    #[test_log::test]
+    fn should_tell_events_posted_or_referenced_in_a_thread() {
+        let index = SentEventsIndex::new();
+        let channel_id = ChannelId::new(1);
+
+        index.record(build_sent_event(1, "https://example.com/hamlet"));
+        index.record_reference(build_reference(1, "https://example.com/medeia"));
+
+        assert!(index.is_in_thread(
+            channel_id,
+            ChannelId::new(101),
+            "https://example.com/hamlet"
+        ));
+        assert!(!index.is_in_thread(
+            channel_id,
+            ChannelId::new(102),
+            "https://example.com/hamlet"
+        ));
+        assert!(index.is_in_thread(
+            channel_id,
+            ChannelId::new(102),
+            "https://example.com/medeia"
+        ));
+        assert!(!index.is_in_thread(
+            channel_id,
+            ChannelId::new(101),
+            "https://example.com/medeia"
+        ));
+    }
+
+    #[test_log::test]
+    fn should_forget_what_was_in_deleted_threads() {
+        let index = SentEventsIndex::new();
+        let channel_id = ChannelId::new(1);
+
+        index.record(build_sent_event(1, "https://example.com/hamlet"));
+        index.record_reference(build_reference(1, "https://example.com/medeia"));
+
+        let forgotten_count =
+            index.forget_missing_threads(channel_id, &HashSet::from([ChannelId::new(101)]));
+
+        assert_eq!(forgotten_count, 1);
+        assert!(index
+            .get(channel_id, "https://example.com/hamlet")
+            .is_some());
+        assert!(!index.is_in_thread(
+            channel_id,
+            ChannelId::new(102),
+            "https://example.com/medeia"
+        ));
+    }
+}
@@ -0,0 +1,7 @@
+/// FNV-1a, which unlike the std hasher is stable across runs and Rust versions,
+/// so hashes can be persisted or compared with previous runs
+pub fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
@@ -1,6 +1,12 @@
 pub mod agenda_cultural;
 pub mod api;
+pub mod cancellation;
 pub mod config;
+pub mod daemon;
+pub mod dedup;
 pub mod discord;
+pub mod hashing;
 pub mod metrics;
+pub mod pipeline;
+pub mod posted_events;
 pub mod tracing;
@@ -15,6 +15,14 @@ pub enum MetricResult {
     Error,
 }
 
+#[derive(Clone, Copy, Debug)]
+pub enum CacheLookupResult {
+    Hit,
+    /// Stale but confirmed unchanged by the server
+    Revalidated,
+    Miss,
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum PipelineStage {
     FetchEvents,
@@ -40,6 +48,17 @@ impl Display for MetricResult {
     }
 }
 
+impl Display for CacheLookupResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            CacheLookupResult::Hit => "hit",
+            CacheLookupResult::Revalidated => "revalidated",
+            CacheLookupResult::Miss => "miss",
+        };
+        write!(f, "{}", value)
+    }
+}
+
 impl Display for PipelineStage {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let value = match self {
@@ -65,7 +84,7 @@ impl Display for PipelineErrorKind {
 
 impl From<&Category> for KeyValue {
     fn from(category: &Category) -> Self {
-        KeyValue::new("category", category.to_string().to_lowercase())
+        KeyValue::new("category", category.slug.clone())
     }
 }
 
@@ -75,6 +94,12 @@ impl From<MetricResult> for KeyValue {
     }
 }
 
+impl From<CacheLookupResult> for KeyValue {
+    fn from(result: CacheLookupResult) -> Self {
+        KeyValue::new("cache_result", result.to_string())
+    }
+}
+
 impl From<PipelineStage> for KeyValue {
     fn from(stage: PipelineStage) -> Self {
         KeyValue::new("stage", stage.to_string())
@@ -140,10 +165,28 @@ lazy_static! {
         .with_description("Duration of AgendaCulturalAPI::get_events_by_month call")
         .with_unit("s")
         .init();
+    static ref EVENT_PAGE_FETCHES_TOTAL: Counter<u64> = METER
+        .u64_counter("aec_event_page_fetches_total")
+        .with_description("Total event page fetch attempts for full descriptions")
+        .init();
+    static ref DESCRIPTION_FALLBACKS_TOTAL: Counter<u64> = METER
+        .u64_counter("aec_description_fallbacks_total")
+        .with_description(
+            "Total events posted with the preview description instead of the full one"
+        )
+        .init();
+    static ref PAGE_CACHE_LOOKUPS_TOTAL: Counter<u64> = METER
+        .u64_counter("aec_page_cache_lookups_total")
+        .with_description("Total event page cache lookups, by whether the cached page was used")
+        .init();
     static ref DM_REVIEW_SENT_TOTAL: Counter<u64> = METER
         .u64_counter("aec_dm_review_sent_total")
         .with_description("Total DM review send attempts")
         .init();
+    static ref STATUS_NOTICE_SENT_TOTAL: Counter<u64> = METER
+        .u64_counter("aec_status_notice_sent_total")
+        .with_description("Total DM notices of cancelled, postponed or removed events")
+        .init();
     static ref DM_REVIEW_REWRITE_TOTAL: Counter<u64> = METER
         .u64_counter("aec_dm_review_rewrite_total")
         .with_description("Total DM review rewrite attempts")
@@ -170,6 +213,10 @@ lazy_static! {
         .u64_gauge("aec_threads_active")
         .with_description("Current active thread count per category")
         .init();
+    static ref VENUES_WITHOUT_TICKET_SHOP: Gauge<u64> = METER
+        .u64_gauge("aec_venues_without_ticket_shop")
+        .with_description("Venues of the fetched events without a ticket shop URL per category")
+        .init();
     static ref VOTE: Gauge<u64> = METER
         .u64_gauge("aec_vote")
         .with_description("Vote number (1-5) cast by a voter")
@@ -196,10 +243,26 @@ pub fn record_get_events_by_month_duration(category: &Category, duration: Durati
     GET_EVENTS_BY_MONTH_DURATION_SECONDS.record(duration.as_secs_f64(), &[category.into()]);
 }
 
+pub fn record_event_page_fetch(result: MetricResult) {
+    EVENT_PAGE_FETCHES_TOTAL.add(1, &[result.into()]);
+}
+
+pub fn record_description_fallback() {
+    DESCRIPTION_FALLBACKS_TOTAL.add(1, &[]);
+}
+
+pub fn record_page_cache_lookup(result: CacheLookupResult) {
+    PAGE_CACHE_LOOKUPS_TOTAL.add(1, &[result.into()]);
+}
+
 pub fn record_dm_review_sent(result: MetricResult) {
     DM_REVIEW_SENT_TOTAL.add(1, &[result.into()]);
 }
 
+pub fn record_status_notice_sent(result: MetricResult) {
+    STATUS_NOTICE_SENT_TOTAL.add(1, &[result.into()]);
+}
+
 pub fn record_dm_review_rewrite(result: MetricResult) {
     DM_REVIEW_REWRITE_TOTAL.add(1, &[result.into()]);
 }
@@ -234,6 +297,10 @@ pub fn set_threads_active(category: &Category, count: u64) {
     THREADS_ACTIVE.record(count, &[category.into()]);
 }
 
+pub fn set_venues_without_ticket_shop(category: &Category, count: u64) {
+    VENUES_WITHOUT_TICKET_SHOP.record(count, &[category.into()]);
+}
+
 pub fn record_vote(vote_number: u64, voter: &str, event_url: &str) {
     VOTE.record(
         vote_number,
@@ -0,0 +1,617 @@
+use crate::agenda_cultural::model::{Event, Price};
+use crate::agenda_cultural::ongoing::ongoing_events_by_month;
+use crate::agenda_cultural::source::EventSource;
+use crate::api::*;
+use crate::cancellation::Cancellation;
+use crate::config::model::{
+    CategoryConfig, Config, EmojiConfig, FreeEventsPolicy, LongRunningEventsPolicy,
+};
+use crate::dedup::EventOwners;
+use crate::discord::api::{is_event_message, DiscordAPI, EventsThread};
+use crate::discord::backend::ChatBackend;
+use crate::discord::backup::{backup_user_votes, VoteRecord};
+use crate::discord::sent_events::SentEvent;
+use crate::metrics::{
+    record_event_send_duration, record_event_sent, record_events_fetched,
+    record_get_events_by_month_duration, record_pipeline_error, record_pipeline_run_duration,
+    record_pipeline_run_duration_without_event_gather, record_reaction_processing_duration,
+    record_vote, record_vote_backup_duration, record_vote_backup_records, set_threads_active,
+    set_venues_without_ticket_shop, MetricResult, PipelineErrorKind, PipelineStage,
+};
+use chrono::{Datelike, Utc};
+use futures::{future, TryFutureExt};
+use itertools::Itertools;
+use lazy_static::lazy_static;
+use serenity::all::{ChannelId, GuildChannel, UserId};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use std::time::Instant;
+use tokio::fs;
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, error, info, info_span, instrument, trace, warn, Instrument};
+
+lazy_static! {
+    pub static ref SAVE_FOR_LATER_EMOJI: char = '🔖';
+}
+
+#[instrument(skip_all, fields(category = %category_config.category))]
+pub async fn run(
+    config: &Config,
+    discord: &DiscordAPI<impl ChatBackend>,
+    source: &impl EventSource,
+    category_config: &CategoryConfig,
+    event_owners: &mut EventOwners,
+) -> Vec<UserId> {
+    let category = &category_config.category;
+    let pipeline_started_at = Instant::now();
+    // One-off runs always finish
+    let cancellation = Cancellation::never();
+    let users_with_reactions =
+        process_reactions(config, discord, category_config, &cancellation).await;
+
+    if !config.gather_new_events {
+        info!("Set to not gather new events");
+        record_pipeline_run_duration_without_event_gather(category, pipeline_started_at.elapsed());
+        return users_with_reactions;
+    }
+
+    gather_events(
+        config,
+        discord,
+        source,
+        category_config,
+        event_owners,
+        &cancellation,
+    )
+    .await;
+    record_pipeline_run_duration(category, pipeline_started_at.elapsed());
+
+    users_with_reactions
+}
+
+/// Returns users who have used reaction features in the threads of the category
+pub async fn process_reactions(
+    config: &Config,
+    discord: &DiscordAPI<impl ChatBackend>,
+    category_config: &CategoryConfig,
+    cancellation: &Cancellation,
+) -> Vec<UserId> {
+    let category = &category_config.category;
+    let channel_id = category_config.channel_id;
+    let guild_id = discord.get_guild(channel_id).await;
+    let threads = discord.get_channel_threads(guild_id, channel_id).await;
+    set_threads_active(category, threads.len() as u64);
+
+    if config.debug_config.skip_feature_reactions {
+        return Vec::new();
+    }
+
+    // The events to look at are the indexed ones
+    index_sent_events(discord, guild_id, channel_id, category)
+        .instrument(info_span!("index_sent_events"))
+        .await;
+
+    let reaction_started_at = Instant::now();
+    let users_with_reactions = handle_reaction_features(
+        discord,
+        channel_id,
+        threads,
+        &config.voting_emojis,
+        cancellation,
+    )
+    .await;
+    record_reaction_processing_duration(category, reaction_started_at.elapsed());
+    discord.sent_events.save().await;
+
+    info!("Handled reaction features");
+
+    users_with_reactions
+}
+
+/// Sends the new events of the category and updates the ones already sent.
+/// Returns the events the category posts, the shared ones it owns included,
+/// or None when they couldn't be fetched or it was cancelled before sending them
+pub async fn gather_events(
+    config: &Config,
+    discord: &DiscordAPI<impl ChatBackend>,
+    source: &impl EventSource,
+    category_config: &CategoryConfig,
+    event_owners: &mut EventOwners,
+    cancellation: &Cancellation,
+) -> Option<Vec<Event>> {
+    let category = &category_config.category;
+    let channel_id = category_config.channel_id;
+    let guild_id = discord.get_guild(channel_id).await;
+
+    index_sent_events(discord, guild_id, channel_id, category)
+        .instrument(info_span!("index_sent_events"))
+        .await;
+
+    let get_events_started_at = Instant::now();
+    let events = source
+        .get_events_by_month(category, config.debug_config.event_limit)
+        .await;
+    record_get_events_by_month_duration(category, get_events_started_at.elapsed());
+
+    if let Err(err) = events {
+        error!("Failed getting events. Reason: {:?}", err);
+        record_pipeline_error(PipelineStage::FetchEvents, PipelineErrorKind::Api);
+        return None;
+    }
+
+    let mut events = events.unwrap();
+
+    if events.is_empty() {
+        error!("No events found");
+        record_pipeline_error(PipelineStage::FetchEvents, PipelineErrorKind::EmptyResult);
+        return None;
+    }
+
+    if cancellation.is_cancelled() {
+        info!("Cancelled before sending the events of {}", category);
+        return None;
+    }
+
+    let fetched_count: usize = events.values().map(|events| events.len()).sum();
+    record_events_fetched(category, fetched_count as u64);
+
+    let unmapped_venues = config
+        .venue_ticket_shops
+        .unmapped_venues(events.values().flatten().map(|event| &event.venue));
+    set_venues_without_ticket_shop(category, unmapped_venues.len() as u64);
+
+    if !unmapped_venues.is_empty() {
+        warn!(
+            "{} venues have no ticket shop URL: {}",
+            unmapped_venues.len(),
+            unmapped_venues.join(", ")
+        );
+    }
+
+    let today = Utc::now().date_naive();
+    let listed_links: HashSet<String> = events
+        .values()
+        .flatten()
+        .map(|event| event.link.clone())
+        .collect();
+
+    if category_config.free_events == FreeEventsPolicy::OnlyFree {
+        events
+            .values_mut()
+            .for_each(|events| events.retain(|event| event.price == Price::Free));
+        events.retain(|_, events| !events.is_empty());
+        info!("Kept only free events for {}", category);
+    }
+
+    if !category_config.tag_filter.is_empty() {
+        events
+            .values_mut()
+            .for_each(|events| events.retain(|event| category_config.tag_filter.allows(event)));
+        events.retain(|_, events| !events.is_empty());
+        info!("Kept only events with the configured tags for {}", category);
+    }
+
+    let shared_events = event_owners.claim(&mut events, channel_id, &discord.sent_events);
+    let shared_count: usize = shared_events.values().map(|events| events.len()).sum();
+    info!("Left {} events to other categories", shared_count);
+
+    if config.shared_events.link_in_other_channels && !config.debug_config.skip_sending {
+        let sent_count = send_shared_event_references(discord, guild_id, shared_events, channel_id)
+            .instrument(info_span!("send_shared_event_references"))
+            .await;
+
+        info!("Sent {} shared event references", sent_count);
+    }
+
+    if !config.debug_config.skip_sending {
+        let updated_count = update_changed_events(
+            discord,
+            &events,
+            channel_id,
+            config,
+            category_config.free_events == FreeEventsPolicy::HighlightFree,
+            *SAVE_FOR_LATER_EMOJI,
+        )
+        .instrument(info_span!("update_changed_events"))
+        .await;
+
+        info!("Updated {} changed events", updated_count);
+    }
+
+    // A limited fetch doesn't list every event, so missing ones can't be told apart
+    if !config.debug_config.skip_sending && config.debug_config.event_limit.is_none() {
+        let removed_count = mark_removed_events(
+            discord,
+            guild_id,
+            &listed_links,
+            channel_id,
+            today,
+            config,
+            *SAVE_FOR_LATER_EMOJI,
+        )
+        .instrument(info_span!("mark_removed_events"))
+        .await;
+
+        info!("Marked {} removed events", removed_count);
+    }
+
+    let ongoing_events = ongoing_events_by_month(&events, today.with_day(1).unwrap_or(today));
+    let category_events: Vec<Event> = events.values().flatten().cloned().collect();
+
+    let new_events = filter_new_events_by_thread(discord, guild_id, events, channel_id)
+        .instrument(info_span!("filter_new_events"))
+        .await;
+
+    info!("Filtered new events");
+
+    send_new_events(discord, new_events, config, category_config, cancellation).await;
+
+    info!("Finished sending new events for {}", category);
+
+    if !config.debug_config.skip_sending && !cancellation.is_cancelled() {
+        match config.long_running_events {
+            LongRunningEventsPolicy::StartMonthOnly => {}
+            LongRunningEventsPolicy::CrossPost => {
+                let sent_count =
+                    send_ongoing_event_references(discord, guild_id, ongoing_events, channel_id)
+                        .instrument(info_span!("send_ongoing_event_references"))
+                        .await;
+
+                info!("Sent {} ongoing event references", sent_count);
+            }
+            LongRunningEventsPolicy::RollUp => {
+                let updated_count = update_ongoing_events_roll_ups(
+                    discord,
+                    guild_id,
+                    ongoing_events,
+                    channel_id,
+                    today,
+                )
+                .instrument(info_span!("update_ongoing_events_roll_ups"))
+                .await;
+
+                info!("Updated {} ongoing events roll-ups", updated_count);
+            }
+        }
+    }
+
+    discord.sent_events.save().await;
+
+    Some(category_events)
+}
+
+#[instrument(skip(discord, user_ids))]
+pub async fn rewrite_reviews_from_replies(
+    discord: &DiscordAPI<impl ChatBackend>,
+    user_ids: &[UserId],
+) {
+    for user_id in user_ids {
+        let rewritten = discord.rewrite_reviews_from_dm_replies(*user_id).await;
+
+        if rewritten > 0 {
+            info!(
+                "Rewrote {} review comment(s) from DM replies for user {}",
+                rewritten, user_id
+            );
+        }
+    }
+}
+
+/// Writes the votes of the users to a file of the day in the folder, unless there's one already
+#[instrument(skip(discord, vote_emojis))]
+pub async fn backup_votes(
+    discord: &DiscordAPI<impl ChatBackend>,
+    vec: Vec<UserId>,
+    vote_emojis: &[EmojiConfig; 5],
+    vote_backups_folder: &Path,
+) {
+    let backup_started_at = Instant::now();
+    let vote_backup_file_path = vote_backups_folder
+        .join(format!("{}.json", Utc::now().format("%Y_%m_%d")))
+        .display()
+        .to_string();
+
+    fs::try_exists(vote_backups_folder)
+        .and_then(|exists| async move {
+            if exists {
+                Ok(())
+            } else {
+                fs::create_dir(vote_backups_folder).await
+            }
+        })
+        .unwrap_or_else(|e| {
+            error!("Failed to create vote backups folder! Error: {}", e);
+            record_pipeline_error(PipelineStage::BackupVotes, PipelineErrorKind::Io);
+        })
+        .await;
+
+    match fs::try_exists(vote_backup_file_path.clone()).await {
+        Ok(exists) => {
+            if exists {
+                info!("Vote backup file already exists for today, skipping backup");
+                record_vote_backup_duration(MetricResult::Ok, backup_started_at.elapsed());
+                return;
+            }
+        }
+        Err(e) => {
+            error!("Failed to check if vote backup file exists! Error: {}", e);
+            record_pipeline_error(PipelineStage::BackupVotes, PipelineErrorKind::Io);
+            record_vote_backup_duration(MetricResult::Error, backup_started_at.elapsed());
+            return;
+        }
+    }
+
+    let user_votes: Vec<VoteRecord> = future::join_all(
+        vec.iter()
+            .map(|user_id| backup_user_votes(discord, *user_id)),
+    )
+    .await
+    .into_iter()
+    .flatten()
+    .concat();
+
+    for vote_record in &user_votes {
+        match vote_emojis
+            .iter()
+            .position(|emoji| emoji.to_string() == vote_record.user_vote.vote)
+        {
+            Some(index) => record_vote(
+                index as u64 + 1,
+                &vote_record.user_id.to_string(),
+                &vote_record.url,
+            ),
+            None => warn!(
+                "Unrecognized vote emoji '{}' for user {}",
+                vote_record.user_vote.vote, vote_record.user_id
+            ),
+        }
+    }
+
+    record_vote_backup_records(user_votes.len() as u64);
+
+    let backup_votes_file = File::create(&vote_backup_file_path).await;
+
+    if let Err(err) = backup_votes_file {
+        error!(
+            "Failed to create vote backup file at {}! Error: {}",
+            vote_backup_file_path, err
+        );
+        record_pipeline_error(PipelineStage::BackupVotes, PipelineErrorKind::Io);
+        record_vote_backup_duration(MetricResult::Error, backup_started_at.elapsed());
+        return;
+    }
+
+    let serialized_votes = match serde_json::to_vec_pretty(&user_votes) {
+        Ok(v) => v,
+        Err(e) => {
+            error!("Failed to serialize user votes! Error: {}", e);
+            record_pipeline_error(PipelineStage::BackupVotes, PipelineErrorKind::Serialize);
+            record_vote_backup_duration(MetricResult::Error, backup_started_at.elapsed());
+            return;
+        }
+    };
+
+    let write_return = write_vote_backup(backup_votes_file.unwrap(), &serialized_votes).await;
+
+    if let Err(e) = write_return {
+        error!("Failed to write vote backup file! Error: {}", e);
+        record_pipeline_error(PipelineStage::BackupVotes, PipelineErrorKind::Io);
+        record_vote_backup_duration(MetricResult::Error, backup_started_at.elapsed());
+        return;
+    }
+
+    info!("Vote backup file written to {}", vote_backup_file_path);
+    record_vote_backup_duration(MetricResult::Ok, backup_started_at.elapsed());
+}
+
+/// Tokio writes in the background, so the file may still be empty once dropped unless flushed
+async fn write_vote_backup(mut file: File, serialized_votes: &[u8]) -> std::io::Result<()> {
+    file.write_all(serialized_votes).await?;
+    file.flush().await
+}
+
+/// Returns users who have used reaction features in the given threads of the channel,
+/// on the events indexed in them
+#[instrument(skip(discord, threads, vote_emojis))]
+pub async fn handle_reaction_features(
+    discord: &DiscordAPI<impl ChatBackend>,
+    channel_id: ChannelId,
+    threads: Vec<GuildChannel>,
+    vote_emojis: &[EmojiConfig; 5],
+    cancellation: &Cancellation,
+) -> Vec<UserId> {
+    let mut users_with_reactions = Vec::new();
+
+    for thread in threads {
+        if cancellation.is_cancelled() {
+            info!("Cancelled before handling the reactions of the remaining threads");
+            break;
+        }
+
+        let thread_span = info_span!("process_thread_reactions", thread = %thread.name);
+
+        async {
+            let Some(meta) = thread.thread_metadata else {
+                warn!("Thread '{}' has no metadata, skipping", thread.name);
+                return;
+            };
+            if meta.locked {
+                trace!("Ignoring locked thread (probably out-of-date)");
+                return;
+            }
+
+            let mut messages = Vec::new();
+
+            for sent_event in discord.sent_events.of_thread(channel_id, thread.id) {
+                // Events whose message was deleted are forgotten here, so they get posted again
+                if let Some(message) = discord.get_sent_event_message(&sent_event).await {
+                    messages.push(message);
+                }
+            }
+
+            debug!(
+                "Tagging save for later and sending votes in DM (on thread '{}' with {} events)",
+                thread.name,
+                messages.len()
+            );
+
+            let mut pin_count = 0usize;
+
+            for mut message in messages {
+                if !is_event_message(discord.own_user.id, &message) {
+                    continue;
+                }
+
+                if discord
+                    .tag_save_for_later_reactions(&mut message, *SAVE_FOR_LATER_EMOJI)
+                    .await
+                {
+                    pin_count += 1;
+                }
+
+                discord
+                    .send_privately_users_review(&message, vote_emojis)
+                    .await
+                    .iter()
+                    .for_each(|u| {
+                        if !users_with_reactions.contains(u) {
+                            users_with_reactions.push(*u);
+                        }
+                    });
+            }
+
+            discord.delete_pin_notifications(thread.id, pin_count).await;
+        }
+        .instrument(thread_span)
+        .await;
+    }
+
+    users_with_reactions
+}
+
+#[instrument(skip_all, fields(new_events_count = %new_events.len()
+))]
+pub async fn send_new_events(
+    discord: &DiscordAPI<impl ChatBackend>,
+    new_events: BTreeMap<EventsThread, Vec<Event>>,
+    config: &Config,
+    category_config: &CategoryConfig,
+    cancellation: &Cancellation,
+) {
+    let category = &category_config.category;
+    let free_events = category_config.free_events;
+
+    if new_events.is_empty() {
+        info!("No new events to send");
+        return;
+    }
+
+    if config.debug_config.skip_sending {
+        info!("Skipping sending events");
+        return;
+    }
+
+    for (thread, events) in new_events {
+        info!(
+            "Found {} new events for thread '{}'",
+            events.len(),
+            discord
+                .backend
+                .channel_name(thread.thread_id)
+                .await
+                .unwrap_or_default()
+        );
+
+        for event in events {
+            if cancellation.is_cancelled() {
+                info!("Cancelled before sending the remaining events");
+                break;
+            }
+
+            async {
+                let ticket_url = config.ticket_shop_url(&event.venue);
+                let last_date = event.occurring_at.last_day();
+                let send_started_at = Instant::now();
+                let message = match discord
+                    .send_event(
+                        thread.thread_id,
+                        event,
+                        ticket_url,
+                        &config.ticket_shop_icon_url,
+                        free_events == FreeEventsPolicy::HighlightFree,
+                    )
+                    .await
+                {
+                    Ok(msg) => {
+                        record_event_sent(category, MetricResult::Ok);
+                        record_event_send_duration(category, send_started_at.elapsed());
+
+                        if let Some(sent_event) = SentEvent::from_message(
+                            &msg,
+                            category_config.channel_id,
+                            &category.slug,
+                        ) {
+                            discord
+                                .sent_events
+                                .record(sent_event.with_last_date(last_date));
+                        }
+                        msg
+                    }
+                    Err(_) => {
+                        record_event_sent(category, MetricResult::Error);
+                        record_event_send_duration(category, send_started_at.elapsed());
+                        record_pipeline_error(PipelineStage::SendEvents, PipelineErrorKind::Api);
+                        return;
+                    }
+                };
+
+                if config.debug_config.skip_feature_reactions {
+                    info!("Skipping feature reactions");
+                    return;
+                }
+
+                add_feature_reactions(
+                    discord,
+                    &message,
+                    &config.voting_emojis,
+                    *SAVE_FOR_LATER_EMOJI,
+                )
+                .await;
+            }
+            .await;
+        }
+
+        // Saved as each thread is done, so a failure further on doesn't post them again
+        discord.sent_events.save().await;
+
+        if cancellation.is_cancelled() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test_log::test(tokio::test)]
+    async fn should_write_whole_vote_backup_before_closing_it() {
+        let path = std::env::temp_dir().join(format!("vote_backup_{}.json", Uuid::new_v4()));
+        let serialized_votes = "[{\"user_id\": 1}]".repeat(10_000);
+
+        write_vote_backup(
+            File::create(&path).await.unwrap(),
+            serialized_votes.as_bytes(),
+        )
+        .await
+        .unwrap();
+
+        let written = std::fs::read_to_string(&path).unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(written, serialized_votes);
+    }
+}
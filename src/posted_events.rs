@@ -0,0 +1,229 @@
+use crate::agenda_cultural::model::{Event, Price};
+use crate::config::{tags, ticket_shops};
+use chrono::NaiveDate;
+use serenity::all::ChannelId;
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+
+/// An event the bot posted, with the link to its message
+#[derive(Debug, Clone)]
+pub struct PostedEvent {
+    pub event: Event,
+    pub message_link: String,
+}
+
+/// The events of the last pass of each channel, so they can be browsed from Discord
+#[derive(Debug, Default)]
+pub struct PostedEvents {
+    by_channel: RwLock<HashMap<ChannelId, Vec<PostedEvent>>>,
+}
+
+impl PostedEvents {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Each pass lists every event of the channel, so older ones are dropped
+    pub fn replace(&self, channel_id: ChannelId, events: Vec<PostedEvent>) {
+        self.by_channel.write().unwrap().insert(channel_id, events);
+    }
+
+    /// Events matching the filter, by the first day they happen on in its range
+    pub fn find(&self, filter: &EventFilter) -> Vec<PostedEvent> {
+        let by_channel = self.by_channel.read().unwrap();
+        let mut links = HashSet::new();
+        let mut found: Vec<(NaiveDate, PostedEvent)> = by_channel
+            .values()
+            .flatten()
+            .filter(|posted_event| filter.allows(&posted_event.event))
+            .filter_map(|posted_event| {
+                filter
+                    .first_date(&posted_event.event)
+                    .map(|date| (date, posted_event.clone()))
+            })
+            .filter(|(_, posted_event)| links.insert(posted_event.event.link.clone()))
+            .collect();
+
+        found.sort_by(|(date, posted_event), (other_date, other_posted_event)| {
+            date.cmp(other_date).then_with(|| {
+                posted_event
+                    .event
+                    .title
+                    .cmp(&other_posted_event.event.title)
+            })
+        });
+
+        found
+            .into_iter()
+            .map(|(_, posted_event)| posted_event)
+            .collect()
+    }
+}
+
+/// Which posted events to browse
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventFilter {
+    pub from: NaiveDate,
+    /// Inclusive
+    pub to: Option<NaiveDate>,
+    /// Part of the venue's name, regardless of case and accents
+    pub venue: Option<String>,
+    pub tag: Option<String>,
+    pub free_only: bool,
+    pub for_children: bool,
+}
+
+impl EventFilter {
+    /// Every event from the day on
+    pub fn from(from: NaiveDate) -> Self {
+        Self {
+            from,
+            to: None,
+            venue: None,
+            tag: None,
+            free_only: false,
+            for_children: false,
+        }
+    }
+
+    fn first_date(&self, event: &Event) -> Option<NaiveDate> {
+        event.occurring_at.first_date_between(self.from, self.to)
+    }
+
+    fn allows(&self, event: &Event) -> bool {
+        let venue_matches = self.venue.as_ref().is_none_or(|venue| {
+            ticket_shops::normalize(&event.venue.name).contains(&ticket_shops::normalize(venue))
+        });
+        let tag_matches = self.tag.as_ref().is_none_or(|tag| {
+            event
+                .tags
+                .iter()
+                .any(|event_tag| tags::normalize(event_tag) == tags::normalize(tag))
+        });
+
+        venue_matches
+            && tag_matches
+            && (!self.free_only || event.price == Price::Free)
+            && (!self.for_children || event.is_for_children)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agenda_cultural::audience::Audience;
+    use crate::agenda_cultural::model::{EventDetails, Schedule, Venue};
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, month, day).unwrap()
+    }
+
+    fn build_posted_event(
+        title: &str,
+        first_date: NaiveDate,
+        last_date: NaiveDate,
+        venue: &str,
+        tags: &[&str],
+    ) -> PostedEvent {
+        let event = Event::new(
+            title.to_string(),
+            EventDetails::new(String::new(), String::new(), String::new()),
+            format!("https://example.com/{}", title),
+            Schedule::new(
+                String::new(),
+                String::new(),
+                Vec::new(),
+                Some(first_date),
+                Some(last_date),
+            ),
+            Venue::new(None, "", venue),
+            tags.iter().map(|tag| tag.to_string()).collect(),
+            Price::Unknown,
+            Audience::default(),
+        );
+
+        PostedEvent {
+            event,
+            message_link: format!("https://discord.com/channels/1/2/{}", title.len()),
+        }
+    }
+
+    fn build_posted_events() -> PostedEvents {
+        let posted_events = PostedEvents::new();
+
+        posted_events.replace(
+            ChannelId::new(1),
+            vec![
+                build_posted_event("Hamlet", date(3, 20), date(5, 2), "Teatro Ibérico", &[]),
+                build_posted_event("Medeia", date(3, 1), date(3, 2), "Culturgest", &["Estreia"]),
+            ],
+        );
+        posted_events.replace(
+            ChannelId::new(2),
+            vec![
+                build_posted_event("Hamlet", date(3, 20), date(5, 2), "Teatro Ibérico", &[]),
+                build_posted_event("Gisela", date(3, 10), date(3, 10), "CCB", &["gratuito"]),
+            ],
+        );
+        posted_events
+    }
+
+    fn titles(posted_events: Vec<PostedEvent>) -> Vec<String> {
+        posted_events
+            .into_iter()
+            .map(|posted_event| posted_event.event.title)
+            .collect()
+    }
+
+    #[test_log::test]
+    fn should_find_events_in_range_by_first_date_once() {
+        let posted_events = build_posted_events();
+
+        assert_eq!(
+            titles(posted_events.find(&EventFilter::from(date(3, 1)))),
+            ["Medeia", "Gisela", "Hamlet"]
+        );
+
+        let filter = EventFilter {
+            to: Some(date(3, 15)),
+            ..EventFilter::from(date(3, 2))
+        };
+
+        assert_eq!(titles(posted_events.find(&filter)), ["Medeia", "Gisela"]);
+    }
+
+    #[test_log::test]
+    fn should_filter_by_venue_tag_and_price() {
+        let posted_events = build_posted_events();
+        let from = EventFilter::from(date(1, 1));
+
+        let by_venue = EventFilter {
+            venue: Some("iberico".to_string()),
+            ..from.clone()
+        };
+        let by_tag = EventFilter {
+            tag: Some("estreia".to_string()),
+            ..from.clone()
+        };
+        let free_only = EventFilter {
+            free_only: true,
+            ..from.clone()
+        };
+
+        assert_eq!(titles(posted_events.find(&by_venue)), ["Hamlet"]);
+        assert_eq!(titles(posted_events.find(&by_tag)), ["Medeia"]);
+        assert_eq!(titles(posted_events.find(&free_only)), ["Gisela"]);
+    }
+
+    #[test_log::test]
+    fn when_channel_is_replaced_should_drop_its_events() {
+        let posted_events = build_posted_events();
+
+        posted_events.replace(ChannelId::new(2), Vec::new());
+
+        assert_eq!(
+            titles(posted_events.find(&EventFilter::from(date(1, 1)))),
+            ["Medeia", "Hamlet"]
+        );
+    }
+}
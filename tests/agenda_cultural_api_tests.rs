@@ -1,10 +1,12 @@
 mod agenda_cultural {
     use alertaemcena::agenda_cultural::api::AgendaCulturalAPI;
     use alertaemcena::agenda_cultural::model::{Category, Event};
+    use alertaemcena::agenda_cultural::source::EventSource;
 
     #[test_log::test(tokio::test)]
     async fn should_scrape_teatro_events() {
-        let res: Vec<Event> = AgendaCulturalAPI::get_events_by_month(&Category::Teatro, Some(5))
+        let res: Vec<Event> = AgendaCulturalAPI::default()
+            .get_events_by_month(&Category::new("teatro", "Teatro"), Some(5))
             .await
             .unwrap()
             .into_values()
@@ -16,7 +18,8 @@ mod agenda_cultural {
 
     #[test_log::test(tokio::test)]
     async fn should_scrape_artes_events() {
-        let res: Vec<Event> = AgendaCulturalAPI::get_events_by_month(&Category::Artes, Some(2))
+        let res: Vec<Event> = AgendaCulturalAPI::default()
+            .get_events_by_month(&Category::new("artes", "Artes"), Some(2))
             .await
             .unwrap()
             .into_values()
@@ -28,15 +31,540 @@ mod agenda_cultural {
 
     #[test_log::test(tokio::test)]
     async fn should_scrape_single_event_page() {
-        let event = AgendaCulturalAPI::scrape_event("https://www.agendalx.pt/events/event/maes/")
+        let event = AgendaCulturalAPI::default()
+            .scrape_event("https://www.agendalx.pt/events/event/maes/")
             .await
             .expect("Failed to scrape event");
 
         assert_eq!(event.title, "Mães");
         assert_eq!(event.link, "https://www.agendalx.pt/events/event/maes/");
-        assert_eq!(event.venue, "Teatro Villaret");
+        assert_eq!(event.venue.name, "Teatro Villaret");
         assert_eq!(event.occurring_at.dates, "14 março a 30 junho 2024");
         assert_eq!(event.details.description, "Três mães e uma grávida juntas num musical hilariante e ternurento onde ficamos a conhecer a poderosa amizade de quatro mulheres…");
-        assert_eq!(event.details.image_url, "https://www.agendalx.pt/content/uploads/2024/02/Maes.jpg");
+        assert_eq!(
+            event.details.image_url,
+            "https://www.agendalx.pt/content/uploads/2024/02/Maes.jpg"
+        );
+    }
+
+    mod stubbed {
+        use super::helpers::{StubRoute, StubServer};
+        use alertaemcena::agenda_cultural::api::{AgendaCulturalAPI, PageFetchLimits};
+        use alertaemcena::agenda_cultural::model::{Category, Event};
+        use alertaemcena::agenda_cultural::page_cache::PageCache;
+        use alertaemcena::agenda_cultural::source::EventSource;
+        use chrono::NaiveDate;
+        use reqwest_retry::policies::ExponentialBackoff;
+        use std::collections::HashSet;
+        use std::fs::read_to_string;
+        use std::time::Duration;
+        use uuid::Uuid;
+
+        fn build_api(server: &StubServer) -> AgendaCulturalAPI {
+            AgendaCulturalAPI::new(
+                &server.base_url,
+                AgendaCulturalAPI::build_client(
+                    ExponentialBackoff::builder().build_with_max_retries(0),
+                ),
+            )
+        }
+
+        fn read_events_response(base_url: &str) -> String {
+            read_to_string("res/tests/events_response.json")
+                .expect("Could not get test resource")
+                .replace("{{base_url}}", base_url)
+        }
+
+        fn event_page_routes() -> Vec<StubRoute> {
+            Vec::from([
+                StubRoute::new(
+                    "/events/event/31-mulheres/",
+                    read_to_string("res/tests/event_page.html")
+                        .expect("Could not get test resource"),
+                ),
+                StubRoute::new(
+                    "/events/event/o-que-seria-de-mim-sem-ti/",
+                    read_to_string("res/tests/event_page_with_italic_description.html")
+                        .expect("Could not get test resource"),
+                ),
+            ])
+        }
+
+        async fn start_agendalx_stub() -> StubServer {
+            StubServer::start(|base_url| {
+                let mut routes = Vec::from([StubRoute::new(
+                    "/wp-json/agendalx/v1/events",
+                    read_events_response(base_url),
+                )
+                .with_header("X-WP-TotalPages", "1")]);
+                routes.extend(event_page_routes());
+                routes
+            })
+            .await
+        }
+
+        /// Serves the recorded events split in pages of `events_per_page`, answering only
+        /// requests for `per_page` events
+        async fn start_paginated_agendalx_stub(
+            per_page: usize,
+            events_per_page: usize,
+        ) -> StubServer {
+            StubServer::start(|base_url| {
+                let events: Vec<serde_json::Value> =
+                    serde_json::from_str(&read_events_response(base_url)).unwrap();
+                let pages: Vec<&[serde_json::Value]> = events.chunks(events_per_page).collect();
+
+                let mut routes: Vec<StubRoute> = pages
+                    .iter()
+                    .enumerate()
+                    .map(|(index, page)| {
+                        StubRoute::new(
+                            &format!(
+                                "/wp-json/agendalx/v1/events?per_page={}&page={}",
+                                per_page,
+                                index + 1
+                            ),
+                            serde_json::to_string(page).unwrap(),
+                        )
+                        .with_header("X-WP-TotalPages", &pages.len().to_string())
+                    })
+                    .collect();
+                routes.extend(event_page_routes());
+                routes
+            })
+            .await
+        }
+
+        #[test_log::test(tokio::test)]
+        async fn should_get_events_by_month_from_recorded_responses() {
+            let server = start_agendalx_stub().await;
+            let api = build_api(&server);
+
+            let events = api
+                .get_events_by_month(&Category::new("teatro", "Teatro"), None)
+                .await
+                .expect("Failed to get events");
+
+            let february = NaiveDate::from_ymd_opt(2025, 2, 1).unwrap();
+            let march = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+
+            assert_eq!(
+                events.keys().cloned().collect::<Vec<NaiveDate>>(),
+                [february, march]
+            );
+
+            let titles = |events: &[Event]| {
+                events
+                    .iter()
+                    .map(|event| event.title.clone())
+                    .collect::<Vec<String>>()
+            };
+
+            assert_eq!(titles(&events[&february]), ["31 Mulheres", "Galafoice"]);
+            assert_eq!(titles(&events[&march]), ["O que seria de mim sem ti"]);
+        }
+
+        #[test_log::test(tokio::test)]
+        async fn should_scrape_full_descriptions_from_event_pages() {
+            let server = start_agendalx_stub().await;
+            let api = build_api(&server);
+
+            let events: Vec<Event> = api
+                .get_events_by_month(&Category::new("teatro", "Teatro"), None)
+                .await
+                .expect("Failed to get events")
+                .into_values()
+                .flatten()
+                .collect();
+
+            assert_eq!(
+                events[0].details.description,
+                read_to_string("res/tests/event_page_full_description.txt")
+                    .expect("Could not get test resource")
+            );
+            assert_eq!(
+                events[2].details.description,
+                read_to_string("res/tests/event_page_full_description_with_italic_description.txt")
+                    .expect("Could not get test resource")
+            );
+        }
+
+        #[test_log::test(tokio::test)]
+        async fn when_event_page_is_unavailable_should_use_preview_description() {
+            let server = start_agendalx_stub().await;
+            let api = build_api(&server);
+
+            let events: Vec<Event> = api
+                .get_events_by_month(&Category::new("teatro", "Teatro"), None)
+                .await
+                .expect("Failed to get events")
+                .into_values()
+                .flatten()
+                .collect();
+
+            assert_eq!(
+                events[1].details.description,
+                "Espetáculo inaugural de uma trilogia autobiográfica e autoficcional de João Moreira. A peça \"funciona ao mesmo tempo como *recap* do passado..."
+            );
+        }
+
+        #[test_log::test(tokio::test)]
+        async fn should_scrape_single_event_page() {
+            let server = start_agendalx_stub().await;
+            let api = build_api(&server);
+            let link = format!("{}/events/event/31-mulheres/", server.base_url);
+
+            let event = api
+                .scrape_event(&link)
+                .await
+                .expect("Failed to scrape event");
+
+            assert_eq!(event.title, "31 Mulheres");
+            assert_eq!(event.link, link);
+            assert_eq!(event.venue.name, "MAC/CCB");
+            assert_eq!(
+                event.venue.address.as_deref(),
+                Some("Praça do Império (Centro Cultural de Belém)")
+            );
+            assert_eq!(event.occurring_at.dates, "27 fevereiro a 29 junho 2025");
+            assert_eq!(
+                event.details.image_url,
+                "https://www.agendalx.pt/content/uploads/2025/01/dddd-4.jpg"
+            );
+        }
+
+        #[test_log::test(tokio::test)]
+        async fn should_follow_pages_until_the_last_one() {
+            let server = start_paginated_agendalx_stub(100, 2).await;
+            let api = build_api(&server);
+
+            let events: Vec<Event> = api
+                .get_events_by_month(&Category::new("teatro", "Teatro"), None)
+                .await
+                .expect("Failed to get events")
+                .into_values()
+                .flatten()
+                .collect();
+
+            assert_eq!(events.len(), 3);
+        }
+
+        #[test_log::test(tokio::test)]
+        async fn should_cap_events_fetched_across_pages_by_the_event_limit() {
+            let server = start_paginated_agendalx_stub(100, 2).await;
+            let api = build_api(&server);
+
+            let events: Vec<Event> = api
+                .get_events_by_month(&Category::new("teatro", "Teatro"), Some(2))
+                .await
+                .expect("Failed to get events")
+                .into_values()
+                .flatten()
+                .collect();
+
+            let titles: Vec<String> = events.iter().map(|event| event.title.clone()).collect();
+
+            assert_eq!(titles, ["31 Mulheres", "Galafoice"]);
+        }
+
+        #[test_log::test(tokio::test)]
+        async fn when_event_limit_is_past_a_page_should_fetch_full_pages() {
+            let server = StubServer::start(|base_url| {
+                let recorded_events: Vec<serde_json::Value> =
+                    serde_json::from_str(&read_events_response(base_url)).unwrap();
+                let events: Vec<serde_json::Value> = (0..250)
+                    .map(|index| {
+                        let mut event = recorded_events[0].clone();
+
+                        event["id"] = serde_json::json!(index);
+                        event["title"] =
+                            serde_json::json!({ "rendered": format!("Evento {}", index) });
+                        event["link"] =
+                            serde_json::json!(format!("{}/events/{}/", base_url, index));
+                        event
+                    })
+                    .collect();
+
+                events
+                    .chunks(100)
+                    .enumerate()
+                    .map(|(index, page)| {
+                        StubRoute::new(
+                            &format!(
+                                "/wp-json/agendalx/v1/events?per_page=100&page={}",
+                                index + 1
+                            ),
+                            serde_json::to_string(page).unwrap(),
+                        )
+                        .with_header("X-WP-TotalPages", "3")
+                    })
+                    .collect()
+            })
+            .await;
+            let api = build_api(&server).with_fetch_limits(PageFetchLimits {
+                max_concurrent_fetches: 16,
+                host_delay: Duration::ZERO,
+            });
+
+            let events: Vec<Event> = api
+                .get_events_by_month(&Category::new("teatro", "Teatro"), Some(150))
+                .await
+                .expect("Failed to get events")
+                .into_values()
+                .flatten()
+                .collect();
+            let titles: HashSet<String> = events.iter().map(|event| event.title.clone()).collect();
+
+            assert_eq!(events.len(), 150);
+            assert_eq!(titles.len(), 150);
+            assert!(titles.contains("Evento 149"));
+            assert!(server
+                .requests_to("/wp-json/agendalx/v1/events?per_page=100&page=3")
+                .is_empty());
+        }
+
+        #[test_log::test(tokio::test)]
+        async fn when_a_page_fails_should_return_error() {
+            let server = StubServer::start(|base_url| {
+                let events: Vec<serde_json::Value> =
+                    serde_json::from_str(&read_events_response(base_url)).unwrap();
+
+                Vec::from([StubRoute::new(
+                    "/wp-json/agendalx/v1/events?page=1",
+                    serde_json::to_string(&events).unwrap(),
+                )
+                .with_header("X-WP-TotalPages", "2")])
+            })
+            .await;
+            let api = build_api(&server);
+
+            let events = api
+                .get_events_by_month(&Category::new("teatro", "Teatro"), None)
+                .await;
+
+            assert!(events.is_err());
+        }
+
+        fn build_cache(ttl: Duration) -> PageCache {
+            PageCache::new(
+                std::env::temp_dir().join(format!("aec-page-cache-{}", Uuid::new_v4())),
+                ttl,
+                1024 * 1024,
+            )
+        }
+
+        #[test_log::test(tokio::test)]
+        async fn when_page_is_cached_should_not_download_it_again() {
+            let server = start_agendalx_stub().await;
+            let api = build_api(&server).with_page_cache(build_cache(Duration::from_secs(60)));
+            let link = format!("{}/events/event/31-mulheres/", server.base_url);
+
+            let first = api
+                .scrape_event(&link)
+                .await
+                .expect("Failed to scrape event");
+            let second = api
+                .scrape_event(&link)
+                .await
+                .expect("Failed to scrape event");
+
+            assert_eq!(first.details.description, second.details.description);
+            assert_eq!(server.requests_to("/events/event/31-mulheres/").len(), 1);
+        }
+
+        #[test_log::test(tokio::test)]
+        async fn when_cached_page_expired_should_revalidate_with_etag() {
+            let server = StubServer::start(|_| {
+                Vec::from([StubRoute::new(
+                    "/events/event/31-mulheres/",
+                    read_to_string("res/tests/event_page.html")
+                        .expect("Could not get test resource"),
+                )
+                .with_etag("\"31-mulheres-v1\"")])
+            })
+            .await;
+            let api = build_api(&server).with_page_cache(build_cache(Duration::ZERO));
+            let link = format!("{}/events/event/31-mulheres/", server.base_url);
+
+            api.scrape_event(&link)
+                .await
+                .expect("Failed to scrape event");
+            let event = api
+                .scrape_event(&link)
+                .await
+                .expect("Failed to scrape event");
+
+            let requests = server.requests_to("/events/event/31-mulheres/");
+
+            assert_eq!(requests.len(), 2);
+            assert!(requests[1]
+                .to_lowercase()
+                .contains("if-none-match: \"31-mulheres-v1\""));
+            assert_eq!(event.title, "31 Mulheres");
+            assert_eq!(
+                event.details.description,
+                read_to_string("res/tests/event_page_full_description.txt")
+                    .expect("Could not get test resource")
+            );
+        }
+
+        #[test_log::test(tokio::test)]
+        async fn when_events_endpoint_fails_should_return_error() {
+            let server = StubServer::start(|_| Vec::new()).await;
+            let api = build_api(&server);
+
+            let events = api
+                .get_events_by_month(&Category::new("teatro", "Teatro"), None)
+                .await;
+
+            assert!(events.is_err());
+        }
+    }
+
+    mod helpers {
+        use std::sync::{Arc, Mutex};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio::net::{TcpListener, TcpStream};
+
+        /// Minimal HTTP server answering GETs with fixed responses, and 404 for everything else.
+        pub struct StubServer {
+            pub base_url: String,
+            /// Request line and headers of every request received
+            requests: Arc<Mutex<Vec<String>>>,
+        }
+
+        #[derive(Clone)]
+        pub struct StubRoute {
+            /// A path, optionally with query parameters that requests must include
+            /// (e.g. `/events?page=2`)
+            target: String,
+            body: String,
+            headers: Vec<(String, String)>,
+            /// Answers 304 to requests sending it in `If-None-Match`
+            etag: Option<String>,
+        }
+
+        impl StubRoute {
+            pub fn new(target: &str, body: String) -> Self {
+                Self {
+                    target: target.to_string(),
+                    body,
+                    headers: Vec::new(),
+                    etag: None,
+                }
+            }
+
+            pub fn with_etag(mut self, etag: &str) -> Self {
+                self.etag = Some(etag.to_string());
+                self.with_header("ETag", etag)
+            }
+
+            pub fn with_header(mut self, name: &str, value: &str) -> Self {
+                self.headers.push((name.to_string(), value.to_string()));
+                self
+            }
+
+            fn matches(&self, request_target: &str) -> bool {
+                let (path, query) = request_target
+                    .split_once('?')
+                    .unwrap_or((request_target, ""));
+                let (route_path, route_query) =
+                    self.target.split_once('?').unwrap_or((&self.target, ""));
+
+                route_path == path
+                    && route_query
+                        .split('&')
+                        .filter(|parameter| !parameter.is_empty())
+                        .all(|parameter| query.split('&').any(|other| other == parameter))
+            }
+        }
+
+        impl StubServer {
+            pub async fn start(routes: impl FnOnce(&str) -> Vec<StubRoute>) -> Self {
+                let listener = TcpListener::bind("127.0.0.1:0")
+                    .await
+                    .expect("Failed to bind stub server");
+                let base_url = format!("http://{}", listener.local_addr().unwrap());
+                let routes = routes(&base_url);
+                let requests = Arc::new(Mutex::new(Vec::new()));
+                let received_requests = requests.clone();
+
+                tokio::spawn(async move {
+                    while let Ok((socket, _)) = listener.accept().await {
+                        let routes = routes.clone();
+                        let requests = received_requests.clone();
+
+                        tokio::spawn(async move { Self::respond(socket, &routes, requests).await });
+                    }
+                });
+
+                Self { base_url, requests }
+            }
+
+            /// Requests received for targets starting with `path`
+            pub fn requests_to(&self, path: &str) -> Vec<String> {
+                self.requests
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .filter(|request| {
+                        request
+                            .split_whitespace()
+                            .nth(1)
+                            .is_some_and(|target| target.starts_with(path))
+                    })
+                    .cloned()
+                    .collect()
+            }
+
+            async fn respond(
+                mut socket: TcpStream,
+                routes: &[StubRoute],
+                requests: Arc<Mutex<Vec<String>>>,
+            ) {
+                let mut request = Vec::new();
+                let mut buffer = [0u8; 1024];
+
+                while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                    match socket.read(&mut buffer).await {
+                        Ok(0) | Err(_) => return,
+                        Ok(read) => request.extend_from_slice(&buffer[..read]),
+                    }
+                }
+
+                let request = String::from_utf8_lossy(&request).to_string();
+                let target = request.split_whitespace().nth(1).unwrap_or_default();
+                requests.lock().unwrap().push(request.clone());
+
+                let response = match routes.iter().find(|route| route.matches(target)) {
+                    Some(route)
+                        if route.etag.as_ref().is_some_and(|etag| {
+                            request
+                                .to_lowercase()
+                                .contains(&format!("if-none-match: {}", etag.to_lowercase()))
+                        }) =>
+                    {
+                        "HTTP/1.1 304 Not Modified\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                            .to_string()
+                    }
+                    Some(route) => format!(
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n{}",
+                        route.body.len(),
+                        route
+                            .headers
+                            .iter()
+                            .map(|(name, value)| format!("{}: {}\r\n", name, value))
+                            .collect::<String>(),
+                        route.body
+                    ),
+                    None => {
+                        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                            .to_string()
+                    }
+                };
+
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        }
     }
 }
@@ -45,12 +45,11 @@ mod discord {
         let (thread_id, link, _) = send_random_event(&api, "should_read_events").await;
 
         let size = api
-            .get_event_urls_sent(thread_id)
-            .await
+            .sent_events
+            .of_thread(*channel_id, thread_id)
             .into_iter()
-            .filter(|msg| *msg == link)
-            .collect::<Vec<String>>()
-            .len();
+            .filter(|sent_event| sent_event.link == link)
+            .count();
 
         assert_eq!(size, 1);
     }
@@ -60,7 +59,7 @@ mod discord {
         let api = build_api().await;
         let (thread_id, link, _) = send_random_event(&api, "should_read_event_sent").await;
 
-        let is_event_sent = api.get_event_urls_sent(thread_id).await.contains(&link);
+        let is_event_sent = api.sent_events.is_in_thread(*channel_id, thread_id, &link);
 
         assert!(is_event_sent);
     }
@@ -72,11 +71,15 @@ mod discord {
             send_random_event(&api, "when_an_event_is_deleted_should_not_read_afterwards").await;
 
         message
-            .delete(&api.client.http)
+            .delete(&api.backend.http)
             .await
             .expect("Failed deleting event sent");
 
-        let is_event_sent = api.get_event_urls_sent(thread_id).await.contains(&link);
+        let sent_event = api.sent_events.get(*channel_id, &link).unwrap();
+
+        assert!(api.get_sent_event_message(&sent_event).await.is_none());
+
+        let is_event_sent = api.sent_events.is_in_thread(*channel_id, thread_id, &link);
 
         assert!(!is_event_sent);
     }
@@ -149,7 +152,7 @@ mod discord {
             .await;
 
         tester_api
-            .client
+            .backend
             .http
             .delete_reaction_me(
                 message.channel_id,
@@ -160,14 +163,14 @@ mod discord {
             .unwrap();
 
         message = thread_id
-            .message(&api.client.http, message.id)
+            .message(&api.backend.http, message.id)
             .await
             .unwrap();
         api.tag_save_for_later_reactions(&mut message, *SAVE_FOR_LATER_EMOJI)
             .await;
 
         let message = tester_api
-            .client
+            .backend
             .http
             .get_message(thread_id, message.id)
             .await
@@ -202,7 +205,10 @@ mod discord {
         api.delete_pin_notifications(thread_id, 1).await;
 
         let pin_notifications = thread_id
-            .messages(&api.client.http, serenity::all::GetMessages::new().limit(1))
+            .messages(
+                &api.backend.http,
+                serenity::all::GetMessages::new().limit(1),
+            )
             .await
             .expect("Failed to fetch thread messages")
             .into_iter()
@@ -271,8 +277,8 @@ mod discord {
     #[test_log::test(tokio::test)]
     async fn should_get_threads_of_only_the_specified_channel() {
         let api = build_api().await;
-        let guild = api.get_guild(*channel_id).await;
-        let active_threads = api.get_channel_threads(&guild, *channel_id).await;
+        let guild_id = api.get_guild(*channel_id).await;
+        let active_threads = api.get_channel_threads(guild_id, *channel_id).await;
 
         assert!(
             active_threads
@@ -288,14 +294,14 @@ mod discord {
         let thread_date = NaiveDate::from_ymd_opt(2021, 3, 12).unwrap();
         let thread_name = "Março 2021";
 
-        let guild = api.get_guild(*channel_id).await;
-        let active_threads = api.get_channel_threads(&guild, *channel_id).await;
+        let guild_id = api.get_guild(*channel_id).await;
+        let active_threads = api.get_channel_threads(guild_id, *channel_id).await;
 
         api.get_date_thread(&active_threads, *channel_id, thread_date)
             .await;
 
         let mut threads = api
-            .get_channel_threads(&guild, *channel_id)
+            .get_channel_threads(guild_id, *channel_id)
             .await
             .into_iter()
             .filter(|thread| thread.name == thread_name)
@@ -308,16 +314,16 @@ mod discord {
     #[test_log::test(tokio::test)]
     async fn should_not_create_duplicate_date_thread() {
         let api = build_api_without_cache().await;
-        let guild = api.get_guild(*channel_id).await;
+        let guild_id = api.get_guild(*channel_id).await;
         let thread_date = NaiveDate::from_ymd_opt(1999, 3, 12).unwrap();
         let thread_name = "Março 1999";
 
-        let active_threads = api.get_channel_threads(&guild, *channel_id).await;
+        let active_threads = api.get_channel_threads(guild_id, *channel_id).await;
         let date_thread = api
             .get_date_thread(&active_threads, *channel_id, thread_date)
             .await;
 
-        let active_threads = api.get_channel_threads(&guild, *channel_id).await;
+        let active_threads = api.get_channel_threads(guild_id, *channel_id).await;
         let second_date_thread = api
             .get_date_thread(&active_threads, *channel_id, thread_date)
             .await;
@@ -328,7 +334,7 @@ mod discord {
         );
 
         let mut threads = api
-            .get_channel_threads(&guild, *channel_id)
+            .get_channel_threads(guild_id, *channel_id)
             .await
             .into_iter()
             .filter(|thread| thread.name == thread_name)
@@ -383,7 +389,7 @@ mod discord {
 
             let events = filter_new_events_by_thread(
                 &api,
-                &api.get_guild(*channel_id).await,
+                api.get_guild(*channel_id).await,
                 events,
                 *channel_id,
             )
@@ -402,7 +408,7 @@ mod discord {
 
             let events = filter_new_events_by_thread(
                 &api,
-                &api.get_guild(*channel_id).await,
+                api.get_guild(*channel_id).await,
                 events,
                 *channel_id,
             )
@@ -435,8 +441,13 @@ mod discord {
 
     mod helpers {
         use super::{channel_id, tester_token, token};
-        use alertaemcena::agenda_cultural::model::{Event, EventDetails, Schedule};
+        use alertaemcena::agenda_cultural::audience::Audience;
+        use alertaemcena::agenda_cultural::model::{
+            Event, EventDetails, EventStatus, Price, Schedule, Venue,
+        };
+        use alertaemcena::agenda_cultural::timetable::Timetable;
         use alertaemcena::discord::api::{DiscordAPI, EventsThread};
+        use alertaemcena::discord::sent_events::SentEvent;
         use chrono::NaiveDate;
         use lazy_static::lazy_static;
         use serenity::all::{ChannelId, Message};
@@ -462,13 +473,20 @@ mod discord {
             event: Event,
             date: NaiveDate,
         ) -> (EventsThread, Message) {
-            let guild = api.get_guild(*channel_id).await;
-            let active_threads = api.get_channel_threads(&guild, *channel_id).await;
+            let guild_id = api.get_guild(*channel_id).await;
+            let active_threads = api.get_channel_threads(guild_id, *channel_id).await;
             let thread = api
                 .get_date_thread(&active_threads, *channel_id, date)
                 .await;
-            let message = api.send_event(thread.thread_id, event, None, "").await;
-            (thread, message.expect("Message should have worked!"))
+            let message = api
+                .send_event(thread.thread_id, event, None, "", false)
+                .await
+                .expect("Message should have worked!");
+
+            if let Some(sent_event) = SentEvent::from_message(&message, *channel_id, "teste") {
+                api.sent_events.record(sent_event);
+            }
+            (thread, message)
         }
 
         pub fn generate_random_event(test_name: &str) -> (String, Event, NaiveDate) {
@@ -489,13 +507,25 @@ mod discord {
                         .to_string(),
                 },
                 link: link.to_string(),
+                ticket_url: None,
                 occurring_at: Schedule {
                     dates: "21 setembro 2024 a 23 fevereiro 2025".to_string(),
                     times: "qui: 21h; sex: 21h; sáb: 21h; dom: 17h".to_string(),
+                    timetable: Timetable::parse("qui: 21h; sex: 21h; sáb: 21h; dom: 17h"),
+                    occurrences: Vec::new(),
+                    first_date: NaiveDate::from_ymd_opt(2024, 9, 21),
+                    last_date: NaiveDate::from_ymd_opt(2025, 2, 23),
                 },
-                venue: "Teatro Nacional D. Maria II, Lisboa".to_string(),
+                venue: Venue::new(
+                    Some(1),
+                    "teatro-nacional-d-maria-ii",
+                    "Teatro Nacional D. Maria II, Lisboa",
+                ),
                 tags: vec!["festival".to_string()],
-                is_for_children: false
+                is_for_children: false,
+                status: EventStatus::Scheduled,
+                price: Price::Fixed(1500),
+                audience: Audience::default(),
             };
             (link, unique_event, date)
         }
@@ -527,11 +557,11 @@ mod discord {
         }
 
         async fn cleanup_channel(api: &DiscordAPI) {
-            let guild = &api.get_guild(*channel_id).await;
+            let guild_id = api.get_guild(*channel_id).await;
 
-            for thread in api.get_channel_threads(guild, *channel_id).await {
+            for thread in api.get_channel_threads(guild_id, *channel_id).await {
                 thread
-                    .delete(&api.client.http)
+                    .delete(&api.backend.http)
                     .await
                     .expect("Failed to delete thread!");
             }
@@ -1,7 +1,7 @@
 use alertaemcena::agenda_cultural::model::Category;
 use alertaemcena::metrics::{
-    record_dm_review_rewrite, record_get_events_by_month_duration, MetricResult, PipelineErrorKind,
-    PipelineStage,
+    record_dm_review_rewrite, record_get_events_by_month_duration, CacheLookupResult, MetricResult,
+    PipelineErrorKind, PipelineStage,
 };
 use opentelemetry::KeyValue;
 use std::time::Duration;
@@ -16,11 +16,15 @@ fn should_convert_metric_enums_to_labels_using_to_string() {
 
     assert_eq!(PipelineErrorKind::Api.to_string(), "api");
     assert_eq!(PipelineErrorKind::Io.to_string(), "io");
+
+    assert_eq!(CacheLookupResult::Hit.to_string(), "hit");
+    assert_eq!(CacheLookupResult::Revalidated.to_string(), "revalidated");
+    assert_eq!(CacheLookupResult::Miss.to_string(), "miss");
 }
 
 #[test]
 fn should_convert_metric_dimensions_into_key_value() {
-    let category_kv: KeyValue = (&Category::Teatro).into();
+    let category_kv: KeyValue = (&Category::new("teatro", "Teatro")).into();
     let result_kv: KeyValue = MetricResult::Error.into();
     let stage_kv: KeyValue = PipelineStage::BackupVotes.into();
     let error_kv: KeyValue = PipelineErrorKind::Serialize.into();
@@ -40,7 +44,10 @@ fn should_convert_metric_dimensions_into_key_value() {
 
 #[test]
 fn should_record_get_events_by_month_duration_metric() {
-    record_get_events_by_month_duration(&Category::Teatro, Duration::from_millis(250));
+    record_get_events_by_month_duration(
+        &Category::new("teatro", "Teatro"),
+        Duration::from_millis(250),
+    );
 }
 
 #[test]
@@ -0,0 +1,827 @@
+use alertaemcena::agenda_cultural::api::{APIError, PageFetchLimits};
+use alertaemcena::agenda_cultural::audience::Audience;
+use alertaemcena::agenda_cultural::model::{Category, Event, EventDetails, Price, Schedule, Venue};
+use alertaemcena::agenda_cultural::source::EventSource;
+use alertaemcena::api::mark_removed_events;
+use alertaemcena::cancellation::Cancellation;
+use alertaemcena::config::model::{
+    CategoryConfig, Config, DaemonConfig, DebugConfig, EmojiConfig, FreeEventsPolicy,
+    LongRunningEventsPolicy, SharedEventsConfig,
+};
+use alertaemcena::config::tags::{TagFilter, TagLabels};
+use alertaemcena::config::ticket_shops::VenueTicketShops;
+use alertaemcena::daemon::run_daemon;
+use alertaemcena::dedup::EventOwners;
+use alertaemcena::discord::api::{is_event_reference, DiscordAPI};
+use alertaemcena::discord::backend::ChatBackend;
+use alertaemcena::discord::fake::FakeChat;
+use alertaemcena::discord::gateway::ReactionHandler;
+use alertaemcena::pipeline::{backup_votes, handle_reaction_features, run, SAVE_FOR_LATER_EMOJI};
+use alertaemcena::posted_events::{EventFilter, PostedEvents};
+use chrono::NaiveDate;
+use serenity::all::{ChannelId, EmojiId, MessageType, ReactionType};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+struct StubSource {
+    events: BTreeMap<NaiveDate, Vec<Event>>,
+}
+
+impl EventSource for StubSource {
+    async fn get_events_by_month(
+        &self,
+        _category: &Category,
+        _event_limit: Option<i32>,
+    ) -> Result<BTreeMap<NaiveDate, Vec<Event>>, APIError> {
+        Ok(self.events.clone())
+    }
+
+    async fn scrape_event(&self, _link: &str) -> Option<Event> {
+        None
+    }
+}
+
+/// Lists the events only the first time, like agendalx failing afterwards
+struct FailingAfterFirstSource {
+    source: StubSource,
+    calls: AtomicUsize,
+}
+
+impl EventSource for FailingAfterFirstSource {
+    async fn get_events_by_month(
+        &self,
+        category: &Category,
+        event_limit: Option<i32>,
+    ) -> Result<BTreeMap<NaiveDate, Vec<Event>>, APIError> {
+        match self.calls.fetch_add(1, Ordering::SeqCst) {
+            0 => self.source.get_events_by_month(category, event_limit).await,
+            _ => Ok(BTreeMap::new()),
+        }
+    }
+
+    async fn scrape_event(&self, _link: &str) -> Option<Event> {
+        None
+    }
+}
+
+/// Takes a while to list the events, like a slow agendalx
+struct SlowSource {
+    source: StubSource,
+    delay: Duration,
+}
+
+impl EventSource for SlowSource {
+    async fn get_events_by_month(
+        &self,
+        category: &Category,
+        event_limit: Option<i32>,
+    ) -> Result<BTreeMap<NaiveDate, Vec<Event>>, APIError> {
+        tokio::time::sleep(self.delay).await;
+
+        self.source.get_events_by_month(category, event_limit).await
+    }
+
+    async fn scrape_event(&self, _link: &str) -> Option<Event> {
+        None
+    }
+}
+
+fn build_event(title: &str, month: u32) -> Event {
+    let first_date = NaiveDate::from_ymd_opt(2030, month, 10);
+
+    Event::new(
+        title.to_string(),
+        EventDetails::new(
+            "Subtítulo".to_string(),
+            "Descrição".to_string(),
+            "https://example.com/image.jpg".to_string(),
+        ),
+        format!("https://example.com/events/{}", title.to_lowercase()),
+        Schedule::new(
+            "10 janeiro 2030".to_string(),
+            "21h".to_string(),
+            Vec::new(),
+            first_date,
+            first_date,
+        ),
+        Venue::new(Some(1), "teatro-iberico", "Teatro Ibérico"),
+        Vec::new(),
+        Price::Fixed(1000),
+        Audience::default(),
+    )
+}
+
+fn build_source() -> StubSource {
+    StubSource {
+        events: BTreeMap::from([
+            (
+                NaiveDate::from_ymd_opt(2030, 1, 1).unwrap(),
+                vec![build_event("Hamlet", 1), build_event("Medeia", 1)],
+            ),
+            (
+                NaiveDate::from_ymd_opt(2030, 2, 1).unwrap(),
+                vec![build_event("Antígona", 2)],
+            ),
+        ]),
+    }
+}
+
+fn voting_emojis() -> [EmojiConfig; 5] {
+    [1, 2, 3, 4, 5].map(|vote| EmojiConfig {
+        id: 100 + vote,
+        name: format!("vote_{}", vote),
+    })
+}
+
+fn vote_reaction(emoji: &EmojiConfig) -> ReactionType {
+    ReactionType::Custom {
+        animated: false,
+        id: EmojiId::new(emoji.id),
+        name: Some(emoji.name.clone()),
+    }
+}
+
+fn build_config(channel_id: ChannelId) -> Config {
+    Config {
+        debug_config: DebugConfig {
+            clear_channel: false,
+            exit_after_clearing: false,
+            skip_sending: false,
+            skip_feature_reactions: false,
+            skip_categories: Vec::new(),
+            event_limit: None,
+        },
+        categories: vec![CategoryConfig {
+            category: Category::new("teatro", "Teatro"),
+            channel_id,
+            free_events: FreeEventsPolicy::All,
+            tag_filter: TagFilter::default(),
+        }],
+        voting_emojis: voting_emojis(),
+        venue_ticket_shops: VenueTicketShops::new(),
+        ticket_shop_icon_url: String::new(),
+        gather_new_events: true,
+        page_fetch_limits: PageFetchLimits::default(),
+        page_cache: None,
+        shared_events: SharedEventsConfig::default(),
+        long_running_events: LongRunningEventsPolicy::StartMonthOnly,
+        tag_labels: TagLabels::new(),
+        daemon: None,
+    }
+}
+
+async fn run_pipeline(discord: &DiscordAPI<FakeChat>, config: &Config) {
+    run(
+        config,
+        discord,
+        &build_source(),
+        &config.categories[0],
+        &mut EventOwners::default(),
+    )
+    .await;
+}
+
+#[test_log::test(tokio::test)]
+async fn should_send_events_to_their_month_threads_once() {
+    let discord = DiscordAPI::with_backend(FakeChat::new()).await;
+    let channel_id = discord.backend.add_channel("teatro");
+    let config = build_config(channel_id);
+
+    run_pipeline(&discord, &config).await;
+    run_pipeline(&discord, &config).await;
+
+    let threads = discord.backend.threads_of(channel_id);
+    let thread_names: Vec<&str> = threads.iter().map(|thread| thread.name.as_str()).collect();
+
+    assert_eq!(thread_names, ["Janeiro 2030", "Fevereiro 2030"]);
+
+    let january_titles: Vec<String> = discord
+        .backend
+        .messages_of(threads[0].id)
+        .into_iter()
+        .filter_map(|message| message.embeds.first().and_then(|embed| embed.title.clone()))
+        .collect();
+
+    assert_eq!(january_titles.len(), 2);
+    assert!(january_titles.iter().any(|title| title.contains("Hamlet")));
+    assert!(january_titles.iter().any(|title| title.contains("Medeia")));
+
+    let event_message = &discord.backend.messages_of(threads[1].id)[0];
+
+    assert_eq!(event_message.reactions.len(), 6);
+    assert!(event_message.reactions.iter().all(|reaction| reaction.me));
+}
+
+#[test_log::test(tokio::test)]
+async fn should_rebuild_sent_events_index_from_history() {
+    let discord = DiscordAPI::with_backend(FakeChat::new()).await;
+    let channel_id = discord.backend.add_channel("teatro");
+    let config = build_config(channel_id);
+
+    run_pipeline(&discord, &config).await;
+
+    let sent_events = discord.sent_events.of_channel(channel_id);
+
+    discord.sent_events.forget_channel(channel_id);
+    run_pipeline(&discord, &config).await;
+
+    let event_messages: usize = discord
+        .backend
+        .threads_of(channel_id)
+        .iter()
+        .map(|thread| discord.backend.messages_of(thread.id).len())
+        .sum();
+
+    assert_eq!(sent_events.len(), 3);
+    assert_eq!(discord.sent_events.of_channel(channel_id), sent_events);
+    assert_eq!(event_messages, 3);
+}
+
+#[test_log::test(tokio::test)]
+async fn when_event_message_was_deleted_should_forget_and_post_it_again() {
+    let discord = DiscordAPI::with_backend(FakeChat::new()).await;
+    let channel_id = discord.backend.add_channel("teatro");
+    let config = build_config(channel_id);
+
+    run_pipeline(&discord, &config).await;
+
+    let hamlet = discord
+        .sent_events
+        .get(channel_id, "https://example.com/events/hamlet")
+        .unwrap();
+
+    discord
+        .backend
+        .delete_message(hamlet.thread_id, hamlet.message_id)
+        .await
+        .unwrap();
+
+    assert!(discord.get_sent_event_message(&hamlet).await.is_none());
+    assert!(discord
+        .sent_events
+        .get(channel_id, "https://example.com/events/hamlet")
+        .is_none());
+
+    run_pipeline(&discord, &config).await;
+
+    let reposted_hamlet = discord
+        .sent_events
+        .get(channel_id, "https://example.com/events/hamlet")
+        .unwrap();
+
+    assert_ne!(reposted_hamlet.message_id, hamlet.message_id);
+    assert_eq!(discord.backend.messages_of(hamlet.thread_id).len(), 2);
+}
+
+#[test_log::test(tokio::test)]
+async fn should_reference_ongoing_events_once_even_after_rebuilding_index() {
+    let discord = DiscordAPI::with_backend(FakeChat::new()).await;
+    let channel_id = discord.backend.add_channel("teatro");
+    let mut config = build_config(channel_id);
+    let mut hamlet = build_event("Hamlet", 1);
+
+    config.long_running_events = LongRunningEventsPolicy::CrossPost;
+    hamlet.occurring_at.last_date = NaiveDate::from_ymd_opt(2030, 2, 20);
+
+    let source = StubSource {
+        events: BTreeMap::from([(NaiveDate::from_ymd_opt(2030, 1, 1).unwrap(), vec![hamlet])]),
+    };
+
+    for run_count in 0..3 {
+        // The last run rebuilds the index from the threads' history
+        if run_count == 2 {
+            discord.sent_events.forget_channel(channel_id);
+        }
+
+        run(
+            &config,
+            &discord,
+            &source,
+            &config.categories[0],
+            &mut EventOwners::default(),
+        )
+        .await;
+    }
+
+    let february_thread = discord.backend.threads_of(channel_id).remove(1);
+    let references: Vec<_> = discord
+        .backend
+        .messages_of(february_thread.id)
+        .into_iter()
+        .filter(is_event_reference)
+        .collect();
+
+    assert_eq!(february_thread.name, "Fevereiro 2030");
+    assert_eq!(references.len(), 1);
+    assert!(discord.sent_events.is_in_thread(
+        channel_id,
+        february_thread.id,
+        "https://example.com/events/hamlet"
+    ));
+}
+
+#[test_log::test(tokio::test)]
+async fn when_owner_failed_to_fetch_events_should_not_post_them_again_once_it_succeeds() {
+    let discord = DiscordAPI::with_backend(FakeChat::new()).await;
+    let teatro_channel_id = discord.backend.add_channel("teatro");
+    let artes_channel_id = discord.backend.add_channel("artes");
+    let mut config = build_config(teatro_channel_id);
+    let failing_source = StubSource {
+        events: BTreeMap::new(),
+    };
+
+    config.categories.push(CategoryConfig {
+        category: Category::new("artes", "Artes"),
+        channel_id: artes_channel_id,
+        free_events: FreeEventsPolicy::All,
+        tag_filter: TagFilter::default(),
+    });
+
+    for teatro_source in [&failing_source, &build_source()] {
+        let mut event_owners = EventOwners::default();
+
+        run(
+            &config,
+            &discord,
+            teatro_source,
+            &config.categories[0],
+            &mut event_owners,
+        )
+        .await;
+        run(
+            &config,
+            &discord,
+            &build_source(),
+            &config.categories[1],
+            &mut event_owners,
+        )
+        .await;
+    }
+
+    let posted_events = |channel_id| {
+        discord
+            .backend
+            .threads_of(channel_id)
+            .into_iter()
+            .flat_map(|thread| discord.backend.messages_of(thread.id))
+            .filter(|message| !message.embeds.is_empty())
+            .count()
+    };
+
+    assert_eq!(posted_events(teatro_channel_id), 0);
+    assert_eq!(posted_events(artes_channel_id), 3);
+}
+
+#[test_log::test(tokio::test)]
+async fn should_edit_changed_events_found_in_index() {
+    let discord = DiscordAPI::with_backend(FakeChat::new()).await;
+    let channel_id = discord.backend.add_channel("teatro");
+    let config = build_config(channel_id);
+
+    run_pipeline(&discord, &config).await;
+
+    let mut source = build_source();
+    let hamlet = &mut source.events.values_mut().next().unwrap()[0];
+    hamlet.details.description = "Nova descrição".to_string();
+    let hamlet_link = hamlet.link.clone();
+
+    run(
+        &config,
+        &discord,
+        &source,
+        &config.categories[0],
+        &mut EventOwners::default(),
+    )
+    .await;
+
+    let sent_event = discord.sent_events.get(channel_id, &hamlet_link).unwrap();
+    let message = discord
+        .backend
+        .message(sent_event.thread_id, sent_event.message_id)
+        .await
+        .unwrap();
+
+    assert!(message.embeds[0]
+        .description
+        .as_deref()
+        .unwrap()
+        .starts_with("Nova descrição"));
+    assert_eq!(
+        Some(sent_event.fingerprint),
+        discord.event_fingerprint(
+            source.events.values().next().unwrap()[0].clone(),
+            None,
+            "",
+            false
+        )
+    );
+}
+
+#[test_log::test(tokio::test)]
+async fn when_running_event_disappears_should_mark_it_removed_in_its_start_month() {
+    let discord = DiscordAPI::with_backend(FakeChat::new()).await;
+    let channel_id = discord.backend.add_channel("teatro");
+    let config = build_config(channel_id);
+    let mut source = build_source();
+    let january_events = source.events.values_mut().next().unwrap();
+
+    january_events[0].occurring_at.last_date = NaiveDate::from_ymd_opt(2030, 3, 10);
+
+    let hamlet_link = january_events[0].link.clone();
+    let medeia_link = january_events[1].link.clone();
+
+    run(
+        &config,
+        &discord,
+        &source,
+        &config.categories[0],
+        &mut EventOwners::default(),
+    )
+    .await;
+
+    let antigona_link = source.events.values().nth(1).unwrap()[0].link.clone();
+    let removed_count = mark_removed_events(
+        &discord,
+        discord.get_guild(channel_id).await,
+        &HashSet::from([antigona_link]),
+        channel_id,
+        NaiveDate::from_ymd_opt(2030, 1, 20).unwrap(),
+        &config,
+        *SAVE_FOR_LATER_EMOJI,
+    )
+    .await;
+
+    let has_status = |link: &str| {
+        let sent_event = discord.sent_events.get(channel_id, link).unwrap();
+
+        discord
+            .backend
+            .messages_of(sent_event.thread_id)
+            .into_iter()
+            .find(|message| message.id == sent_event.message_id)
+            .is_some_and(|message| alertaemcena::discord::api::has_status(&message))
+    };
+
+    assert_eq!(removed_count, 1);
+    assert!(has_status(&hamlet_link));
+    assert!(!has_status(&medeia_link));
+}
+
+#[test_log::test(tokio::test)]
+async fn when_user_reacts_should_pin_and_send_review_in_dm() {
+    let discord = DiscordAPI::with_backend(FakeChat::new()).await;
+    let channel_id = discord.backend.add_channel("teatro");
+    let config = build_config(channel_id);
+    let user = discord.backend.add_user("espectador");
+
+    run_pipeline(&discord, &config).await;
+
+    let thread = discord.backend.threads_of(channel_id).remove(1);
+    let event_message = discord.backend.messages_of(thread.id).remove(0);
+
+    discord.backend.react_as(
+        &user,
+        thread.id,
+        event_message.id,
+        ReactionType::from(*SAVE_FOR_LATER_EMOJI),
+    );
+    discord.backend.react_as(
+        &user,
+        thread.id,
+        event_message.id,
+        vote_reaction(&config.voting_emojis[3]),
+    );
+
+    let users = handle_reaction_features(
+        &discord,
+        channel_id,
+        discord.backend.threads_of(channel_id),
+        &config.voting_emojis,
+        &Cancellation::never(),
+    )
+    .await;
+
+    assert_eq!(users, [user.id]);
+
+    let thread_messages = discord.backend.messages_of(thread.id);
+
+    assert!(thread_messages[0].pinned);
+    assert!(thread_messages
+        .iter()
+        .all(|message| message.kind != MessageType::PinsAdd));
+
+    let dm = discord.backend.dm_channel(user.id).await.unwrap();
+    let reviews: Vec<_> = discord
+        .backend
+        .messages_of(dm)
+        .into_iter()
+        .filter_map(|message| message.embeds.first().cloned())
+        .collect();
+
+    assert_eq!(reviews.len(), 1);
+    assert_eq!(reviews[0].url, event_message.embeds[0].url);
+    assert!(reviews[0]
+        .fields
+        .iter()
+        .any(|field| field.name == "Voto" && field.value == config.voting_emojis[3].to_string()));
+
+    handle_reaction_features(
+        &discord,
+        channel_id,
+        discord.backend.threads_of(channel_id),
+        &config.voting_emojis,
+        &Cancellation::never(),
+    )
+    .await;
+
+    assert_eq!(discord.backend.messages_of(dm).len(), reviews.len());
+}
+
+#[test_log::test(tokio::test)]
+async fn should_backup_votes_sent_in_dm() {
+    let discord = DiscordAPI::with_backend(FakeChat::new()).await;
+    let channel_id = discord.backend.add_channel("teatro");
+    let config = build_config(channel_id);
+    let user = discord.backend.add_user("espectador");
+    let backups_folder = std::env::temp_dir().join(format!("vote_backups_{}", Uuid::new_v4()));
+
+    run_pipeline(&discord, &config).await;
+
+    let thread = discord.backend.threads_of(channel_id).remove(0);
+    let event_message = discord.backend.messages_of(thread.id).remove(0);
+
+    discord.backend.react_as(
+        &user,
+        thread.id,
+        event_message.id,
+        vote_reaction(&config.voting_emojis[0]),
+    );
+    handle_reaction_features(
+        &discord,
+        channel_id,
+        discord.backend.threads_of(channel_id),
+        &config.voting_emojis,
+        &Cancellation::never(),
+    )
+    .await;
+
+    backup_votes(
+        &discord,
+        vec![user.id],
+        &config.voting_emojis,
+        &backups_folder,
+    )
+    .await;
+
+    let backup_file = std::fs::read_dir(&backups_folder)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    let votes: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(backup_file).unwrap()).unwrap();
+
+    std::fs::remove_dir_all(&backups_folder).unwrap();
+
+    assert_eq!(votes.as_array().unwrap().len(), 1);
+    assert_eq!(votes[0]["user_id"], user.id.to_string());
+    assert_eq!(
+        votes[0]["url"],
+        event_message.embeds[0].url.clone().unwrap()
+    );
+    assert_eq!(
+        votes[0]["user_vote"]["vote"],
+        config.voting_emojis[0].to_string()
+    );
+}
+
+#[test_log::test(tokio::test)]
+async fn daemon_should_run_both_pipelines_until_shutdown() {
+    let discord = DiscordAPI::with_backend(FakeChat::new()).await;
+    let channel_id = discord.backend.add_channel("teatro");
+    let config = build_config(channel_id);
+    let backups_folder = std::env::temp_dir().join(format!("vote_backups_{}", Uuid::new_v4()));
+    let daemon_config = DaemonConfig {
+        events_interval: Duration::from_secs(60 * 60),
+        reactions_interval: Duration::from_secs(60 * 60),
+    };
+    let posted_events = PostedEvents::new();
+
+    run_daemon(
+        &config,
+        daemon_config,
+        &discord,
+        &build_source(),
+        &posted_events,
+        &backups_folder,
+        tokio::time::sleep(Duration::from_millis(200)),
+    )
+    .await;
+
+    let backup_exists = backups_folder.exists();
+
+    std::fs::remove_dir_all(&backups_folder).unwrap();
+
+    assert_eq!(discord.backend.threads_of(channel_id).len(), 2);
+    assert!(backup_exists);
+
+    let found = posted_events.find(&EventFilter::from(
+        NaiveDate::from_ymd_opt(2030, 1, 1).unwrap(),
+    ));
+    let january_thread = discord.backend.threads_of(channel_id).remove(0);
+    let hamlet_message = discord
+        .backend
+        .messages_of(january_thread.id)
+        .into_iter()
+        .find(|message| message.embeds[0].url.as_deref() == Some(found[0].event.link.as_str()))
+        .unwrap();
+
+    assert_eq!(found.len(), 3);
+    assert_eq!(found[2].event.title, "Antígona");
+    assert!(found[0]
+        .message_link
+        .ends_with(&format!("/{}/{}", january_thread.id, hamlet_message.id)));
+}
+
+#[test_log::test(tokio::test)]
+async fn when_shut_down_mid_pass_daemon_should_stop_before_sending_events() {
+    let discord = DiscordAPI::with_backend(FakeChat::new()).await;
+    let channel_id = discord.backend.add_channel("teatro");
+    let config = build_config(channel_id);
+    let backups_folder = std::env::temp_dir().join(format!("vote_backups_{}", Uuid::new_v4()));
+    let daemon_config = DaemonConfig {
+        events_interval: Duration::from_secs(60 * 60),
+        reactions_interval: Duration::from_secs(60 * 60),
+    };
+    let source = SlowSource {
+        source: build_source(),
+        delay: Duration::from_millis(300),
+    };
+
+    run_daemon(
+        &config,
+        daemon_config,
+        &discord,
+        &source,
+        &PostedEvents::new(),
+        &backups_folder,
+        tokio::time::sleep(Duration::from_millis(100)),
+    )
+    .await;
+
+    let _ = std::fs::remove_dir_all(&backups_folder);
+
+    assert!(discord
+        .backend
+        .threads_of(channel_id)
+        .iter()
+        .all(|thread| discord.backend.messages_of(thread.id).is_empty()));
+}
+
+#[test_log::test(tokio::test)]
+async fn when_events_fail_to_be_fetched_daemon_should_keep_posted_events() {
+    let discord = DiscordAPI::with_backend(FakeChat::new()).await;
+    let channel_id = discord.backend.add_channel("teatro");
+    let mut config = build_config(channel_id);
+    let backups_folder = std::env::temp_dir().join(format!("vote_backups_{}", Uuid::new_v4()));
+    let daemon_config = DaemonConfig {
+        events_interval: Duration::from_millis(50),
+        reactions_interval: Duration::from_secs(60 * 60),
+    };
+    let source = FailingAfterFirstSource {
+        source: build_source(),
+        calls: AtomicUsize::new(0),
+    };
+    let posted_events = PostedEvents::new();
+
+    config.debug_config.skip_feature_reactions = true;
+
+    run_daemon(
+        &config,
+        daemon_config,
+        &discord,
+        &source,
+        &posted_events,
+        &backups_folder,
+        tokio::time::sleep(Duration::from_millis(300)),
+    )
+    .await;
+
+    let _ = std::fs::remove_dir_all(&backups_folder);
+
+    assert!(source.calls.load(Ordering::SeqCst) > 1);
+    assert_eq!(
+        posted_events
+            .find(&EventFilter::from(
+                NaiveDate::from_ymd_opt(2030, 1, 1).unwrap()
+            ))
+            .len(),
+        3
+    );
+}
+
+#[test_log::test(tokio::test)]
+async fn reaction_handler_should_handle_reactions_right_away() {
+    let discord = Arc::new(DiscordAPI::with_backend(FakeChat::new()).await);
+    let channel_id = discord.backend.add_channel("teatro");
+    let config = build_config(channel_id);
+    let user = discord.backend.add_user("espectador");
+    let handler = ReactionHandler::new(config.voting_emojis.clone(), *SAVE_FOR_LATER_EMOJI);
+
+    run_pipeline(&discord, &config).await;
+    handler.set_discord(discord.clone());
+
+    let thread = discord.backend.threads_of(channel_id).remove(0);
+    let event_message = discord.backend.messages_of(thread.id).remove(0);
+    let save_for_later = ReactionType::from(*SAVE_FOR_LATER_EMOJI);
+    let vote = vote_reaction(&config.voting_emojis[4]);
+
+    discord
+        .backend
+        .react_as(&user, thread.id, event_message.id, save_for_later.clone());
+    handler
+        .handle_reaction(
+            thread.id,
+            event_message.id,
+            Some(user.id),
+            Some(discord.own_user.id),
+            &save_for_later,
+            false,
+        )
+        .await;
+
+    assert!(discord.backend.messages_of(thread.id)[0].pinned);
+
+    discord
+        .backend
+        .react_as(&user, thread.id, event_message.id, vote.clone());
+    handler
+        .handle_reaction(
+            thread.id,
+            event_message.id,
+            Some(user.id),
+            Some(discord.own_user.id),
+            &vote,
+            false,
+        )
+        .await;
+
+    let dm = discord.backend.dm_channel(user.id).await.unwrap();
+
+    assert_eq!(discord.backend.messages_of(dm).len(), 1);
+
+    handler
+        .handle_reaction(
+            thread.id,
+            event_message.id,
+            Some(user.id),
+            Some(discord.own_user.id),
+            &vote,
+            true,
+        )
+        .await;
+
+    assert_eq!(discord.backend.messages_of(dm).len(), 1);
+}
+
+#[test_log::test(tokio::test)]
+async fn when_gateway_and_polling_handle_the_same_vote_should_send_one_review() {
+    let discord = Arc::new(DiscordAPI::with_backend(FakeChat::new()).await);
+    let channel_id = discord.backend.add_channel("teatro");
+    let config = build_config(channel_id);
+    let user = discord.backend.add_user("espectador");
+    let handler = ReactionHandler::new(config.voting_emojis.clone(), *SAVE_FOR_LATER_EMOJI);
+
+    run_pipeline(&discord, &config).await;
+    handler.set_discord(discord.clone());
+
+    let thread = discord.backend.threads_of(channel_id).remove(0);
+    let event_message = discord.backend.messages_of(thread.id).remove(0);
+    let vote = vote_reaction(&config.voting_emojis[4]);
+
+    discord
+        .backend
+        .react_as(&user, thread.id, event_message.id, vote.clone());
+
+    let message_lock = discord.message_locks.lock(event_message.id).await;
+    let gateway = handler.handle_reaction(
+        thread.id,
+        event_message.id,
+        Some(user.id),
+        Some(discord.own_user.id),
+        &vote,
+        false,
+    );
+    let polling = discord.send_privately_users_review(&event_message, &config.voting_emojis);
+
+    // Both wait for the lock, then race for it once released
+    tokio::join!(gateway, polling, async { drop(message_lock) });
+
+    let dm = discord.backend.dm_channel(user.id).await.unwrap();
+
+    assert_eq!(discord.backend.messages_of(dm).len(), 1);
+}